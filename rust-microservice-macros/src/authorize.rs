@@ -0,0 +1,312 @@
+//! Compile-time validation of the `authorize` expressions of the `secured` macro.
+//!
+//! The grammar mirrors the runtime parser of `rust_microservice` (module
+//! `security::authorize`), so both implementations must be kept in sync. Both
+//! parsers are tested with the expressions of `tests/authorize_expressions.txt`.
+//!
+//! ```text
+//! rule    := ROLE_ident ("," ROLE_ident)+ | or
+//! or      := and (("or" | "||") and)*
+//! and     := unary (("and" | "&&") unary)*
+//! unary   := ("not" | "!") unary | primary
+//! primary := "(" or ")" | ident "(" args? ")" | ROLE_ident
+//! args    := (ident | string) ("," (ident | string))*
+//! ```
+
+/// Outline of a parsed expression. The functions depending on the caller (roles,
+/// scopes, claims, authentication) are reduced to `Check`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Rule {
+    PermitAll,
+    DenyAll,
+    Check,
+    Not(Box<Rule>),
+    And(Box<Rule>, Box<Rule>),
+    Or(Box<Rule>, Box<Rule>),
+}
+
+impl Rule {
+    /// Returns `true` if the rule grants access to every request, authenticated
+    /// or not (e.g. `permitAll()` or `permitAll() or hasRole(ROLE_ADMIN)`).
+    pub(crate) fn permits_all(&self) -> bool {
+        self.outcome() == Some(true)
+    }
+
+    /// Returns the outcome of the rule when it doesn't depend on the caller.
+    fn outcome(&self) -> Option<bool> {
+        match self {
+            Rule::PermitAll => Some(true),
+            Rule::DenyAll => Some(false),
+            Rule::Check => None,
+            Rule::Not(rule) => rule.outcome().map(|outcome| !outcome),
+            Rule::And(left, right) => match (left.outcome(), right.outcome()) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Rule::Or(left, right) => match (left.outcome(), right.outcome()) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Lexical tokens of an authorization expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    LParen,
+    RParen,
+    Comma,
+    And,
+    Or,
+    Not,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{ident}`"),
+            Token::Str(value) => write!(f, "'{value}'"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::And => write!(f, "`and`"),
+            Token::Or => write!(f, "`or`"),
+            Token::Not => write!(f, "`not`"),
+        }
+    }
+}
+
+/// Parses an authorization expression.
+///
+/// # Returns
+/// The outline of the expression, or an error message describing the problem.
+pub(crate) fn parse(authorize: &str) -> Result<Rule, String> {
+    let tokens = tokenize(authorize)?;
+    if is_role_list(&tokens) {
+        return Ok(Rule::Check);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+
+    let rule = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {token}"));
+    }
+
+    Ok(rule)
+}
+
+/// Returns `true` if the tokens are a `ROLE_A, ROLE_B` list, a shorthand for
+/// `hasAnyRole(ROLE_A, ROLE_B)`.
+fn is_role_list(tokens: &[Token]) -> bool {
+    tokens.len() > 1
+        && !tokens.len().is_multiple_of(2)
+        && tokens.iter().enumerate().all(|(index, token)| match token {
+            Token::Ident(role) => {
+                index.is_multiple_of(2) && role.to_uppercase().starts_with("ROLE_")
+            }
+            Token::Comma => !index.is_multiple_of(2),
+            _ => false,
+        })
+}
+
+/// Splits the expression into tokens.
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            ',' => tokens.push(Token::Comma),
+            '!' => tokens.push(Token::Not),
+            '&' | '|' => {
+                if chars.next_if_eq(&c).is_none() {
+                    return Err(format!("expected `{c}{c}`"));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '\'' | '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => break,
+                        Some(next) => value.push(next),
+                        None => return Err("unterminated string".into()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(next) = chars.next_if(|n| n.is_alphanumeric() || *n == '_') {
+                    ident.push(next);
+                }
+                tokens.push(match ident.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(ident),
+                });
+            }
+            other => return Err(format!("unexpected character `{other}`")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser of authorization expressions.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Rule, String> {
+        let mut rule = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            rule = Rule::Or(Box::new(rule), Box::new(self.parse_and()?));
+        }
+        Ok(rule)
+    }
+
+    fn parse_and(&mut self) -> Result<Rule, String> {
+        let mut rule = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            rule = Rule::And(Box::new(rule), Box::new(self.parse_unary()?));
+        }
+        Ok(rule)
+    }
+
+    fn parse_unary(&mut self) -> Result<Rule, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Rule::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Rule, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let rule = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(rule),
+                    Some(token) => Err(format!("expected `)`, found {token}")),
+                    None => Err("expected `)`".into()),
+                }
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.position += 1;
+                let args = self.parse_args()?;
+                check_function(&name, args)
+            }
+            Some(Token::Ident(role)) if role.to_uppercase().starts_with("ROLE_") => Ok(Rule::Check),
+            Some(token) => Err(format!("unexpected {token}")),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<usize, String> {
+        if self.peek() == Some(&Token::RParen) {
+            self.position += 1;
+            return Ok(0);
+        }
+
+        let mut args = 0;
+        loop {
+            match self.next() {
+                Some(Token::Ident(_)) | Some(Token::Str(_)) => args += 1,
+                Some(token) => return Err(format!("unexpected {token}")),
+                None => return Err("unterminated function call".into()),
+            }
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(args),
+                Some(token) => return Err(format!("unexpected {token}")),
+                None => return Err("unterminated function call".into()),
+            }
+        }
+    }
+}
+
+/// Checks that the function exists and receives the expected number of arguments.
+fn check_function(name: &str, args: usize) -> Result<Rule, String> {
+    let arity_error = |expected: &str| {
+        Err(format!(
+            "`{name}` expects {expected}, found {args} argument(s)"
+        ))
+    };
+
+    match name.to_lowercase().as_str() {
+        "permitall" if args == 0 => Ok(Rule::PermitAll),
+        "denyall" if args == 0 => Ok(Rule::DenyAll),
+        "isauthenticated" if args == 0 => Ok(Rule::Check),
+        "permitall" | "denyall" | "isauthenticated" => arity_error("no arguments"),
+        "hasrole" | "hasscope" if args == 1 => Ok(Rule::Check),
+        "hasrole" | "hasscope" => arity_error("one argument"),
+        "hasanyrole" | "hasallroles" | "hasanyscope" if args > 0 => Ok(Rule::Check),
+        "hasanyrole" | "hasallroles" | "hasanyscope" => arity_error("at least one argument"),
+        "hasclaim" if args == 1 || args == 2 => Ok(Rule::Check),
+        "hasclaim" => arity_error("one or two arguments"),
+        _ => Err(format!("unknown function `{name}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rule, parse};
+
+    /// Expressions shared with the tests of the runtime parser.
+    const EXPRESSIONS: &str = include_str!("../tests/authorize_expressions.txt");
+
+    #[test]
+    fn should_accept_the_same_expressions_as_the_runtime_parser() {
+        for line in EXPRESSIONS
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        {
+            let (expected, expression) = line.split_once(' ').unwrap_or((line, ""));
+            assert_eq!(
+                parse(expression).is_ok(),
+                expected == "valid",
+                "unexpected result for `{expression}`"
+            );
+        }
+    }
+
+    #[test]
+    fn should_find_rules_granted_to_everyone() {
+        let permits_all = |expression: &str| parse(expression).is_ok_and(|rule| rule.permits_all());
+
+        assert!(permits_all("permitAll()"));
+        assert!(permits_all("permitAll() or hasRole(ROLE_ADMIN)"));
+        assert!(permits_all("not denyAll()"));
+        assert!(!permits_all("permitAll() and hasRole(ROLE_ADMIN)"));
+        assert!(!permits_all("isAuthenticated() or denyAll()"));
+        assert_eq!(parse("ROLE_ADMIN, ROLE_USER"), Ok(Rule::Check));
+    }
+}
@@ -348,6 +348,8 @@ use syn::{
 };
 use walkdir::DirEntry;
 
+mod authorize;
+
 /// Represents a single `key = value` pair parsed from a macro input.
 ///
 /// This structure is used when implementing procedural macros
@@ -1062,13 +1064,48 @@ fn get_arg_string_value(arg_list: &ArgList, key: String, default: String) -> Str
 /// `authorize = "ROLE_ADMIN"`
 ///
 /// 2. `hasAnyRole`: validates that at least one role in the list exists in the token.
+///    A plain list of roles is a shorthand for `hasAnyRole`.
 ///
-/// `authorize = "hasAnyRole(ROLE_ADMIN, ROLE_USER)"`
+/// `authorize = "hasAnyRole(ROLE_ADMIN, ROLE_USER)"` or `authorize = "ROLE_ADMIN, ROLE_USER"`
 ///
 /// 3. `hasAllRoles`: validates that all roles in the list exist in the token.
 ///
 /// `authorize = "hasAllRoles(ROLE_ADMIN, ROLE_USER)"`
 ///
+/// 4. `Expressions`: combines the functions below with `and` / `&&`, `or` / `||`,
+///    `not` / `!` and parentheses.
+///
+/// | Function                  | Description                                             |
+/// | ------------------------- | ------------------------------------------------------- |
+/// | `hasRole(role)`           | The user has the role.                                  |
+/// | `hasAnyRole(role, ...)`   | The user has at least one of the roles.                 |
+/// | `hasAllRoles(role, ...)`  | The user has all the roles.                             |
+/// | `hasScope(scope)`         | The token was granted the scope.                        |
+/// | `hasAnyScope(scope, ...)` | The token was granted at least one of the scopes.       |
+/// | `hasClaim(name)`          | The token has the claim.                                |
+/// | `hasClaim(name, value)`   | The claim is equal to (or, for arrays, contains) value. |
+/// | `isAuthenticated()`       | Any valid token.                                        |
+/// | `permitAll()`             | Public endpoint. A token is optional, never rejected.   |
+/// | `denyAll()`               | Always denied.                                          |
+///
+/// `authorize = "hasRole(ROLE_ADMIN) or (hasScope('read:users') and hasClaim('tenant', 'acme'))"`
+///
+/// The expression is validated at compile time: an unknown function, a wrong number
+/// of arguments or a syntax error fails the build. An expression granted to every
+/// caller (e.g. `permitAll() or hasRole(ROLE_ADMIN)`) requires no token and never
+/// rejects the request: a valid token, when sent, still exposes the authenticated
+/// user, and an invalid one is ignored. Such handlers should declare an
+/// `Option<AuthenticatedUser>` parameter, since the user is absent for anonymous
+/// calls.
+///
+/// ### **Authenticated user**
+///
+/// The validated claims are inserted into the request extensions, so the handler
+/// can declare an `AuthenticatedUser` parameter to access the subject, roles,
+/// scopes and raw claims of the token. The user is also available through
+/// `AuthenticatedUser::current()` while the handler runs, which lets the outbound
+/// HTTP clients forward or exchange its bearer token.
+///
 /// ## Examples
 ///
 /// ### **`Single role`**:
@@ -1155,6 +1192,20 @@ fn impl_secured_fn(secured_fn: ItemFn, arg_list: ArgList) -> TokenStream {
     );
     let path = get_arg_string_value(&arg_list, "path".to_string(), "".to_string()).to_lowercase();
     let authorize = get_arg_string_value(&arg_list, "authorize".to_string(), "".to_string());
+
+    // Validates the authorization expression at compile time
+    let rule = match authorize::parse(&authorize) {
+        Ok(rule) => rule,
+        Err(message) => {
+            let message = format!("invalid `authorize` expression: {message}");
+            let error = match arg_list.items.iter().find(|kv| kv.key == "authorize") {
+                Some(kv) => syn::Error::new_spanned(&kv.value, message),
+                None => syn::Error::new(Span::call_site(), "missing `authorize` expression"),
+            };
+            return error.to_compile_error().into();
+        }
+    };
+
     let _actix_web_attr = update_actix_web_attr(&secured_fn.attrs);
     let auth_module_name = format_ident!("auth_{}", fn_name);
     let wrap_fn = format!(
@@ -1163,6 +1214,46 @@ fn impl_secured_fn(secured_fn: ItemFn, arg_list: ArgList) -> TokenStream {
     )
     .to_string();
 
+    // Endpoints granted to everyone never reject the request. The credentials, when
+    // sent and valid, only expose the authenticated user.
+    let middleware_body = if rule.permits_all() {
+        quote! {
+            let user = match Server::global() {
+                Ok(server) => server
+                    .validate_jwt(&req, #authorize.to_string())
+                    .await
+                    .inspect_err(|e| tracing::debug!("Anonymous access: {}", e))
+                    .ok(),
+                Err(_) => None,
+            };
+
+            match user {
+                Some(user) => {
+                    req.extensions_mut().insert(user.clone());
+                    rust_microservice::AuthenticatedUser::scope(user, next.call(req)).await
+                }
+                None => next.call(req).await,
+            }
+        }
+    } else {
+        quote! {
+            let user = Server::global()
+                .map_err(|e| ::actix_web::error::ErrorInternalServerError(e.to_string()))?
+                .validate_jwt(&req, #authorize.to_string())
+                .await
+                .map_err(|e| {
+                    tracing::warn!("Unauthorized: {}", e);
+                    ::actix_web::Error::from(e)
+                })?;
+
+            // Exposes the authenticated user to the handler extractors and, for
+            // the duration of the handler, to the outbound HTTP clients
+            req.extensions_mut().insert(user.clone());
+
+            rust_microservice::AuthenticatedUser::scope(user, next.call(req)).await
+        }
+    };
+
     quote! {
         mod #auth_module_name {
             use ::actix_web::{
@@ -1170,7 +1261,6 @@ fn impl_secured_fn(secured_fn: ItemFn, arg_list: ArgList) -> TokenStream {
                 http::header::{self, HeaderValue}
             };
             use rust_microservice::Server;
-            use tracing::warn;
 
             pub async fn auth_middleware(
                 req: ::actix_web::dev::ServiceRequest,
@@ -1179,14 +1269,7 @@ fn impl_secured_fn(secured_fn: ItemFn, arg_list: ArgList) -> TokenStream {
                 ::actix_web::dev::ServiceResponse<impl ::actix_web::body::MessageBody>,
                 ::actix_web::Error,
             > {
-                Server::global()
-                    .map_err(|e| ::actix_web::error::ErrorInternalServerError(e.to_string()))?
-                    .validate_jwt(&req, #authorize.to_string())
-                    .map_err(|e| {
-                        //warn!("Unauthorized: {}", e);
-                        ::actix_web::error::ErrorUnauthorized("Unauthorized user.")
-                    })?;
-                next.call(req).await
+                #middleware_body
             }
         }
 
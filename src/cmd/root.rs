@@ -33,6 +33,7 @@
 //!   flags available to the root command.
 use crate::cmd::style;
 use crate::cmd::style::CURRENT;
+use crate::lifecycle::LifecycleHooks;
 use crate::settings::Settings;
 use crate::{
     Asset,
@@ -108,13 +109,15 @@ impl Cli {
     /// - `args`: Parsed CLI arguments.
     /// - `settings`: Loaded application settings.
     /// - `fnconfig`: Optional callback to configure the main Actix-Web service.
+    /// - `lifecycle`: Application hooks run at the server lifecycle transitions.
     pub(crate) async fn init(
         args: &Cli,
         settings: &Settings,
         fnconfig: Option<fn(&mut ServiceConfig)>,
+        lifecycle: &LifecycleHooks,
     ) {
         match &args.commands {
-            Commands::Run(_) => process_command(settings, fnconfig).await,
+            Commands::Run(_) => process_command(settings, fnconfig, lifecycle).await,
         }
     }
 
@@ -15,6 +15,7 @@
 //! <binary> run
 //! ```
 
+use crate::lifecycle::LifecycleHooks;
 use crate::{http::web::bootstrap_server, settings::Settings};
 use actix_web::web::ServiceConfig;
 use clap::Args;
@@ -40,6 +41,7 @@ pub(crate) struct RunArgs {}
 /// - `settings`: Reference to the server configuration.
 /// - `fnconfig`: Optional callback used to customize the Actix-Web
 ///   `ServiceConfig` during initialization.
+/// - `lifecycle`: Application hooks run at the server lifecycle transitions.
 ///
 /// # Behavior
 /// - Bootstrap the server using the settings configuration;
@@ -48,8 +50,12 @@ pub(crate) struct RunArgs {}
 ///   - Prints a confirmation message when the server is shut down.
 /// - If an error occurs:
 ///   - Prints a failure message.
-pub(crate) async fn process_command(settings: &Settings, fnconfig: Option<fn(&mut ServiceConfig)>) {
-    if let Err(error) = try_process_command(settings, fnconfig).await {
+pub(crate) async fn process_command(
+    settings: &Settings,
+    fnconfig: Option<fn(&mut ServiceConfig)>,
+    lifecycle: &LifecycleHooks,
+) {
+    if let Err(error) = try_process_command(settings, fnconfig, lifecycle).await {
         tracing::error!(
             "{} {}",
             "An unexpected error occurred on the server.".bright_red(),
@@ -72,6 +78,7 @@ pub(crate) async fn process_command(settings: &Settings, fnconfig: Option<fn(&mu
 /// # Parameters
 /// - `settings`: Reference to the application [`Settings`] used during bootstrap.
 /// - `fnconfig`: Optional function used to customize the [`ServiceConfig`].
+/// - `lifecycle`: Application hooks run at the server lifecycle transitions.
 ///
 /// # Returns
 /// - `Ok(())` if the shutdown process completes successfully.
@@ -79,8 +86,9 @@ pub(crate) async fn process_command(settings: &Settings, fnconfig: Option<fn(&mu
 async fn try_process_command(
     settings: &Settings,
     fnconfig: Option<fn(&mut ServiceConfig)>,
+    lifecycle: &LifecycleHooks,
 ) -> Result<()> {
-    if let Some(compose) = bootstrap_server(settings, fnconfig)
+    if let Some(compose) = bootstrap_server(settings, fnconfig, lifecycle)
         .await
         .map_err(|e| RunError::RunError(e.to_string()))?
     {
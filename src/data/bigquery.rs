@@ -34,13 +34,14 @@ use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use colored::Colorize;
 use google_cloud_bigquery::client::google_cloud_auth::credentials::CredentialsFile;
-use google_cloud_bigquery::client::{Client, ClientConfig, QueryError};
+use google_cloud_bigquery::client::{Client, ClientConfig};
 use google_cloud_bigquery::http::job::query::QueryRequest;
 use google_cloud_bigquery::http::table::TableReference;
 use google_cloud_bigquery::http::table::list::ListTablesRequest;
 use google_cloud_bigquery::http::types::QueryParameter;
 use google_cloud_bigquery::query::row::Row;
 use google_cloud_bigquery::query::{self, ExponentialBuilder, QueryOption};
+use std::sync::{Arc, RwLock};
 use tracing::error;
 
 use crate::data::DataError;
@@ -53,11 +54,12 @@ use crate::data::DataError;
 /// configured datasets.
 ///
 /// It is designed to be cloned and shared safely across async
-/// contexts.
+/// contexts. All clones share the same underlying client, so closing one
+/// of them closes the client for all.
 #[derive(Clone)]
 pub struct BigQueryClient {
     project_id: String,
-    client: Client,
+    client: Arc<RwLock<Option<Client>>>,
     tables: Vec<TableReference>,
 }
 
@@ -145,7 +147,7 @@ impl BigQueryClient {
 
         Ok(BigQueryClient {
             project_id,
-            client,
+            client: Arc::new(RwLock::new(Some(client))),
             tables,
         })
     }
@@ -156,7 +158,7 @@ impl BigQueryClient {
         query_parameters: Vec<QueryParameter>,
         max_results: Option<i64>,
         legacy_sql: Option<bool>,
-    ) -> Result<query::Iterator<Row>, QueryError> {
+    ) -> Result<query::Iterator<Row>, DataError> {
         let project_id = self.project_id.as_str();
         let request = QueryRequest {
             max_results,
@@ -171,15 +173,33 @@ impl BigQueryClient {
         let retry = ExponentialBuilder::default().with_max_times(10);
         let option = QueryOption::default().with_retry(retry); //.with_enable_storage_read(true);
 
-        self.client
+        let client = self
+            .client
+            .read()
+            .ok()
+            .and_then(|client| client.clone())
+            .ok_or_else(|| DataError::BigQuery("The BigQuery client is closed.".into()))?;
+
+        client
             .query_with_option::<Row>(project_id, request, option)
             .await
             .map_err(|e| {
                 error!("{}", format!("Failed to execute query: {:?}", e).red());
-                e
+                DataError::BigQuery(e.to_string())
             })
     }
 
+    /// Runs a trivial query (`SELECT 1`) to check the BigQuery connectivity.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the query succeeded, or a `DataError` otherwise.
+    pub async fn ping(&self) -> Result<(), DataError> {
+        self.query("SELECT 1", Vec::new(), Some(1), None)
+            .await
+            .map(|_| ())
+    }
+
     /// Returns the cached list of BigQuery tables.
     ///
     /// The tables are loaded during client initialization from the
@@ -192,4 +212,14 @@ impl BigQueryClient {
     pub fn get_tables(&self) -> &Vec<TableReference> {
         self.tables.as_ref()
     }
+
+    /// Closes the client by releasing its HTTP and gRPC connections.
+    ///
+    /// Queries already running complete with their own handle to the client. Any
+    /// later call to [`BigQueryClient::query`] fails.
+    pub fn close(&self) {
+        if let Ok(mut client) = self.client.write() {
+            client.take();
+        }
+    }
 }
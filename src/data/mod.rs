@@ -1,7 +1,7 @@
 //! # ServerDatabase Module
 //!
 //! This module provides a high-level abstraction for managing the application's
-//! database connections and BigQuery clients. It is responsible for initializing
+//! database connections, Redis and BigQuery clients. It is responsible for initializing
 //! and managing the connections to the underlying databases and data warehouses.
 //!
 //! The module provides a `ServerDatabase` struct which encapsulates the connections
@@ -21,12 +21,13 @@
 
 pub mod bigquery;
 pub mod database;
+pub mod redis;
 
 use colored::Colorize;
 
-use crate::settings::{BigQuery, Database, Settings};
+use crate::settings::{BigQuery, Database, Redis, Settings};
 use thiserror::Error;
-use tracing::info;
+use tracing::{info, warn};
 
 #[cfg(feature = "memory-database")]
 use std::sync::Arc;
@@ -34,7 +35,7 @@ use std::sync::Arc;
 #[cfg(feature = "memory-database")]
 use sea_orm::MockDatabase;
 
-/// Represents the application's database connections and BigQuery clients.
+/// Represents the application's database connections, Redis and BigQuery clients.
 ///
 /// This struct encapsulates the connections to the different databases and data warehouses.
 /// It provides convenience functions for initializing the connections with the provided settings.
@@ -49,6 +50,9 @@ pub struct ServerDatabase {
     /// An optional reference to the BigQuery client
     pub bigquery: Option<bigquery::BigQueryClient>,
 
+    /// An optional reference to the pooled Redis client
+    pub redis: Option<redis::RedisClient>,
+
     /// A vector of database clients, each representing a connection to a different database
     pub databases: Vec<database::DatabaseClient>,
 }
@@ -71,6 +75,7 @@ impl ServerDatabase {
     pub fn new_with_mock_database(name: String, database: MockDatabase) -> Self {
         ServerDatabase {
             bigquery: None,
+            redis: None,
             databases: vec![database::DatabaseClient {
                 name,
                 connection: Arc::new(database.into_connection()),
@@ -100,6 +105,7 @@ impl ServerDatabase {
 
         Ok(ServerDatabase {
             bigquery: None,
+            redis: None,
             databases: vec![database],
         })
     }
@@ -134,6 +140,14 @@ impl ServerDatabase {
             _ => None,
         };
 
+        // Initialize Redis client
+        let redis = match data.and_then(|d| d.redis.as_ref()) {
+            Some(cfg) if is_enabled(cfg.enabled) => {
+                Some(ServerDatabase::create_redis_client(cfg).await?)
+            }
+            _ => None,
+        };
+
         // Initialize Database clients
         let mut databases = Vec::new();
         if let Some(configs) = data.and_then(|d| d.databases.as_ref()) {
@@ -149,6 +163,7 @@ impl ServerDatabase {
 
         Ok(ServerDatabase {
             bigquery,
+            redis,
             databases,
         })
     }
@@ -197,6 +212,35 @@ impl ServerDatabase {
         Ok(client)
     }
 
+    /// Creates a new pooled Redis client based on the provided configuration.
+    ///
+    /// # Parameters
+    ///
+    /// - `settings`: A reference to the Redis configuration.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a new `RedisClient` instance, or a `DataError` if the
+    /// client cannot be initialized or the Redis server does not answer a `PING`.
+    async fn create_redis_client(settings: &Redis) -> Result<redis::RedisClient> {
+        info!("Setting up Redis client...");
+
+        let client = redis::RedisClient::new(settings).await?;
+
+        client
+            .ping()
+            .await
+            .map_err(|e| DataError::Redis(e.to_string().red().to_string()))?;
+
+        info!(
+            "Redis client initialized on {}:{}.",
+            settings.host.clone().unwrap_or("localhost".into()).blue(),
+            settings.port.unwrap_or(6379).to_string().blue()
+        );
+
+        Ok(client)
+    }
+
     /// Prints the mapped tables when the BigQuery database has been
     /// successfully initialized.
     ///
@@ -314,37 +358,30 @@ impl ServerDatabase {
         Ok(())
     }
 
-    /// Closes all database connections in the server database client.
+    /// Closes the Redis and BigQuery clients and all database connections.
     ///
-    /// This method is called when the server is shutting down
-    /// and all database connections must be closed to prevent resource
-    /// leaks.
-    #[cfg(feature = "memory-database")]
-    pub fn close(&self) {
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
-        rt.block_on(async {
-            for database in self.databases.iter() {
-                let db = database.clone();
-                if let Ok(conn) = Arc::try_unwrap(db.connection) {
-                    let _ = conn.close().await;
-                }
-            }
-        });
-    }
+    /// This method is called when the server is shutting down, after the
+    /// in-flight requests completed and the shutdown hooks ran, so that all
+    /// connections are released to prevent resource leaks.
+    pub async fn close(&self) {
+        if let Some(redis) = &self.redis {
+            redis.close();
+        }
+
+        if let Some(bigquery) = &self.bigquery {
+            bigquery.close();
+        }
 
-    /// Closes all database connections in the server database client.
-    ///
-    /// This method is called when the server is shutting down
-    /// and all database connections must be closed to prevent resource
-    /// leaks.
-    #[cfg(not(feature = "memory-database"))]
-    pub fn close(&self) {
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
-        rt.block_on(async {
-            for database in self.databases.iter() {
-                let _ = database.connection.clone().close().await;
+        for database in &self.databases {
+            if let Err(e) = database.connection.close_by_ref().await {
+                warn!(
+                    "Failed to close the database connection [{}]: {}",
+                    database.name, e
+                );
             }
-        });
+        }
+
+        info!("Data sources closed.");
     }
 }
 
@@ -360,6 +397,7 @@ pub type Result<T, E = DataError> = std::result::Result<T, E>;
 /// # Variants
 /// - `Configuration`: Invalid database configuration.
 /// - `BigQuery`: An error occurred while interacting with BigQuery.
+/// - `Redis`: An error occurred while interacting with Redis.
 /// - `Database`: An error occurred while interacting with the database.
 #[derive(Debug, Error)]
 pub enum DataError {
@@ -369,6 +407,9 @@ pub enum DataError {
     #[error("BigQuery error: {0}")]
     BigQuery(String),
 
+    #[error("Redis error: {0}")]
+    Redis(String),
+
     #[error("Database error: {0}")]
     Database(String),
 }
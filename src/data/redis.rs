@@ -0,0 +1,317 @@
+//! # Redis Module
+//!
+//! This module provides the Redis integration layer for the application. It is
+//! responsible for creating a pooled, asynchronous Redis client from the `data.redis`
+//! configuration section and exposing it to repositories and services.
+//!
+//! ## Responsibilities
+//!
+//! - Build the Redis connection information from the server settings.
+//! - Manage a pool of multiplexed asynchronous connections using `bb8`.
+//! - Validate the connectivity with the Redis server (`PING`).
+//! - Release the pooled connections when the server shuts down.
+//!
+//! ## Usage
+//!
+//! The client is initialized by the `ServerDatabase` during the server bootstrap and
+//! can be retrieved through the global server instance:
+//!
+//! ```rust,ignore
+//! use rust_microservice::Server;
+//!
+//! let redis = Server::global()?.redis().ok_or(MyError::RedisNotConfigured)?;
+//! let mut connection = redis.get().await?;
+//! let _: () = redis::cmd("SET").arg("key").arg("value").query_async(&mut *connection).await?;
+//! ```
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use ::redis::aio::MultiplexedConnection;
+use ::redis::{
+    Client, ConnectionAddr, ConnectionInfo, ProtocolVersion, RedisConnectionInfo, RedisError,
+};
+use bb8::{ManageConnection, Pool, PooledConnection};
+use colored::Colorize;
+use tracing::debug;
+
+use crate::data::DataError;
+use crate::settings::Redis;
+
+/// Default Redis server host.
+const DEFAULT_HOST: &str = "localhost";
+
+/// Default Redis server port.
+const DEFAULT_PORT: u16 = 6379;
+
+/// Default maximum number of active connections in the pool.
+const DEFAULT_MAX_ACTIVE: u32 = 8;
+
+/// Pool options resolved from the Redis settings.
+#[derive(Debug, PartialEq)]
+struct PoolOptions {
+    max_size: u32,
+    min_idle: Option<u32>,
+    connection_timeout: Option<Duration>,
+}
+
+/// Resolves the pool options from the Redis settings.
+///
+/// # Errors
+///
+/// Returns `DataError::Configuration` if `max-active` is zero or too large, or if
+/// `connect-timeout` is zero. `bb8` rejects these values with a panic.
+fn pool_options(settings: &Redis) -> Result<PoolOptions, DataError> {
+    let pool_settings = settings.lettuce.as_ref().and_then(|l| l.pool.as_ref());
+
+    let (max_size, min_idle) = if pool_settings.and_then(|p| p.enabled).unwrap_or(true) {
+        let max_size = match pool_settings.and_then(|p| p.max_active) {
+            Some(value) => u32::try_from(value)
+                .ok()
+                .filter(|value| *value > 0)
+                .ok_or_else(|| {
+                    DataError::Configuration(format!(
+                        "Invalid Redis max-active: {value}. Expected a value between 1 and {}.",
+                        u32::MAX
+                    ))
+                })?,
+            None => DEFAULT_MAX_ACTIVE,
+        };
+        let min_idle = pool_settings
+            .and_then(|p| p.min_idle)
+            .map(|value| u32::try_from(value).unwrap_or(u32::MAX).min(max_size));
+        (max_size, min_idle)
+    } else {
+        (1, None)
+    };
+
+    let connection_timeout = match settings.connect_timeout {
+        Some(0) => {
+            return Err(DataError::Configuration(
+                "Invalid Redis connect-timeout: 0. Expected at least 1 second.".into(),
+            ));
+        }
+        timeout => timeout.map(Duration::from_secs),
+    };
+
+    Ok(PoolOptions {
+        max_size,
+        min_idle,
+        connection_timeout,
+    })
+}
+
+/// Connection manager used by the `bb8` pool to create and validate
+/// multiplexed Redis connections.
+pub struct RedisConnectionManager {
+    client: Client,
+}
+
+impl ManageConnection for RedisConnectionManager {
+    type Connection = MultiplexedConnection;
+    type Error = RedisError;
+
+    /// Opens a new multiplexed connection with the Redis server.
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.client.get_multiplexed_async_connection().await
+    }
+
+    /// Validates the connection by sending a `PING` command.
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        ::redis::cmd("PING").query_async::<String>(conn).await?;
+        Ok(())
+    }
+
+    /// Multiplexed connections reconnect on demand and are never flagged as broken.
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// A pooled Redis connection borrowed from the [`RedisClient`].
+pub type RedisConnection = PooledConnection<'static, RedisConnectionManager>;
+
+/// Redis client abstraction responsible for managing a pool of asynchronous
+/// connections to the configured Redis server.
+///
+/// It is designed to be cloned and shared safely across async contexts. All clones
+/// share the same underlying pool, so closing one of them closes the pool for all.
+#[derive(Clone)]
+pub struct RedisClient {
+    pool: Arc<RwLock<Option<Pool<RedisConnectionManager>>>>,
+}
+
+impl RedisClient {
+    /// Creates a new Redis client with a connection pool configured from the
+    /// provided settings.
+    ///
+    /// # Parameters
+    ///
+    /// - `settings`: A reference to the Redis configuration.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the new `RedisClient`, or a `DataError` if the
+    /// pool settings are invalid or the pool cannot be created.
+    ///
+    /// # Notes
+    ///
+    /// - When `lettuce.pool.enabled` is `false`, the pool holds a single connection.
+    /// - `max-active` sets the pool size and `min-idle` the number of idle connections
+    ///   kept open. Idle connections above `min-idle` are released after the pool idle
+    ///   timeout, so `max-idle` is not enforced separately.
+    pub async fn new(settings: &Redis) -> Result<Self, DataError> {
+        let info = ConnectionInfo {
+            addr: ConnectionAddr::Tcp(
+                settings
+                    .host
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_HOST.to_string()),
+                settings.port.unwrap_or(DEFAULT_PORT),
+            ),
+            redis: RedisConnectionInfo {
+                db: settings.database.unwrap_or(0),
+                username: settings.username.clone(),
+                password: settings.password.clone(),
+                protocol: ProtocolVersion::RESP2,
+            },
+        };
+
+        let client = Client::open(info)
+            .map_err(|e| DataError::Configuration(e.to_string().red().to_string()))?;
+
+        let options = pool_options(settings)?;
+        debug!(
+            "Redis pool configuration: max-size={}, min-idle={:?}",
+            options.max_size, options.min_idle
+        );
+
+        let mut builder = Pool::builder()
+            .max_size(options.max_size)
+            .min_idle(options.min_idle);
+        if let Some(timeout) = options.connection_timeout {
+            builder = builder.connection_timeout(timeout);
+        }
+
+        let pool = builder
+            .build(RedisConnectionManager { client })
+            .await
+            .map_err(|e| DataError::Redis(e.to_string().red().to_string()))?;
+
+        Ok(RedisClient {
+            pool: Arc::new(RwLock::new(Some(pool))),
+        })
+    }
+
+    /// Returns the underlying pool, or an error if the client has been closed.
+    fn pool(&self) -> Result<Pool<RedisConnectionManager>, DataError> {
+        self.pool
+            .read()
+            .map_err(|e| DataError::Redis(e.to_string()))?
+            .clone()
+            .ok_or_else(|| DataError::Redis("Redis client is closed.".into()))
+    }
+
+    /// Borrows a connection from the pool.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the pooled connection, or a `DataError` if the
+    /// client is closed or no connection could be acquired.
+    pub async fn get(&self) -> Result<RedisConnection, DataError> {
+        self.pool()?
+            .get_owned()
+            .await
+            .map_err(|e| DataError::Redis(e.to_string()))
+    }
+
+    /// Sends a `PING` command to the Redis server.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the server answered, or a `DataError` otherwise.
+    pub async fn ping(&self) -> Result<(), DataError> {
+        let mut connection = self.get().await?;
+
+        ::redis::cmd("PING")
+            .query_async::<String>(&mut *connection)
+            .await
+            .map_err(|e| DataError::Redis(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Closes the client by releasing the connection pool.
+    ///
+    /// Idle connections are dropped immediately and connections currently borrowed
+    /// are dropped when returned. Any later call to [`RedisClient::get`] fails.
+    pub fn close(&self) {
+        if let Ok(mut pool) = self.pool.write() {
+            pool.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::{DEFAULT_MAX_ACTIVE, PoolOptions, pool_options};
+    use crate::settings::Redis;
+
+    /// Deserializes the Redis settings of a test.
+    fn redis(value: serde_json::Value) -> Redis {
+        serde_json::from_value(value).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn should_resolve_pool_options_from_settings() {
+        assert_eq!(
+            pool_options(&redis(json!({}))).ok(),
+            Some(PoolOptions {
+                max_size: DEFAULT_MAX_ACTIVE,
+                min_idle: None,
+                connection_timeout: None,
+            })
+        );
+        assert_eq!(
+            pool_options(&redis(json!({
+                "connect-timeout": 5,
+                "lettuce": { "pool": { "max-active": 4, "min-idle": 10 } }
+            })))
+            .ok(),
+            Some(PoolOptions {
+                max_size: 4,
+                min_idle: Some(4),
+                connection_timeout: Some(Duration::from_secs(5)),
+            })
+        );
+        assert_eq!(
+            pool_options(&redis(json!({
+                "lettuce": { "pool": { "enabled": false, "max-active": 0 } }
+            })))
+            .map(|options| options.max_size)
+            .ok(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn should_reject_pool_options_rejected_by_bb8() {
+        assert!(
+            pool_options(&redis(
+                json!({ "lettuce": { "pool": { "max-active": 0 } } })
+            ))
+            .is_err()
+        );
+        assert!(
+            pool_options(&redis(
+                json!({ "lettuce": { "pool": { "max-active": u64::from(u32::MAX) + 1 } } })
+            ))
+            .is_err()
+        );
+        assert!(pool_options(&redis(json!({ "connect-timeout": 0 }))).is_err());
+    }
+}
@@ -0,0 +1,597 @@
+//! # Health Module
+//!
+//! This module defines the [`HealthIndicator`] trait, the built-in indicators
+//! aggregated by the `/actuator/health` endpoint and the health groups served by
+//! `/actuator/health/{group}`.
+//!
+//! ## Built-in Indicators
+//!
+//! | Component         | Check                                                     |
+//! | ----------------- | --------------------------------------------------------- |
+//! | `db.<name>`       | Pings each relational database connection.                |
+//! | `bigquery`        | Runs a `SELECT 1` query.                                  |
+//! | `redis`           | Sends a `PING` command.                                   |
+//! | `disk-space`      | Compares the free disk space with the `threshold`.        |
+//! | `liveness-state`  | `DOWN` once the servers are stopped.                      |
+//! | `readiness-state` | `OUT_OF_SERVICE` while starting and draining.             |
+//! | `startup-state`   | `OUT_OF_SERVICE` until the startup hooks and servers run. |
+//!
+//! Applications register their own indicators with `Server::with_health_indicator`.
+//!
+//! ## Groups
+//!
+//! A group aggregates the components matching its `include` patterns, where a
+//! trailing `*` matches a prefix. The `liveness`, `readiness` and `startup` groups
+//! are always available and can be overridden in the `health.groups` settings.
+//!
+//! ## Aggregation
+//!
+//! The indicators run concurrently, each one bounded by its timeout. The aggregated
+//! status is the most severe component status, in the order `DOWN`,
+//! `OUT_OF_SERVICE`, `UP` and `UNKNOWN`. Without any component, the status is `UP`.
+//! The component details are only reported with `show-details: always`:
+//!
+//! ```json
+//! {
+//!   "status": "UP",
+//!   "components": {
+//!     "db.primary": { "status": "UP", "details": { "database": "Postgres" } },
+//!     "disk-space": { "status": "UP", "details": { "free": 52349853696, "threshold": 10485760 } }
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sysinfo::Disks;
+use utoipa::ToSchema;
+
+use crate::data::{bigquery::BigQueryClient, database::DatabaseClient, redis::RedisClient};
+use crate::lifecycle::{self, LifecycleState};
+use crate::server::LocalBoxFuture;
+use crate::settings::{self, Settings};
+
+/// Default timeout (in milliseconds) of each health indicator.
+const DEFAULT_TIMEOUT: u64 = 2000;
+
+/// Default minimum free disk space (in bytes) of the disk space indicator.
+const DEFAULT_DISK_THRESHOLD: u64 = 10 * 1024 * 1024;
+
+/// Default health groups, with their included components.
+const DEFAULT_GROUPS: [(&str, &[&str]); 3] = [
+    ("liveness", &["liveness-state"]),
+    (
+        "readiness",
+        &["readiness-state", "db.*", "bigquery", "redis"],
+    ),
+    ("startup", &["startup-state"]),
+];
+
+/// Status of a health component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HealthStatus {
+    Up,
+    Down,
+    OutOfService,
+    Unknown,
+}
+
+impl HealthStatus {
+    /// Severity used to aggregate the statuses, the highest one wins.
+    fn severity(&self) -> u8 {
+        match self {
+            HealthStatus::Unknown => 0,
+            HealthStatus::Up => 1,
+            HealthStatus::OutOfService => 2,
+            HealthStatus::Down => 3,
+        }
+    }
+
+    /// Returns `true` if the status should be served with HTTP 503.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, HealthStatus::Down | HealthStatus::OutOfService)
+    }
+}
+
+/// Health of a component, with optional details.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Health {
+    pub status: HealthStatus,
+
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    #[schema(value_type = Object)]
+    pub details: Map<String, Value>,
+}
+
+impl Health {
+    /// Creates a health with the given status and no details.
+    pub fn status(status: HealthStatus) -> Self {
+        Health {
+            status,
+            details: Map::new(),
+        }
+    }
+
+    /// Creates an `UP` health.
+    pub fn up() -> Self {
+        Health::status(HealthStatus::Up)
+    }
+
+    /// Creates a `DOWN` health.
+    pub fn down() -> Self {
+        Health::status(HealthStatus::Down)
+    }
+
+    /// Adds a detail to the health.
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A component whose health is reported by the `/actuator/health` endpoint.
+///
+/// # Example
+///
+/// ```rust
+/// use rust_microservice::{Health, HealthIndicator, LocalBoxFuture};
+///
+/// struct QueueHealthIndicator;
+///
+/// impl HealthIndicator for QueueHealthIndicator {
+///     fn name(&self) -> &str {
+///         "queue"
+///     }
+///
+///     fn health(&self) -> LocalBoxFuture<'_, Health> {
+///         Box::pin(async { Health::up().with_detail("pending", 0) })
+///     }
+/// }
+/// ```
+pub trait HealthIndicator: Send + Sync {
+    /// Name of the component in the health response.
+    fn name(&self) -> &str;
+
+    /// Checks the health of the component.
+    fn health(&self) -> LocalBoxFuture<'_, Health>;
+}
+
+/// Aggregated health returned by the `/actuator/health` endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct HealthReport {
+    pub status: HealthStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<BTreeMap<String, Health>>,
+}
+
+/// Runs the enabled indicators concurrently and aggregates their health.
+///
+/// Each indicator is bounded by its timeout (`health.indicators.<name>.timeout`,
+/// then `health.timeout`) and reported `DOWN` when exceeding it. The components
+/// and their details are included according to `health.show-details`.
+pub(crate) async fn check(
+    indicators: &[Arc<dyn HealthIndicator>],
+    settings: Option<&settings::Health>,
+) -> HealthReport {
+    let show_details = settings.and_then(|s| s.show_details.as_deref());
+    report(indicators, settings, show_details).await
+}
+
+/// Checks the components of a health group.
+///
+/// The group is looked up in `health.groups`, then in the default groups. Its
+/// `show-details` overrides `health.show-details`. Returns `None` for an unknown
+/// group.
+pub(crate) async fn check_group(
+    indicators: &[Arc<dyn HealthIndicator>],
+    settings: Option<&settings::Health>,
+    group: &str,
+) -> Option<HealthReport> {
+    let configured = settings
+        .and_then(|s| s.groups.as_ref())
+        .and_then(|groups| groups.get(group));
+
+    let include: Vec<&str> = match configured {
+        Some(configured) => configured
+            .include
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect(),
+        None => DEFAULT_GROUPS
+            .iter()
+            .find(|(name, _)| *name == group)
+            .map(|(_, include)| include.to_vec())?,
+    };
+
+    let members = indicators
+        .iter()
+        .filter(|indicator| {
+            include
+                .iter()
+                .any(|pattern| matches_pattern(pattern, indicator.name()))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let show_details = configured
+        .and_then(|g| g.show_details.as_deref())
+        .or_else(|| settings.and_then(|s| s.show_details.as_deref()));
+
+    Some(report(&members, settings, show_details).await)
+}
+
+/// Returns `true` if the component name matches an exact or `prefix*` pattern.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// Runs the indicators and builds the report with the given details visibility.
+async fn report(
+    indicators: &[Arc<dyn HealthIndicator>],
+    settings: Option<&settings::Health>,
+    show_details: Option<&str>,
+) -> HealthReport {
+    let default_timeout = settings.and_then(|s| s.timeout).unwrap_or(DEFAULT_TIMEOUT);
+    let indicator_settings = |name: &str| {
+        settings
+            .and_then(|s| s.indicators.as_ref())
+            .and_then(|indicators| indicators.get(name))
+    };
+
+    let checks = indicators
+        .iter()
+        .filter(|indicator| {
+            indicator_settings(indicator.name())
+                .and_then(|s| s.enabled)
+                .unwrap_or(true)
+        })
+        .map(|indicator| async move {
+            let timeout = indicator_settings(indicator.name())
+                .and_then(|s| s.timeout)
+                .unwrap_or(default_timeout);
+
+            let health = tokio::time::timeout(Duration::from_millis(timeout), indicator.health())
+                .await
+                .unwrap_or_else(|_| {
+                    Health::down().with_detail("error", format!("Timed out after {timeout} ms."))
+                });
+
+            (indicator.name().to_string(), health)
+        });
+    let components = join_all(checks)
+        .await
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+
+    let status = aggregate(components.values().map(|health| health.status));
+    let components = match show_details {
+        Some("never") => None,
+        Some("always") => Some(components),
+        _ => Some(
+            components
+                .into_iter()
+                .map(|(name, health)| (name, Health::status(health.status)))
+                .collect(),
+        ),
+    };
+
+    HealthReport { status, components }
+}
+
+/// Returns the most severe status, or `UP` when there is no status.
+fn aggregate(statuses: impl Iterator<Item = HealthStatus>) -> HealthStatus {
+    statuses
+        .max_by_key(HealthStatus::severity)
+        .unwrap_or(HealthStatus::Up)
+}
+
+/// Creates the built-in indicators of the configured data sources and the disk space.
+pub(crate) fn builtin_indicators(
+    settings: Option<&Settings>,
+    databases: &[DatabaseClient],
+    bigquery: Option<&BigQueryClient>,
+    redis: Option<&RedisClient>,
+) -> Vec<Arc<dyn HealthIndicator>> {
+    let mut indicators: Vec<Arc<dyn HealthIndicator>> = vec![
+        Arc::new(LifecycleHealthIndicator::Liveness),
+        Arc::new(LifecycleHealthIndicator::Readiness),
+        Arc::new(LifecycleHealthIndicator::Startup),
+    ];
+
+    for database in databases {
+        indicators.push(Arc::new(DatabaseHealthIndicator {
+            name: format!("db.{}", database.name),
+            client: database.clone(),
+        }));
+    }
+
+    if let Some(client) = bigquery {
+        indicators.push(Arc::new(BigQueryHealthIndicator {
+            client: client.clone(),
+        }));
+    }
+
+    if let Some(client) = redis {
+        indicators.push(Arc::new(RedisHealthIndicator {
+            client: client.clone(),
+        }));
+    }
+
+    let disk_space = settings
+        .and_then(|s| s.health.as_ref())
+        .and_then(|h| h.disk_space.as_ref());
+    if disk_space.and_then(|d| d.enabled).unwrap_or(true) {
+        indicators.push(Arc::new(DiskSpaceHealthIndicator {
+            path: PathBuf::from(disk_space.and_then(|d| d.path.as_deref()).unwrap_or(".")),
+            threshold: disk_space
+                .and_then(|d| d.threshold)
+                .unwrap_or(DEFAULT_DISK_THRESHOLD),
+        }));
+    }
+
+    indicators
+}
+
+/// Reports the lifecycle state of the server for the Kubernetes probes.
+enum LifecycleHealthIndicator {
+    Liveness,
+    Readiness,
+    Startup,
+}
+
+impl HealthIndicator for LifecycleHealthIndicator {
+    fn name(&self) -> &str {
+        match self {
+            LifecycleHealthIndicator::Liveness => "liveness-state",
+            LifecycleHealthIndicator::Readiness => "readiness-state",
+            LifecycleHealthIndicator::Startup => "startup-state",
+        }
+    }
+
+    fn health(&self) -> LocalBoxFuture<'_, Health> {
+        let state = lifecycle::state();
+        let status = match (self, state) {
+            (LifecycleHealthIndicator::Liveness, LifecycleState::Stopped) => HealthStatus::Down,
+            (LifecycleHealthIndicator::Readiness, LifecycleState::Ready) => HealthStatus::Up,
+            (LifecycleHealthIndicator::Readiness, _) => HealthStatus::OutOfService,
+            (LifecycleHealthIndicator::Startup, LifecycleState::Starting) => {
+                HealthStatus::OutOfService
+            }
+            _ => HealthStatus::Up,
+        };
+
+        Box::pin(async move { Health::status(status).with_detail("state", format!("{state:?}")) })
+    }
+}
+
+/// Pings a relational database connection.
+struct DatabaseHealthIndicator {
+    name: String,
+    client: DatabaseClient,
+}
+
+impl HealthIndicator for DatabaseHealthIndicator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn health(&self) -> LocalBoxFuture<'_, Health> {
+        Box::pin(async move {
+            let backend = format!("{:?}", self.client.connection.get_database_backend());
+            match self.client.connection.ping().await {
+                Ok(_) => Health::up().with_detail("database", backend),
+                Err(e) => Health::down()
+                    .with_detail("database", backend)
+                    .with_detail("error", e.to_string()),
+            }
+        })
+    }
+}
+
+/// Runs a trivial BigQuery query.
+struct BigQueryHealthIndicator {
+    client: BigQueryClient,
+}
+
+impl HealthIndicator for BigQueryHealthIndicator {
+    fn name(&self) -> &str {
+        "bigquery"
+    }
+
+    fn health(&self) -> LocalBoxFuture<'_, Health> {
+        Box::pin(async move {
+            match self.client.ping().await {
+                Ok(_) => Health::up(),
+                Err(e) => Health::down().with_detail("error", e.to_string()),
+            }
+        })
+    }
+}
+
+/// Pings the Redis server.
+struct RedisHealthIndicator {
+    client: RedisClient,
+}
+
+impl HealthIndicator for RedisHealthIndicator {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    fn health(&self) -> LocalBoxFuture<'_, Health> {
+        Box::pin(async move {
+            match self.client.ping().await {
+                Ok(_) => Health::up(),
+                Err(e) => Health::down().with_detail("error", e.to_string()),
+            }
+        })
+    }
+}
+
+/// Checks the free space of the file system holding a path.
+struct DiskSpaceHealthIndicator {
+    path: PathBuf,
+    threshold: u64,
+}
+
+impl HealthIndicator for DiskSpaceHealthIndicator {
+    fn name(&self) -> &str {
+        "disk-space"
+    }
+
+    fn health(&self) -> LocalBoxFuture<'_, Health> {
+        Box::pin(async move {
+            // Reading the mounted disks is blocking file system work
+            let path = self.path.clone();
+            let threshold = self.threshold;
+            tokio::task::spawn_blocking(move || disk_space(&path, threshold))
+                .await
+                .unwrap_or_else(|e| Health::down().with_detail("error", e.to_string()))
+        })
+    }
+}
+
+/// Compares the free space of the file system holding the path with the threshold.
+fn disk_space(path: &Path, threshold: u64) -> Health {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    // The file system holding the path is the disk with the longest mount point
+    let disks = Disks::new_with_refreshed_list();
+    let Some(disk) = disks
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return Health::status(HealthStatus::Unknown)
+            .with_detail("path", path.display().to_string());
+    };
+
+    let status = if disk.available_space() >= threshold {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+
+    Health::status(status)
+        .with_detail("path", path.display().to_string())
+        .with_detail("total", disk.total_space())
+        .with_detail("free", disk.available_space())
+        .with_detail("threshold", threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::{Health, HealthIndicator, HealthStatus, check, check_group};
+    use crate::server::LocalBoxFuture;
+
+    struct StaticIndicator(&'static str, HealthStatus, u64);
+
+    impl HealthIndicator for StaticIndicator {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn health(&self) -> LocalBoxFuture<'_, Health> {
+            Box::pin(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(self.2)).await;
+                Health::status(self.1).with_detail("checked", true)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn should_aggregate_most_severe_status() {
+        let indicators: Vec<Arc<dyn HealthIndicator>> = vec![
+            Arc::new(StaticIndicator("cache", HealthStatus::Unknown, 0)),
+            Arc::new(StaticIndicator("db.primary", HealthStatus::Up, 0)),
+            Arc::new(StaticIndicator("queue", HealthStatus::OutOfService, 0)),
+        ];
+
+        let report = check(&indicators, None).await;
+
+        assert_eq!(report.status, HealthStatus::OutOfService);
+        assert_eq!(
+            serde_json::to_value(&report.components).ok(),
+            Some(json!({
+                "cache": { "status": "UNKNOWN" },
+                "db.primary": { "status": "UP" },
+                "queue": { "status": "OUT_OF_SERVICE" }
+            }))
+        );
+        assert_eq!(check(&[], None).await.status, HealthStatus::Up);
+    }
+
+    #[tokio::test]
+    async fn should_report_slow_indicator_down_and_hide_details() {
+        let settings = serde_json::from_value(json!({
+            "show-details": "components",
+            "timeout": 1000,
+            "indicators": {
+                "slow": { "timeout": 10 },
+                "disabled": { "enabled": false }
+            }
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        let indicators: Vec<Arc<dyn HealthIndicator>> = vec![
+            Arc::new(StaticIndicator("slow", HealthStatus::Up, 200)),
+            Arc::new(StaticIndicator("fast", HealthStatus::Up, 0)),
+            Arc::new(StaticIndicator("disabled", HealthStatus::Down, 0)),
+        ];
+
+        let report = check(&indicators, Some(&settings)).await;
+
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(
+            serde_json::to_value(&report.components).ok(),
+            Some(json!({ "fast": { "status": "UP" }, "slow": { "status": "DOWN" } }))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_check_default_and_configured_groups() {
+        let settings = serde_json::from_value(json!({
+            "show-details": "never",
+            "groups": {
+                "dependencies": { "include": ["db.*", "redis"], "show-details": "components" }
+            }
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        let indicators: Vec<Arc<dyn HealthIndicator>> = vec![
+            Arc::new(StaticIndicator("readiness-state", HealthStatus::Up, 0)),
+            Arc::new(StaticIndicator("db.primary", HealthStatus::Up, 0)),
+            Arc::new(StaticIndicator("db.replica", HealthStatus::Down, 0)),
+            Arc::new(StaticIndicator("disk-space", HealthStatus::Down, 0)),
+        ];
+
+        let readiness = check_group(&indicators, Some(&settings), "readiness").await;
+        let dependencies = check_group(&indicators, Some(&settings), "dependencies").await;
+        let liveness = check_group(&indicators, Some(&settings), "liveness").await;
+
+        assert_eq!(
+            readiness.map(|r| (r.status, r.components.is_none())),
+            Some((HealthStatus::Down, true))
+        );
+        assert_eq!(
+            dependencies.and_then(|r| serde_json::to_value(&r.components).ok()),
+            Some(json!({ "db.primary": { "status": "UP" }, "db.replica": { "status": "DOWN" } }))
+        );
+        assert_eq!(liveness.map(|r| r.status), Some(HealthStatus::Up));
+        assert!(check_group(&indicators, None, "unknown").await.is_none());
+    }
+}
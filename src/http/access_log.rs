@@ -0,0 +1,477 @@
+//! # Access Log Module
+//!
+//! This module provides the access log middleware of the main and health-check
+//! servers, configured by the `access-log` settings.
+//!
+//! Each request outside the excluded paths is logged once its response is created,
+//! with the `access_log` target, as a `text` line rendered from a template or as a
+//! JSON object. The values of the sensitive query parameters and headers are masked,
+//! and the requests slower than `slow-threshold` are logged with the `WARN` level.
+//!
+//! In the `json` logging format, the fields of the JSON entries are merged into the
+//! log record.
+
+use std::future::{Ready, ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderMap, HeaderName};
+use actix_web::{Error, HttpMessage};
+use colored::Colorize;
+use log::Level;
+use regex::Regex;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::http::request_id::RequestId;
+use crate::security::oauth2::AuthenticatedUser;
+use crate::server::LocalBoxFuture;
+use crate::settings::Settings;
+
+/// Target of the access log records.
+pub(crate) const ACCESS_LOG_TARGET: &str = "access_log";
+
+/// Template of the `text` entries when `template` is not set.
+const DEFAULT_TEMPLATE: &str = r#"{remote_addr} "{method} {uri} {protocol}" {status} {bytes} {duration_ms}ms "{user_agent}" {request_id} {subject} {headers}"#;
+
+/// Paths not logged when `exclude-paths` is not set.
+const DEFAULT_EXCLUDED_PATHS: [&str; 3] =
+    ["^/actuator/health", "^/actuator/metrics$", "^/metrics$"];
+
+/// Query parameters masked when `masked-query-params` is not set.
+const DEFAULT_MASKED_PARAMS: [&str; 8] = [
+    "access_token",
+    "token",
+    "code",
+    "password",
+    "secret",
+    "client_secret",
+    "api_key",
+    "apikey",
+];
+
+/// Headers whose values are always masked.
+const SENSITIVE_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// Fields of an entry, usable as template placeholders.
+const FIELDS: [&str; 14] = [
+    "remote_addr",
+    "method",
+    "uri",
+    "path",
+    "protocol",
+    "status",
+    "bytes",
+    "duration_ms",
+    "request_id",
+    "subject",
+    "user_agent",
+    "referer",
+    "headers",
+    "slow",
+];
+
+/// Replacement of the masked values.
+const MASK: &str = "******";
+
+/// Access log options built from the `access-log` settings.
+#[derive(Debug)]
+struct AccessLogConfig {
+    json: bool,
+    template: String,
+    excluded_paths: Vec<Regex>,
+    headers: Vec<HeaderName>,
+    masked_params: Vec<String>,
+    slow_threshold: Option<Duration>,
+}
+
+impl AccessLogConfig {
+    /// Returns `true` if the requests of the path are logged.
+    fn is_logged(&self, path: &str) -> bool {
+        !self.excluded_paths.iter().any(|regex| regex.is_match(path))
+    }
+
+    /// Returns the path and the query of the URI, with the sensitive query parameters
+    /// masked.
+    fn masked_uri(&self, path: &str, query: &str) -> String {
+        if query.is_empty() {
+            return path.to_string();
+        }
+
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_masked(name) => format!("{name}={MASK}"),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("{path}?{query}")
+    }
+
+    /// Returns `true` if the value of the query parameter is masked.
+    fn is_masked(&self, name: &str) -> bool {
+        self.masked_params
+            .iter()
+            .any(|masked| masked == "*" || masked.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the allowed request headers, with the credentials masked.
+    fn allowed_headers(&self, headers: &HeaderMap) -> Map<String, Value> {
+        self.headers
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name)?.to_str().ok()?;
+                let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                    MASK
+                } else {
+                    value
+                };
+                Some((name.to_string(), Value::from(value)))
+            })
+            .collect()
+    }
+
+    /// Writes the entry of a completed request.
+    fn write(&self, mut entry: Map<String, Value>, duration: Duration) {
+        let slow = self
+            .slow_threshold
+            .is_some_and(|threshold| duration >= threshold);
+        entry.insert("duration_ms".into(), (duration.as_millis() as u64).into());
+        entry.insert("slow".into(), slow.into());
+
+        let level = if slow { Level::Warn } else { Level::Info };
+        if self.json {
+            log::log!(target: ACCESS_LOG_TARGET, level, "{}", Value::Object(entry));
+        } else if slow {
+            let line = render(&self.template, &entry);
+            log::log!(target: ACCESS_LOG_TARGET, level, "{}", line.yellow());
+        } else {
+            let line = render(&self.template, &entry);
+            log::log!(target: ACCESS_LOG_TARGET, level, "{}", line);
+        }
+    }
+}
+
+/// Builds the access log middleware from the `access-log` settings.
+///
+/// # Errors
+///
+/// Returns an error if the format, a template placeholder, an excluded path or a
+/// header name is invalid.
+pub(crate) fn configure_access_log(settings: &Settings) -> Result<AccessLog> {
+    let config = settings.access_log.as_ref();
+    if config.and_then(|c| c.enabled) == Some(false) {
+        return Ok(AccessLog(None));
+    }
+
+    let json = match config.and_then(|c| c.format.as_deref()).unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        format => return Err(AccessLogError::Format(format.into())),
+    };
+
+    let template = config
+        .and_then(|c| c.template.clone())
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.into());
+    if let Some(placeholder) = placeholders(&template).find(|name| !FIELDS.contains(name)) {
+        return Err(AccessLogError::Placeholder(placeholder.into()));
+    }
+
+    let excluded_paths = match config.and_then(|c| c.exclude_paths.clone()) {
+        Some(paths) => paths,
+        None => DEFAULT_EXCLUDED_PATHS.map(String::from).to_vec(),
+    }
+    .iter()
+    .map(|path| Regex::new(path).map_err(|e| AccessLogError::ExcludedPath(path.clone(), e)))
+    .collect::<Result<Vec<_>>>()?;
+
+    let headers = config
+        .and_then(|c| c.headers.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|name| {
+            HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes())
+                .map_err(|_| AccessLogError::Header(name.clone()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let masked_params = match config.and_then(|c| c.masked_query_params.clone()) {
+        Some(params) => params,
+        None => DEFAULT_MASKED_PARAMS.map(String::from).to_vec(),
+    };
+
+    Ok(AccessLog(Some(Arc::new(AccessLogConfig {
+        json,
+        template,
+        excluded_paths,
+        headers,
+        masked_params,
+        slow_threshold: config
+            .and_then(|c| c.slow_threshold)
+            .map(Duration::from_millis),
+    }))))
+}
+
+/// Returns the placeholder names of a template.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+}
+
+/// Renders a `text` entry, replacing the placeholders with the entry fields.
+fn render(template: &str, entry: &Map<String, Value>) -> String {
+    let mut line = String::with_capacity(template.len() * 2);
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        line.push_str(&rest[..start]);
+        line.push_str(&field_text(entry.get(&rest[start + 1..start + end])));
+        rest = &rest[start + end + 1..];
+    }
+    line.push_str(rest);
+    line
+}
+
+/// Returns the text of an entry field, `-` when it is missing.
+fn field_text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".into(),
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Object(headers)) if headers.is_empty() => "-".into(),
+        Some(Value::Object(headers)) => headers
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Access log middleware of the main and health-check servers.
+///
+/// Created from the `access-log` settings and applied with `App::wrap`. Requests are
+/// not logged when the access log is disabled.
+#[derive(Clone)]
+pub(crate) struct AccessLog(Option<Arc<AccessLogConfig>>);
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessLogMiddleware<S>;
+    type Future = Ready<std::result::Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogMiddleware {
+            service: Rc::new(service),
+            config: self.0.clone(),
+        }))
+    }
+}
+
+/// Service created by [`AccessLog`].
+pub(crate) struct AccessLogMiddleware<S> {
+    service: Rc<S>,
+    config: Option<Arc<AccessLogConfig>>,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let Some(config) = self.config.clone().filter(|c| c.is_logged(req.path())) else {
+            return Box::pin(service.call(req));
+        };
+
+        let start = Instant::now();
+        let entry = request_entry(&config, &req);
+
+        Box::pin(async move {
+            let result = service.call(req).await;
+            let entry = response_entry(entry, &result);
+            config.write(entry, start.elapsed());
+            result
+        })
+    }
+}
+
+/// Creates the entry fields known before the request is handled.
+fn request_entry(config: &AccessLogConfig, req: &ServiceRequest) -> Map<String, Value> {
+    let header = |name: HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(Value::from)
+            .unwrap_or_default()
+    };
+
+    let mut entry = Map::new();
+    entry.insert(
+        "remote_addr".into(),
+        req.peer_addr()
+            .map(|addr| Value::from(addr.ip().to_string()))
+            .unwrap_or_default(),
+    );
+    entry.insert("method".into(), req.method().as_str().into());
+    entry.insert(
+        "uri".into(),
+        config.masked_uri(req.path(), req.query_string()).into(),
+    );
+    entry.insert("path".into(), req.path().into());
+    entry.insert("protocol".into(), format!("{:?}", req.version()).into());
+    entry.insert(
+        "request_id".into(),
+        req.extensions()
+            .get::<RequestId>()
+            .map(|id| Value::from(id.as_str()))
+            .unwrap_or_default(),
+    );
+    entry.insert("user_agent".into(), header(header::USER_AGENT));
+    entry.insert("referer".into(), header(header::REFERER));
+    entry.insert(
+        "headers".into(),
+        Value::Object(config.allowed_headers(req.headers())),
+    );
+    entry
+}
+
+/// Adds the status, the body size and the authenticated subject to the entry.
+fn response_entry<B: MessageBody>(
+    mut entry: Map<String, Value>,
+    result: &std::result::Result<ServiceResponse<B>, Error>,
+) -> Map<String, Value> {
+    let (status, bytes, subject) = match result {
+        Ok(response) => {
+            let bytes = match response.response().body().size() {
+                BodySize::Sized(size) => Value::from(size),
+                BodySize::None => Value::from(0),
+                BodySize::Stream => Value::Null,
+            };
+            let subject = response
+                .request()
+                .extensions()
+                .get::<AuthenticatedUser>()
+                .and_then(|user| user.sub.clone())
+                .map(Value::from)
+                .unwrap_or_default();
+            (response.status(), bytes, subject)
+        }
+        Err(e) => (
+            e.as_response_error().status_code(),
+            Value::Null,
+            Value::Null,
+        ),
+    };
+
+    entry.insert("status".into(), status.as_u16().into());
+    entry.insert("bytes".into(), bytes);
+    entry.insert("subject".into(), subject);
+    entry
+}
+
+/// A type alias for a `Result` with the `AccessLogError` error type.
+pub type Result<T, E = AccessLogError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum AccessLogError {
+    #[error("Unsupported access log format: {0}. Expected text or json.")]
+    Format(String),
+
+    #[error("Unknown access log placeholder: {{{0}}}.")]
+    Placeholder(String),
+
+    #[error("Invalid access log excluded path {0}: {1}")]
+    ExcludedPath(String, regex::Error),
+
+    #[error("Invalid access log header name: {0}.")]
+    Header(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+    use serde_json::{Map, Value, json};
+
+    use super::{AccessLogError, configure_access_log, render};
+    use crate::settings::Settings;
+
+    #[test]
+    fn should_mask_query_and_render_template() {
+        let settings: Settings = serde_json::from_value(json!({
+            "access-log": {
+                "template": "{method} {uri} {status} {subject} {headers}",
+                "headers": ["X-Tenant-Id", "Authorization"]
+            }
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        let Ok(super::AccessLog(Some(config))) = configure_access_log(&settings) else {
+            panic!("The access log should be enabled.");
+        };
+
+        assert!(!config.is_logged("/actuator/health/readiness"));
+        assert!(config.is_logged("/v1/orders"));
+        assert_eq!(
+            config.masked_uri("/v1/login", "user=ana&Password=s3cret&token"),
+            "/v1/login?user=ana&Password=******&token"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer eyJhbGciOi"));
+        headers.insert(
+            HeaderName::from_static("x-tenant-id"),
+            HeaderValue::from_static("acme"),
+        );
+
+        let mut entry = Map::new();
+        entry.insert("method".into(), "GET".into());
+        entry.insert("uri".into(), "/v1/orders".into());
+        entry.insert("status".into(), 200.into());
+        entry.insert("subject".into(), Value::Null);
+        entry.insert(
+            "headers".into(),
+            Value::Object(config.allowed_headers(&headers)),
+        );
+        assert_eq!(
+            render(&config.template, &entry),
+            r#"GET /v1/orders 200 - authorization="******" x-tenant-id="acme""#
+        );
+
+        let settings: Settings =
+            serde_json::from_value(json!({ "access-log": { "template": "{latency}" } }))
+                .unwrap_or_else(|e| panic!("{e}"));
+        assert!(matches!(
+            configure_access_log(&settings),
+            Err(AccessLogError::Placeholder(name)) if name == "latency"
+        ));
+    }
+}
@@ -0,0 +1,1505 @@
+//! # HTTP Client Module
+//!
+//! This module provides the outbound HTTP clients used for service-to-service calls.
+//!
+//! ## Responsibilities
+//!
+//! - Fetch client-credentials tokens from the identity provider token endpoint, cache
+//!   them and refresh them shortly before they expire.
+//! - Inject the `Authorization: Bearer` header into the outbound requests, with the
+//!   token of the server, the token of the `#[secured]` request being handled, or a
+//!   token exchanged for it (RFC 8693).
+//! - Build the named clients declared in the `http-clients` settings, with timeouts,
+//!   retries, circuit breaker and bulkhead.
+//! - Trace outbound requests through `reqwest-tracing` and export Prometheus metrics
+//!   labelled with the client name.
+//! - Forward the `X-Request-Id` of the request being handled by the main server.
+//!
+//! ## Default Client
+//!
+//! The default client is created during the server initialization when client
+//! credentials and a token endpoint are configured, and can be retrieved through the
+//! global server instance:
+//!
+//! ```rust,ignore
+//! use rust_microservice::Server;
+//!
+//! let client = Server::global()?.http_client().ok_or(MyError::ClientNotConfigured)?;
+//! let orders = client.get("http://orders/v1/orders").send().await?.json::<Vec<Order>>().await?;
+//! ```
+//!
+//! ## Named Clients
+//!
+//! Each entry of the `http-clients` settings creates a client whose requests go
+//! through the following middlewares (outermost first):
+//!
+//! 1. Metrics — counts the calls and measures their duration.
+//! 2. Retry — retries idempotent requests on transient failures with an exponential
+//!    backoff.
+//! 3. Circuit breaker — rejects the calls while the remote service is failing.
+//! 4. Bulkhead — limits the number of concurrent calls.
+//! 5. Tracing, request id and authentication (`authentication` setting).
+//!
+//! Relative paths are resolved against the `base-url` of the client:
+//!
+//! ```rust,ignore
+//! use rust_microservice::Server;
+//!
+//! let orders = Server::global()?
+//!     .http_client_with_name("orders")
+//!     .ok_or(MyError::ClientNotConfigured)?
+//!     .get("/v1/orders")
+//!     .send()
+//!     .await?;
+//! ```
+//!
+//! ## Metrics
+//!
+//! | Metric                                       | Labels                       |
+//! | -------------------------------------------- | ---------------------------- |
+//! | `<app>_http_client_requests_total`           | `client`, `method`, `status` |
+//! | `<app>_http_client_request_duration_seconds` | `client`, `method`           |
+//! | `<app>_http_client_retries_total`            | `client`                     |
+//! | `<app>_http_client_rejected_calls_total`     | `client`, `reason`           |
+//! | `<app>_http_client_circuit_state`            | `client`                     |
+//!
+//! The `status` label holds the response status code, or `error` when no response
+//! was received. The circuit state is `0` (closed), `1` (open) or `2` (half-open).
+
+use std::collections::HashMap;
+use std::sync::{Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use colored::Colorize;
+use http::Extensions;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry};
+use reqwest::header::{AUTHORIZATION, HeaderValue};
+use reqwest::{Method, Request, Response, StatusCode};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next, RequestBuilder};
+use reqwest_tracing::TracingMiddleware;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::sync::{Mutex, Semaphore};
+use tracing::{debug, info, warn};
+
+use crate::http::request_id::REQUEST_ID_HEADER;
+use crate::logging;
+use crate::security::oauth2::{self, AuthenticatedUser, OAuth2Error, Token};
+use crate::settings::{self, Settings};
+
+/// Maximum margin before the token expiration at which it is refreshed.
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Token lifetime (in seconds) assumed when the token response has no `expires_in`.
+const DEFAULT_EXPIRES_IN: u64 = 60;
+
+/// Maximum token lifetime (in seconds) trusted by the cache, one day.
+const MAX_EXPIRES_IN: u64 = 86_400;
+
+/// Default connection timeout (in seconds) of the token requests.
+const DEFAULT_TOKEN_CONNECT_TIMEOUT: u64 = 5;
+
+/// Default total timeout (in seconds) of the token requests.
+const DEFAULT_TOKEN_TIMEOUT: u64 = 10;
+
+/// Maximum number of cached exchanged tokens.
+const MAX_EXCHANGED_TOKENS: usize = 10_000;
+
+/// Grant type of the token exchange requests (RFC 8693).
+const TOKEN_EXCHANGE_GRANT: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// Type of the subject and requested tokens of the token exchange requests.
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// Default number of attempts of the retry policy, including the first one.
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default delay (in milliseconds) before the first retry.
+const DEFAULT_INITIAL_BACKOFF: u64 = 100;
+
+/// Default maximum delay (in milliseconds) between two attempts.
+const DEFAULT_MAX_BACKOFF: u64 = 2000;
+
+/// Default growth factor of the delay between two attempts.
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+/// Default number of consecutive failures that opens the circuit.
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time (in seconds) the circuit stays open.
+const DEFAULT_OPEN_DURATION: u64 = 30;
+
+/// Maximum time (in seconds) the circuit stays open, one day.
+const MAX_OPEN_DURATION: u64 = 86_400;
+
+/// Default maximum number of concurrent calls of the bulkhead.
+const DEFAULT_MAX_CONCURRENT_CALLS: usize = 100;
+
+/// Prometheus metrics of the named clients, created with the namespace of the first
+/// registry they are registered in.
+static METRICS: OnceLock<HttpClientMetrics> = OnceLock::new();
+
+/// Cached access token and its refresh instant.
+struct CachedToken {
+    access_token: String,
+    refresh_at: Instant,
+}
+
+impl CachedToken {
+    /// Caches the access token of a token response. The token is refreshed before it
+    /// expires (at most 30 seconds, half of its lifetime for short-lived tokens), and
+    /// at least once a day.
+    fn new(token: Token) -> oauth2::Result<Self> {
+        let access_token = token.access_token.ok_or_else(|| {
+            OAuth2Error::Unauthorized("Token response without access token.".into())
+        })?;
+
+        let expires_in = token.expires_in.unwrap_or(DEFAULT_EXPIRES_IN);
+        debug!("Access token obtained. Expires in {expires_in} seconds.");
+
+        let lifetime = Duration::from_secs(expires_in.min(MAX_EXPIRES_IN));
+        let now = Instant::now();
+        let refresh_at = now
+            .checked_add(lifetime - REFRESH_MARGIN.min(lifetime / 2))
+            .unwrap_or(now);
+
+        Ok(CachedToken {
+            access_token,
+            refresh_at,
+        })
+    }
+
+    /// Returns `true` if the token can still be used.
+    fn is_valid(&self) -> bool {
+        self.refresh_at > Instant::now()
+    }
+}
+
+/// Builds the client of the token requests, with the timeouts of the OAuth2 client
+/// settings.
+fn token_client(client: &settings::OAuth2Client) -> Option<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(
+            client
+                .connect_timeout
+                .unwrap_or(DEFAULT_TOKEN_CONNECT_TIMEOUT),
+        ))
+        .timeout(Duration::from_secs(
+            client.timeout.unwrap_or(DEFAULT_TOKEN_TIMEOUT),
+        ))
+        .build()
+        .inspect_err(|e| warn!("Failed to build the token endpoint client: {e}"))
+        .ok()
+}
+
+/// Sends a token request to the token endpoint.
+///
+/// Answers `4xx` are rejections of the client credentials (`Unauthorized`), while
+/// connection errors, `5xx` answers and unreadable bodies mean the identity provider
+/// is unavailable.
+async fn request_token(
+    client: &reqwest::Client,
+    token_uri: &str,
+    form: &[(&str, &str)],
+) -> oauth2::Result<Token> {
+    let response = client
+        .post(token_uri)
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| OAuth2Error::unavailable(token_uri, e))?;
+
+    if response.status().is_client_error() {
+        let status = response.status();
+        return Err(OAuth2Error::Unauthorized(format!(
+            "Token request rejected with {status}."
+        )));
+    }
+
+    response
+        .error_for_status()
+        .map_err(|e| OAuth2Error::unavailable(token_uri, e))?
+        .json::<Token>()
+        .await
+        .map_err(|e| OAuth2Error::unavailable(token_uri, e))
+}
+
+/// Provider of OAuth2 access tokens obtained with the `client_credentials` grant.
+///
+/// The token is cached and shared by all the requests. Concurrent callers wait for
+/// a single token request when the cached token must be refreshed.
+pub struct ClientCredentialsProvider {
+    client: reqwest::Client,
+    token_uri: String,
+    client_id: String,
+    client_secret: Option<String>,
+    scope: Option<String>,
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl ClientCredentialsProvider {
+    /// Creates a provider from the OAuth2 settings.
+    ///
+    /// # Returns
+    ///
+    /// `None` when the OAuth2 client ID or the token endpoint are not configured, or
+    /// when the token endpoint client cannot be built.
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let oauth2 = settings.get_oauth2_config()?;
+        let client = oauth2.client.as_ref()?;
+
+        Some(ClientCredentialsProvider {
+            client: token_client(client)?,
+            token_uri: oauth2.token_uri.clone()?,
+            client_id: client.id.clone()?,
+            client_secret: client.secret.clone(),
+            scope: client.scope.clone(),
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Returns a valid access token, requesting a new one when the cached token is
+    /// missing or about to expire.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OAuth2Error` if the token endpoint cannot be reached or doesn't
+    /// return an access token.
+    pub async fn access_token(&self) -> oauth2::Result<String> {
+        let mut cached = self.token.lock().await;
+
+        if let Some(token) = cached.as_ref().filter(|token| token.is_valid()) {
+            return Ok(token.access_token.clone());
+        }
+
+        let token = CachedToken::new(self.request_token().await?)?;
+        let access_token = token.access_token.clone();
+        *cached = Some(token);
+
+        Ok(access_token)
+    }
+
+    /// Discards the cached token, so the next request obtains a new one.
+    pub async fn invalidate(&self) {
+        self.token.lock().await.take();
+    }
+
+    /// Requests a new token from the token endpoint.
+    async fn request_token(&self) -> oauth2::Result<Token> {
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.client_id.as_str()),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.as_str()));
+        }
+
+        request_token(&self.client, &self.token_uri, &form).await
+    }
+}
+
+/// Middleware that authenticates the outbound requests with a client-credentials
+/// access token.
+///
+/// Requests that already carry an `Authorization` header are sent unchanged. When
+/// the remote service answers `401 Unauthorized`, the cached token is discarded.
+pub struct ClientCredentialsMiddleware {
+    provider: ClientCredentialsProvider,
+}
+
+impl ClientCredentialsMiddleware {
+    /// Creates the middleware for the given token provider.
+    pub fn new(provider: ClientCredentialsProvider) -> Self {
+        ClientCredentialsMiddleware { provider }
+    }
+}
+
+#[async_trait]
+impl Middleware for ClientCredentialsMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !req.headers().contains_key(AUTHORIZATION) {
+            let token = self
+                .provider
+                .access_token()
+                .await
+                .map_err(reqwest_middleware::Error::middleware)?;
+            let value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(reqwest_middleware::Error::middleware)?;
+            req.headers_mut().insert(AUTHORIZATION, value);
+        }
+
+        let response = next.run(req, extensions).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            warn!("Outbound request rejected with 401. Discarding the cached token.");
+            self.provider.invalidate().await;
+        }
+
+        Ok(response)
+    }
+}
+
+/// Middleware that forwards the bearer token of the `#[secured]` request being
+/// handled.
+///
+/// Requests that already carry an `Authorization` header, or that are sent outside a
+/// secured handler, are sent unchanged.
+pub struct BearerPropagationMiddleware;
+
+#[async_trait]
+impl Middleware for BearerPropagationMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !req.headers().contains_key(AUTHORIZATION) {
+            match AuthenticatedUser::current().and_then(|user| user.token) {
+                Some(token) => {
+                    let value = HeaderValue::from_str(&format!("Bearer {token}"))
+                        .map_err(reqwest_middleware::Error::middleware)?;
+                    req.headers_mut().insert(AUTHORIZATION, value);
+                }
+                None => debug!("No inbound bearer token to propagate."),
+            }
+        }
+
+        next.run(req, extensions).await
+    }
+}
+
+/// Middleware that forwards the id of the request being handled by the main server
+/// in the `X-Request-Id` header.
+///
+/// Requests that already carry an `X-Request-Id` header, or that are sent outside a
+/// request, are sent unchanged.
+pub struct RequestIdPropagationMiddleware;
+
+#[async_trait]
+impl Middleware for RequestIdPropagationMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !req.headers().contains_key(REQUEST_ID_HEADER)
+            && let Some(id) = logging::current_request_id()
+        {
+            let value =
+                HeaderValue::from_str(&id).map_err(reqwest_middleware::Error::middleware)?;
+            req.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        next.run(req, extensions).await
+    }
+}
+
+/// Provider of OAuth2 access tokens obtained by exchanging the bearer token of the
+/// authenticated user (RFC 8693 token exchange).
+///
+/// The exchanged tokens are cached by subject token until they expire.
+pub struct TokenExchangeProvider {
+    client: reqwest::Client,
+    token_uri: String,
+    client_id: String,
+    client_secret: Option<String>,
+    audience: Option<String>,
+    resource: Option<String>,
+    scope: Option<String>,
+    tokens: StdMutex<HashMap<String, CachedToken>>,
+}
+
+impl TokenExchangeProvider {
+    /// Creates a provider from the OAuth2 settings and the token exchange parameters
+    /// of a named client.
+    ///
+    /// # Returns
+    ///
+    /// `None` when the OAuth2 client ID or the token endpoint are not configured, or
+    /// when the token endpoint client cannot be built.
+    pub fn from_settings(
+        settings: &Settings,
+        exchange: Option<&settings::TokenExchange>,
+    ) -> Option<Self> {
+        let oauth2 = settings.get_oauth2_config()?;
+        let client = oauth2.client.as_ref()?;
+
+        Some(TokenExchangeProvider {
+            client: token_client(client)?,
+            token_uri: oauth2.token_uri.clone()?,
+            client_id: client.id.clone()?,
+            client_secret: client.secret.clone(),
+            audience: exchange.and_then(|e| e.audience.clone()),
+            resource: exchange.and_then(|e| e.resource.clone()),
+            scope: exchange.and_then(|e| e.scope.clone()),
+            tokens: StdMutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns an access token exchanged for the given subject token, requesting a
+    /// new one when the cached token is missing or about to expire.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OAuth2Error` if the token endpoint cannot be reached or refuses
+    /// the exchange.
+    pub async fn exchange(&self, subject_token: &str) -> oauth2::Result<String> {
+        let key = format!("{:x}", Sha256::digest(subject_token.as_bytes()));
+
+        if let Ok(tokens) = self.tokens.lock()
+            && let Some(token) = tokens.get(&key).filter(|token| token.is_valid())
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let token = CachedToken::new(self.request_token(subject_token).await?)?;
+        let access_token = token.access_token.clone();
+
+        if let Ok(mut tokens) = self.tokens.lock() {
+            if tokens.len() >= MAX_EXCHANGED_TOKENS {
+                tokens.retain(|_, token| token.is_valid());
+            }
+            if tokens.len() < MAX_EXCHANGED_TOKENS {
+                tokens.insert(key, token);
+            }
+        }
+
+        Ok(access_token)
+    }
+
+    /// Discards the token exchanged for the given subject token.
+    pub fn invalidate(&self, subject_token: &str) {
+        let key = format!("{:x}", Sha256::digest(subject_token.as_bytes()));
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.remove(&key);
+        }
+    }
+
+    /// Requests a token exchange from the token endpoint.
+    async fn request_token(&self, subject_token: &str) -> oauth2::Result<Token> {
+        let mut form = vec![
+            ("grant_type", TOKEN_EXCHANGE_GRANT),
+            ("subject_token", subject_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+            ("requested_token_type", ACCESS_TOKEN_TYPE),
+            ("client_id", self.client_id.as_str()),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        if let Some(audience) = &self.audience {
+            form.push(("audience", audience.as_str()));
+        }
+        if let Some(resource) = &self.resource {
+            form.push(("resource", resource.as_str()));
+        }
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.as_str()));
+        }
+
+        request_token(&self.client, &self.token_uri, &form).await
+    }
+}
+
+/// Middleware that authenticates the outbound requests with a token exchanged for
+/// the bearer token of the `#[secured]` request being handled.
+///
+/// Requests that already carry an `Authorization` header, or that are sent outside a
+/// secured handler, are sent unchanged. When the remote service answers
+/// `401 Unauthorized`, the exchanged token is discarded.
+pub struct TokenExchangeMiddleware {
+    provider: TokenExchangeProvider,
+}
+
+impl TokenExchangeMiddleware {
+    /// Creates the middleware for the given token provider.
+    pub fn new(provider: TokenExchangeProvider) -> Self {
+        TokenExchangeMiddleware { provider }
+    }
+}
+
+#[async_trait]
+impl Middleware for TokenExchangeMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let subject_token = if req.headers().contains_key(AUTHORIZATION) {
+            None
+        } else {
+            AuthenticatedUser::current().and_then(|user| user.token)
+        };
+
+        if let Some(subject_token) = &subject_token {
+            let token = self
+                .provider
+                .exchange(subject_token)
+                .await
+                .map_err(reqwest_middleware::Error::middleware)?;
+            let value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(reqwest_middleware::Error::middleware)?;
+            req.headers_mut().insert(AUTHORIZATION, value);
+        }
+
+        let response = next.run(req, extensions).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED
+            && let Some(subject_token) = &subject_token
+        {
+            warn!("Outbound request rejected with 401. Discarding the exchanged token.");
+            self.provider.invalidate(subject_token);
+        }
+
+        Ok(response)
+    }
+}
+
+/// Creates the outbound HTTP client authenticated with client-credentials tokens.
+///
+/// # Returns
+///
+/// `None` when the OAuth2 client ID or the token endpoint are not configured.
+pub(crate) fn create_oauth2_client(settings: &Settings) -> Option<ClientWithMiddleware> {
+    let provider = ClientCredentialsProvider::from_settings(settings)?;
+
+    info!(
+        "Outbound HTTP client configured with client credentials of {}.",
+        provider.client_id.bright_blue()
+    );
+
+    Some(
+        ClientBuilder::new(reqwest::Client::new())
+            .with(TracingMiddleware::default())
+            .with(RequestIdPropagationMiddleware)
+            .with(ClientCredentialsMiddleware::new(provider))
+            .build(),
+    )
+}
+
+/// Named outbound HTTP client built from the `http-clients` settings.
+///
+/// Requests created with a relative path are resolved against the `base-url` of the
+/// client. The underlying [`ClientWithMiddleware`] is cheap to clone and shares its
+/// connection pool and resilience state.
+#[derive(Clone)]
+pub struct HttpClient {
+    name: String,
+    base_url: Option<String>,
+    client: ClientWithMiddleware,
+}
+
+impl HttpClient {
+    /// Returns the name of the client.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the underlying client with its middlewares.
+    pub fn client(&self) -> &ClientWithMiddleware {
+        &self.client
+    }
+
+    /// Resolves the given path against the base URL of the client.
+    ///
+    /// Absolute URLs (`http://` or `https://`) are returned unchanged.
+    pub fn url(&self, path: &str) -> String {
+        match &self.base_url {
+            Some(base) if !path.starts_with("http://") && !path.starts_with("https://") => {
+                let path = path.trim_start_matches('/');
+                if path.is_empty() {
+                    base.clone()
+                } else {
+                    format!("{}/{}", base.trim_end_matches('/'), path)
+                }
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// Starts building a request with the given method and path.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client.request(method, self.url(path))
+    }
+
+    /// Starts building a `GET` request.
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    /// Starts building a `POST` request.
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    /// Starts building a `PUT` request.
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PUT, path)
+    }
+
+    /// Starts building a `PATCH` request.
+    pub fn patch(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PATCH, path)
+    }
+
+    /// Starts building a `DELETE` request.
+    pub fn delete(&self, path: &str) -> RequestBuilder {
+        self.request(Method::DELETE, path)
+    }
+}
+
+/// Registry of the named outbound HTTP clients.
+#[derive(Clone, Default)]
+pub struct HttpClientRegistry {
+    clients: HashMap<String, HttpClient>,
+}
+
+impl HttpClientRegistry {
+    /// Creates the clients declared in the `http-clients` settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `HttpClientError` if a client configuration is invalid.
+    pub(crate) fn from_settings(settings: &Settings) -> Result<Self> {
+        let mut clients = HashMap::new();
+
+        for (name, config) in settings.http_clients.iter().flatten() {
+            let client = create_client(name, config, settings)?;
+            info!("HTTP client {} configured.", name.bright_blue());
+            clients.insert(name.clone(), client);
+        }
+
+        Ok(HttpClientRegistry { clients })
+    }
+
+    /// Returns the client with the given name, if configured.
+    pub fn get(&self, name: &str) -> Option<&HttpClient> {
+        self.clients.get(name)
+    }
+}
+
+/// Creates a named client and its middleware stack.
+fn create_client(
+    name: &str,
+    config: &settings::HttpClient,
+    settings: &Settings,
+) -> Result<HttpClient> {
+    let base_url = match &config.base_url {
+        Some(url) => {
+            reqwest::Url::parse(url).map_err(|e| {
+                HttpClientError::Configuration(format!("{name}: invalid base URL {url}: {e}"))
+            })?;
+            Some(url.clone())
+        }
+        None => None,
+    };
+
+    let mut builder = reqwest::Client::builder();
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(Duration::from_secs(timeout));
+    }
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    let client = builder
+        .build()
+        .map_err(|e| HttpClientError::Configuration(format!("{name}: {e}")))?;
+
+    let mut builder = ClientBuilder::new(client).with(MetricsMiddleware::new(name));
+
+    if let Some(retry) = config.retry.as_ref().filter(|r| r.enabled.unwrap_or(true)) {
+        builder = builder.with(RetryMiddleware::new(name, retry)?);
+    }
+    if let Some(breaker) = config
+        .circuit_breaker
+        .as_ref()
+        .filter(|c| c.enabled.unwrap_or(true))
+    {
+        builder = builder.with(CircuitBreakerMiddleware::new(name, breaker));
+    }
+    if let Some(bulkhead) = config
+        .bulkhead
+        .as_ref()
+        .filter(|b| b.enabled.unwrap_or(true))
+    {
+        builder = builder.with(BulkheadMiddleware::new(name, bulkhead)?);
+    }
+
+    builder = builder
+        .with(TracingMiddleware::default())
+        .with(RequestIdPropagationMiddleware);
+
+    match config.authentication.as_deref().unwrap_or("none") {
+        "none" => {}
+        "client-credentials" => {
+            let provider = ClientCredentialsProvider::from_settings(settings).ok_or_else(|| {
+                HttpClientError::Configuration(format!(
+                    "{name}: client-credentials authentication requires the OAuth2 client and token URI."
+                ))
+            })?;
+            builder = builder.with(ClientCredentialsMiddleware::new(provider));
+        }
+        "propagate" => builder = builder.with(BearerPropagationMiddleware),
+        "token-exchange" => {
+            let provider =
+                TokenExchangeProvider::from_settings(settings, config.token_exchange.as_ref())
+                    .ok_or_else(|| {
+                        HttpClientError::Configuration(format!(
+                            "{name}: token-exchange authentication requires the OAuth2 client and token URI."
+                        ))
+                    })?;
+            builder = builder.with(TokenExchangeMiddleware::new(provider));
+        }
+        other => {
+            return Err(HttpClientError::Configuration(format!(
+                "{name}: unknown authentication `{other}`."
+            )));
+        }
+    }
+
+    Ok(HttpClient {
+        name: name.to_string(),
+        base_url,
+        client: builder.build(),
+    })
+}
+
+/// Middleware that records the Prometheus metrics of a named client.
+struct MetricsMiddleware {
+    name: String,
+}
+
+impl MetricsMiddleware {
+    fn new(name: &str) -> Self {
+        MetricsMiddleware {
+            name: name.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for MetricsMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let method = req.method().to_string();
+        let start = Instant::now();
+
+        let result = next.run(req, extensions).await;
+
+        if let Some(metrics) = METRICS.get() {
+            let status = match &result {
+                Ok(response) => response.status().as_u16().to_string(),
+                Err(_) => "error".to_string(),
+            };
+            metrics
+                .requests
+                .with_label_values(&[self.name.as_str(), &method, &status])
+                .inc();
+            metrics
+                .duration
+                .with_label_values(&[self.name.as_str(), &method])
+                .observe(start.elapsed().as_secs_f64());
+        }
+
+        result
+    }
+}
+
+/// Middleware that retries idempotent requests on transient failures.
+///
+/// Connection errors, timeouts and `429`, `502`, `503` and `504` responses are
+/// retried with an exponential backoff and jitter. Requests with a streaming body
+/// (that cannot be cloned) are sent once.
+struct RetryMiddleware {
+    name: String,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+}
+
+impl RetryMiddleware {
+    fn new(name: &str, config: &settings::Retry) -> Result<Self> {
+        let multiplier = config.multiplier.unwrap_or(DEFAULT_MULTIPLIER);
+        if !multiplier.is_finite() {
+            return Err(HttpClientError::Configuration(format!(
+                "{name}: invalid retry multiplier {multiplier}."
+            )));
+        }
+
+        Ok(RetryMiddleware {
+            name: name.to_string(),
+            max_attempts: config.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+            initial_backoff: Duration::from_millis(
+                config.initial_backoff.unwrap_or(DEFAULT_INITIAL_BACKOFF),
+            ),
+            max_backoff: Duration::from_millis(config.max_backoff.unwrap_or(DEFAULT_MAX_BACKOFF)),
+            multiplier: multiplier.max(1.0),
+        })
+    }
+
+    /// Returns the delay before the given retry (starting at 1), with a random
+    /// jitter of up to half of the delay.
+    fn backoff(&self, retry: u32) -> Duration {
+        // Computed in seconds so that a large exponent saturates to the maximum
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = Duration::try_from_secs_f64(
+            self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent),
+        )
+        .unwrap_or(self.max_backoff)
+        .min(self.max_backoff);
+        delay / 2 + (delay / 2).mul_f64(fastrand::f64())
+    }
+}
+
+/// Returns `true` if the result of an attempt is a transient failure.
+fn is_transient(result: &reqwest_middleware::Result<Response>) -> bool {
+    match result {
+        Ok(response) => matches!(
+            response.status(),
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        ),
+        Err(reqwest_middleware::Error::Reqwest(e)) => e.is_connect() || e.is_timeout(),
+        Err(_) => false,
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !req.method().is_idempotent() {
+            return next.run(req, extensions).await;
+        }
+
+        let mut attempt = 1;
+        loop {
+            // The last attempt (or a request that cannot be cloned) consumes the request
+            let Some(attempt_req) = (attempt < self.max_attempts)
+                .then(|| req.try_clone())
+                .flatten()
+            else {
+                return next.run(req, extensions).await;
+            };
+
+            let result = next.clone().run(attempt_req, extensions).await;
+            if !is_transient(&result) {
+                return result;
+            }
+
+            let delay = self.backoff(attempt);
+            warn!(
+                "Outbound request of the HTTP client {} failed (attempt {}/{}). Retrying in {} ms.",
+                self.name,
+                attempt,
+                self.max_attempts,
+                delay.as_millis()
+            );
+            if let Some(metrics) = METRICS.get() {
+                metrics
+                    .retries
+                    .with_label_values(&[self.name.as_str()])
+                    .inc();
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// State of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    /// Calls are allowed. Holds the number of consecutive failures.
+    Closed(u32),
+    /// Calls are rejected until the given instant.
+    Open(Instant),
+    /// A trial call, started at the given instant, is in flight.
+    HalfOpen(Instant),
+}
+
+impl CircuitState {
+    /// Value exported by the circuit state gauge.
+    fn gauge_value(&self) -> i64 {
+        match self {
+            CircuitState::Closed(_) => 0,
+            CircuitState::Open(_) => 1,
+            CircuitState::HalfOpen(_) => 2,
+        }
+    }
+}
+
+/// Middleware that stops calling a failing service.
+///
+/// The circuit opens after `failure-threshold` consecutive failures (connection
+/// errors, timeouts or `5xx` responses) and rejects the calls during `open-duration`.
+/// A single trial call is then allowed: its success closes the circuit, its failure
+/// opens it again.
+struct CircuitBreakerMiddleware {
+    name: String,
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: std::sync::Mutex<CircuitState>,
+}
+
+impl CircuitBreakerMiddleware {
+    fn new(name: &str, config: &settings::CircuitBreaker) -> Self {
+        CircuitBreakerMiddleware {
+            name: name.to_string(),
+            failure_threshold: config
+                .failure_threshold
+                .unwrap_or(DEFAULT_FAILURE_THRESHOLD)
+                .max(1),
+            open_duration: Duration::from_secs(
+                config
+                    .open_duration
+                    .unwrap_or(DEFAULT_OPEN_DURATION)
+                    .min(MAX_OPEN_DURATION),
+            ),
+            state: std::sync::Mutex::new(CircuitState::Closed(0)),
+        }
+    }
+
+    /// Returns `true` if a call is allowed, moving an expired open circuit to
+    /// half-open. A trial call that never completes (e.g. cancelled) is replaced by a
+    /// new one after `open-duration`.
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        match *state {
+            CircuitState::Closed(_) => true,
+            CircuitState::Open(until) if now >= until => {
+                self.transition(&mut state, CircuitState::HalfOpen(now));
+                true
+            }
+            CircuitState::HalfOpen(since) if now >= self.open_until(since) => {
+                *state = CircuitState::HalfOpen(now);
+                true
+            }
+            CircuitState::Open(_) | CircuitState::HalfOpen(_) => false,
+        }
+    }
+
+    /// Records the outcome of an allowed call.
+    fn record(&self, failure: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        match (*state, failure) {
+            (CircuitState::Closed(failures), true) if failures + 1 >= self.failure_threshold => {
+                warn!(
+                    "Circuit breaker of the HTTP client {} opened after {} consecutive failures.",
+                    self.name,
+                    failures + 1
+                );
+                self.transition(
+                    &mut state,
+                    CircuitState::Open(self.open_until(Instant::now())),
+                );
+            }
+            (CircuitState::Closed(failures), true) => *state = CircuitState::Closed(failures + 1),
+            (CircuitState::Closed(_), false) => *state = CircuitState::Closed(0),
+            (CircuitState::HalfOpen(_), true) => {
+                warn!("Circuit breaker of the HTTP client {} reopened.", self.name);
+                self.transition(
+                    &mut state,
+                    CircuitState::Open(self.open_until(Instant::now())),
+                );
+            }
+            (CircuitState::HalfOpen(_), false) => {
+                info!("Circuit breaker of the HTTP client {} closed.", self.name);
+                self.transition(&mut state, CircuitState::Closed(0));
+            }
+            // Calls started before the circuit opened don't change its state
+            (CircuitState::Open(_), _) => {}
+        }
+    }
+
+    /// Returns the end of an open period started at `from`.
+    fn open_until(&self, from: Instant) -> Instant {
+        from.checked_add(self.open_duration).unwrap_or(from)
+    }
+
+    /// Changes the state and updates the circuit state gauge.
+    fn transition(&self, state: &mut CircuitState, next: CircuitState) {
+        *state = next;
+        if let Some(metrics) = METRICS.get() {
+            metrics
+                .circuit_state
+                .with_label_values(&[self.name.as_str()])
+                .set(next.gauge_value());
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for CircuitBreakerMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if !self.try_acquire() {
+            record_rejection(&self.name, "circuit_open");
+            return Err(reqwest_middleware::Error::middleware(
+                HttpClientError::CircuitOpen(self.name.clone()),
+            ));
+        }
+
+        let result = next.run(req, extensions).await;
+
+        let failure = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(reqwest_middleware::Error::Reqwest(e)) => e.is_connect() || e.is_timeout(),
+            Err(_) => false,
+        };
+        self.record(failure);
+
+        result
+    }
+}
+
+/// Middleware that limits the number of concurrent calls of a named client.
+struct BulkheadMiddleware {
+    name: String,
+    semaphore: Semaphore,
+    max_wait: Duration,
+}
+
+impl BulkheadMiddleware {
+    fn new(name: &str, config: &settings::Bulkhead) -> Result<Self> {
+        let max_concurrent_calls = config
+            .max_concurrent_calls
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_CALLS);
+        if max_concurrent_calls == 0 || max_concurrent_calls > Semaphore::MAX_PERMITS {
+            return Err(HttpClientError::Configuration(format!(
+                "{name}: invalid bulkhead max concurrent calls {max_concurrent_calls}."
+            )));
+        }
+
+        Ok(BulkheadMiddleware {
+            name: name.to_string(),
+            semaphore: Semaphore::new(max_concurrent_calls),
+            max_wait: Duration::from_millis(config.max_wait.unwrap_or(0)),
+        })
+    }
+}
+
+#[async_trait]
+impl Middleware for BulkheadMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let permit = if self.max_wait.is_zero() {
+            self.semaphore.try_acquire().ok()
+        } else {
+            tokio::time::timeout(self.max_wait, self.semaphore.acquire())
+                .await
+                .ok()
+                .and_then(|permit| permit.ok())
+        };
+
+        let Some(_permit) = permit else {
+            record_rejection(&self.name, "bulkhead_full");
+            return Err(reqwest_middleware::Error::middleware(
+                HttpClientError::BulkheadFull(self.name.clone()),
+            ));
+        };
+
+        next.run(req, extensions).await
+    }
+}
+
+/// Prometheus metrics of the named clients.
+struct HttpClientMetrics {
+    requests: IntCounterVec,
+    duration: HistogramVec,
+    retries: IntCounterVec,
+    rejections: IntCounterVec,
+    circuit_state: IntGaugeVec,
+}
+
+impl HttpClientMetrics {
+    fn new(namespace: &str) -> prometheus::Result<Self> {
+        Ok(HttpClientMetrics {
+            requests: IntCounterVec::new(
+                Opts::new("http_client_requests_total", "Outbound HTTP requests.")
+                    .namespace(namespace),
+                &["client", "method", "status"],
+            )?,
+            duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_client_request_duration_seconds",
+                    "Outbound HTTP request duration, including the retries.",
+                )
+                .namespace(namespace),
+                &["client", "method"],
+            )?,
+            retries: IntCounterVec::new(
+                Opts::new(
+                    "http_client_retries_total",
+                    "Outbound HTTP request retries.",
+                )
+                .namespace(namespace),
+                &["client"],
+            )?,
+            rejections: IntCounterVec::new(
+                Opts::new(
+                    "http_client_rejected_calls_total",
+                    "Outbound HTTP calls rejected by the circuit breaker or the bulkhead.",
+                )
+                .namespace(namespace),
+                &["client", "reason"],
+            )?,
+            circuit_state: IntGaugeVec::new(
+                Opts::new(
+                    "http_client_circuit_state",
+                    "Circuit breaker state (0 closed, 1 open, 2 half-open).",
+                )
+                .namespace(namespace),
+                &["client"],
+            )?,
+        })
+    }
+}
+
+/// Increments the rejected calls counter of a named client.
+fn record_rejection(name: &str, reason: &str) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.rejections.with_label_values(&[name, reason]).inc();
+    }
+}
+
+/// Registers the metrics of the named clients in the given registry.
+///
+/// The metrics are shared by all the registries (e.g. the main and the health
+/// servers) and use the namespace of the first registration.
+///
+/// # Errors
+///
+/// Returns a Prometheus error if the metrics cannot be created or registered.
+pub(crate) fn register_metrics(registry: &Registry, namespace: &str) -> prometheus::Result<()> {
+    let metrics = match METRICS.get() {
+        Some(metrics) => metrics,
+        None => {
+            let metrics = HttpClientMetrics::new(namespace)?;
+            METRICS.get_or_init(|| metrics)
+        }
+    };
+
+    registry.register(Box::new(metrics.requests.clone()))?;
+    registry.register(Box::new(metrics.duration.clone()))?;
+    registry.register(Box::new(metrics.retries.clone()))?;
+    registry.register(Box::new(metrics.rejections.clone()))?;
+    registry.register(Box::new(metrics.circuit_state.clone()))?;
+
+    Ok(())
+}
+
+/// A type alias for a `Result` with the `HttpClientError` error type.
+pub type Result<T, E = HttpClientError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum HttpClientError {
+    #[error("Invalid HTTP client configuration: {0}")]
+    Configuration(String),
+
+    #[error("The circuit breaker of the HTTP client {0} is open.")]
+    CircuitOpen(String),
+
+    #[error("The bulkhead of the HTTP client {0} is full.")]
+    BulkheadFull(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_web::{App, HttpResponse, web};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test::mock;
+
+    /// Starts a local token endpoint answering `status` with numbered tokens, and
+    /// returns its URI and its request counter.
+    fn token_endpoint(status: u16, expires_in: u64) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base_uri = mock::serve(move || {
+            let counter = counter.clone();
+            App::new().route(
+                "/token",
+                web::post().to(move || {
+                    let number = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move {
+                        let status = actix_web::http::StatusCode::from_u16(status)
+                            .unwrap_or_else(|e| panic!("{e}"));
+                        HttpResponse::build(status).json(json!({
+                            "access_token": format!("token-{number}"),
+                            "token_type": "Bearer",
+                            "expires_in": expires_in
+                        }))
+                    }
+                }),
+            )
+        });
+        let uri = format!("{base_uri}/token");
+
+        (uri, requests)
+    }
+
+    /// Creates a provider requesting its tokens from `token_uri`.
+    fn provider(token_uri: String) -> ClientCredentialsProvider {
+        ClientCredentialsProvider {
+            client: reqwest::Client::new(),
+            token_uri,
+            client_id: "orders".into(),
+            client_secret: Some("secret".into()),
+            scope: None,
+            token: Mutex::new(None),
+        }
+    }
+
+    #[actix_web::test]
+    async fn should_reuse_the_cached_token() {
+        let (uri, requests) = token_endpoint(200, 3600);
+        let provider = provider(uri);
+
+        let first = provider
+            .access_token()
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+        let second = provider
+            .access_token()
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(first, "token-1");
+        assert_eq!(second, "token-1");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn should_refresh_the_token_before_it_expires() {
+        // A 1 second token is refreshed after half of its lifetime
+        let (uri, requests) = token_endpoint(200, 1);
+        let provider = provider(uri);
+
+        let first = provider
+            .access_token()
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let second = provider
+            .access_token()
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(first, "token-1");
+        assert_eq!(second, "token-2");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn should_fail_when_the_token_endpoint_answers_an_error() {
+        let (uri, requests) = token_endpoint(401, 3600);
+        let provider = provider(uri);
+
+        let result = provider.access_token().await;
+
+        assert!(matches!(result, Err(OAuth2Error::Unauthorized(_))));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(provider.token.lock().await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn should_report_an_unavailable_token_endpoint() {
+        let (uri, _) = token_endpoint(503, 3600);
+        let provider = provider(uri);
+
+        let result = provider.access_token().await;
+
+        assert!(matches!(result, Err(OAuth2Error::Unavailable(_))));
+    }
+
+    #[actix_web::test]
+    async fn should_time_out_when_the_token_endpoint_never_answers() {
+        // Connections are queued by the listener but never answered
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+        let uri = format!(
+            "http://{}/token",
+            listener.local_addr().unwrap_or_else(|e| panic!("{e}"))
+        );
+        let client = serde_json::from_value::<settings::OAuth2Client>(json!({
+            "id": "orders",
+            "connect-timeout": 1,
+            "timeout": 1
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        let provider = ClientCredentialsProvider {
+            client: token_client(&client).unwrap_or_else(|| panic!("token client")),
+            ..provider(uri)
+        };
+
+        let started = Instant::now();
+        let result = provider.access_token().await;
+
+        assert!(matches!(result, Err(OAuth2Error::Unavailable(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(provider.token.lock().await.is_none());
+    }
+
+    #[test]
+    fn should_cap_the_lifetime_of_the_cached_token() {
+        let token = serde_json::from_value::<Token>(json!({
+            "access_token": "token-1",
+            "expires_in": u64::MAX
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let token = CachedToken::new(token).unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(token.is_valid());
+        assert!(token.refresh_at <= Instant::now() + Duration::from_secs(MAX_EXPIRES_IN));
+    }
+
+    #[test]
+    fn should_resolve_paths_against_the_base_url() {
+        let client = HttpClient {
+            name: "orders".into(),
+            base_url: Some("http://orders:8080/api/".into()),
+            client: ClientBuilder::new(reqwest::Client::new()).build(),
+        };
+
+        assert_eq!(client.url("/v1/orders"), "http://orders:8080/api/v1/orders");
+        assert_eq!(client.url("v1/orders"), "http://orders:8080/api/v1/orders");
+        assert_eq!(client.url("https://other/v1"), "https://other/v1");
+    }
+
+    #[test]
+    fn should_cap_the_backoff_for_large_attempts() {
+        let mut config = settings::Retry {
+            enabled: None,
+            max_attempts: None,
+            initial_backoff: Some(100),
+            max_backoff: Some(2000),
+            multiplier: Some(10.0),
+        };
+        let retry = RetryMiddleware::new("orders", &config).unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(retry.backoff(1) <= Duration::from_millis(100));
+        for attempt in [40, 400, u32::MAX] {
+            let delay = retry.backoff(attempt);
+            assert!(delay >= Duration::from_millis(1000));
+            assert!(delay <= Duration::from_millis(2000));
+        }
+
+        config.multiplier = Some(f64::INFINITY);
+        assert!(RetryMiddleware::new("orders", &config).is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_bulkhead_sizes() {
+        let mut config = settings::Bulkhead {
+            enabled: None,
+            max_concurrent_calls: Some(10),
+            max_wait: None,
+        };
+        assert!(BulkheadMiddleware::new("orders", &config).is_ok());
+
+        for invalid in [0, Semaphore::MAX_PERMITS + 1, usize::MAX] {
+            config.max_concurrent_calls = Some(invalid);
+            assert!(matches!(
+                BulkheadMiddleware::new("orders", &config),
+                Err(HttpClientError::Configuration(message)) if message.starts_with("orders: ")
+            ));
+        }
+    }
+
+    #[test]
+    fn should_open_the_circuit_after_consecutive_failures() {
+        let breaker = CircuitBreakerMiddleware::new(
+            "orders",
+            &settings::CircuitBreaker {
+                enabled: None,
+                failure_threshold: Some(2),
+                open_duration: Some(0),
+            },
+        );
+
+        assert!(breaker.try_acquire());
+        breaker.record(true);
+        breaker.record(false);
+        breaker.record(true);
+        assert!(matches!(
+            *breaker.state.lock().unwrap_or_else(|e| e.into_inner()),
+            CircuitState::Closed(1)
+        ));
+
+        breaker.record(true);
+        assert!(matches!(
+            *breaker.state.lock().unwrap_or_else(|e| e.into_inner()),
+            CircuitState::Open(_)
+        ));
+
+        // The open duration is elapsed: a single trial call is allowed
+        assert!(breaker.try_acquire());
+        breaker.record(false);
+        assert_eq!(
+            *breaker.state.lock().unwrap_or_else(|e| e.into_inner()),
+            CircuitState::Closed(0)
+        );
+    }
+
+    #[test]
+    fn should_cap_the_open_duration_of_the_circuit() {
+        let breaker = CircuitBreakerMiddleware::new(
+            "orders",
+            &settings::CircuitBreaker {
+                enabled: None,
+                failure_threshold: Some(1),
+                open_duration: Some(u64::MAX),
+            },
+        );
+
+        assert!(breaker.try_acquire());
+        breaker.record(true);
+        assert!(!breaker.try_acquire());
+        assert_eq!(
+            breaker.open_duration,
+            Duration::from_secs(MAX_OPEN_DURATION)
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! # CORS Module
+//!
+//! This module builds the Cross-Origin Resource Sharing (CORS) policies of the main
+//! server from the `server.cors` and `server.cors-policies` settings.
+//!
+//! ## Responsibilities
+//!
+//! - Apply every field of the CORS settings (origins, methods, headers, exposed
+//!   headers, credentials and preflight max age).
+//! - Match origins exactly, with `*` wildcards (e.g. `https://*.example.com`) or with
+//!   regular expressions (entries starting with `^`).
+//! - Dispatch the requests to the named policy attached to their path, falling back
+//!   to the default `server.cors` policy.
+//!
+//! ## Named Policies
+//!
+//! Each named policy declares the `paths` of the controllers it applies to. A path
+//! matches itself and all its sub-paths (`/v1/partners` matches
+//! `/v1/partners/42`). When several policies match, the longest path wins.
+//!
+//! ```yaml
+//! server:
+//!   cors:
+//!     allowed-origins-pattern: "https://*.example.com"
+//!   cors-policies:
+//!     partners:
+//!       paths: ["/v1/partners"]
+//!       allowed-origins-pattern: "https://portal.partner.com"
+//!       allowed-methods: "GET"
+//! ```
+
+use std::rc::Rc;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+
+use actix_cors::{Cors, CorsMiddleware};
+use actix_web::Error;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::{Method, Uri};
+use regex::Regex;
+use tracing::warn;
+
+use crate::server::LocalBoxFuture;
+use crate::settings::{self, Settings};
+
+/// Methods allowed when `allowed-methods` is not configured.
+const DEFAULT_METHODS: [Method; 7] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::HEAD,
+    Method::OPTIONS,
+];
+
+/// Builds the CORS policies of the main server.
+///
+/// When no CORS configuration is provided, a permissive default policy is applied.
+pub(crate) fn configure_cors(settings: &Settings) -> CorsPolicies {
+    let server = settings.server.as_ref();
+
+    let default = match server.and_then(|s| s.cors.as_ref()) {
+        Some(config) => build_cors(config),
+        None => Cors::permissive(),
+    };
+
+    let mut policies = server
+        .and_then(|s| s.cors_policies.as_ref())
+        .into_iter()
+        .flatten()
+        .flat_map(|(name, policy)| {
+            if policy.paths.is_empty() {
+                warn!("CORS policy {name} has no paths and is ignored.");
+            }
+            let cors = Rc::new(build_cors(&policy.cors));
+            policy
+                .paths
+                .iter()
+                .map(move |path| (path.trim_end_matches('/').to_string(), cors.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    // The most specific path is matched first
+    policies.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+
+    CorsPolicies { default, policies }
+}
+
+/// Builds a CORS policy from its settings.
+///
+/// Invalid origins, methods and headers are logged and ignored, so a typo in the
+/// configuration doesn't prevent the server from starting.
+fn build_cors(config: &settings::Cors) -> Cors {
+    let mut cors = Cors::default();
+
+    // Configure CORS origins
+    if let Some(pattern) = &config.allowed_origins_pattern {
+        let mut patterns = Vec::new();
+
+        for origin in split(pattern) {
+            if origin == "*" {
+                cors = cors.allow_any_origin();
+            } else if origin.starts_with('^') || origin.contains('*') {
+                match origin_regex(origin) {
+                    Ok(regex) => patterns.push(regex),
+                    Err(e) => warn!("Invalid CORS origin pattern {origin}: {e}"),
+                }
+            } else if Uri::from_str(origin).is_ok() {
+                cors = cors.allowed_origin(origin);
+            } else {
+                warn!("Invalid CORS origin {origin}.");
+            }
+        }
+
+        if !patterns.is_empty() {
+            cors = cors.allowed_origin_fn(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| patterns.iter().any(|regex| regex.is_match(origin)))
+            });
+        }
+    }
+
+    // Configure CORS allowed methods
+    match config.allowed_methods.as_deref().map(split) {
+        Some(methods) if methods.contains(&"*") => cors = cors.allow_any_method(),
+        Some(methods) => {
+            cors = cors.allowed_methods(methods.into_iter().filter_map(|method| {
+                Method::from_str(&method.to_uppercase())
+                    .inspect_err(|_| warn!("Invalid CORS method {method}."))
+                    .ok()
+            }))
+        }
+        None => cors = cors.allowed_methods(DEFAULT_METHODS),
+    }
+
+    // Configure CORS allowed headers
+    if let Some(headers) = config.allowed_headers.as_deref().map(split) {
+        if headers.contains(&"*") {
+            cors = cors.allow_any_header();
+        } else {
+            cors = cors.allowed_headers(header_names(headers));
+        }
+    }
+
+    // Configure CORS exposed headers
+    if let Some(headers) = config.exposed_headers.as_deref().map(split) {
+        if headers.contains(&"*") {
+            cors = cors.expose_any_header();
+        } else {
+            cors = cors.expose_headers(header_names(headers));
+        }
+    }
+
+    if config.allow_credentials.unwrap_or(false) {
+        cors = cors.supports_credentials();
+    }
+
+    if let Some(max_age) = config.max_age {
+        cors = cors.max_age(max_age as usize);
+    }
+
+    cors
+}
+
+/// Splits a comma-separated settings value.
+fn split(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Parses the valid header names, logging the invalid ones.
+fn header_names(headers: Vec<&str>) -> Vec<HeaderName> {
+    headers
+        .into_iter()
+        .filter_map(|header| {
+            HeaderName::from_str(header)
+                .inspect_err(|_| warn!("Invalid CORS header {header}."))
+                .ok()
+        })
+        .collect()
+}
+
+/// Compiles an origin pattern.
+///
+/// Patterns starting with `^` are regular expressions. Otherwise, each `*` matches
+/// any sequence of host name characters (letters, digits, `-` and `.`), so
+/// `https://*.example.com` matches the subdomains of `example.com` and
+/// `http://localhost:*` matches any port.
+fn origin_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    if pattern.starts_with('^') {
+        return Regex::new(pattern);
+    }
+
+    let wildcard = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join("[A-Za-z0-9.-]*");
+
+    Regex::new(&format!("^{wildcard}$"))
+}
+
+/// Returns `true` if the request path belongs to the policy path.
+fn matches_path(path: &str, policy_path: &str) -> bool {
+    path.strip_prefix(policy_path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// CORS middleware applying the default policy or the named policy attached to the
+/// request path.
+///
+/// Created by [`create_server_wrappers`](crate::server_wrappers) from the server
+/// settings and applied with `App::wrap`.
+pub struct CorsPolicies {
+    default: Cors,
+    policies: Vec<(String, Rc<Cors>)>,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CorsPolicies
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CorsPoliciesMiddleware<S>;
+    type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let service = SharedService(Rc::new(service));
+
+        let default = self.default.new_transform(service.clone());
+        let policies = self
+            .policies
+            .iter()
+            .map(|(path, cors)| (path.clone(), cors.new_transform(service.clone())))
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            let mut middlewares = Vec::with_capacity(policies.len());
+            for (path, middleware) in policies {
+                middlewares.push((path, middleware.await?));
+            }
+
+            Ok(CorsPoliciesMiddleware {
+                default: default.await?,
+                policies: middlewares,
+            })
+        })
+    }
+}
+
+/// Service created by [`CorsPolicies`].
+pub struct CorsPoliciesMiddleware<S> {
+    default: CorsMiddleware<SharedService<S>>,
+    policies: Vec<(String, CorsMiddleware<SharedService<S>>)>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsPoliciesMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_web::dev::forward_ready!(default);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let middleware = self
+            .policies
+            .iter()
+            .find(|(path, _)| matches_path(req.path(), path))
+            .map_or(&self.default, |(_, middleware)| middleware);
+
+        middleware.call(req)
+    }
+}
+
+/// Service shared by the CORS middlewares of the policies.
+pub struct SharedService<S>(Rc<S>);
+
+impl<S> Clone for SharedService<S> {
+    fn clone(&self) -> Self {
+        SharedService(self.0.clone())
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for SharedService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        self.0.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use actix_web::http::header;
+    use actix_web::test::{TestRequest, call_service, init_service};
+    use actix_web::{App, HttpResponse, web};
+
+    use super::{CorsPolicies, build_cors, matches_path, origin_regex};
+    use crate::settings::Cors;
+
+    fn cors_settings(origins: &str) -> Cors {
+        Cors {
+            max_age: Some(600),
+            allow_credentials: None,
+            allowed_methods: Some("GET".into()),
+            allowed_headers: None,
+            allowed_origins_pattern: Some(origins.into()),
+            exposed_headers: Some("X-Request-Id".into()),
+        }
+    }
+
+    #[test]
+    fn should_match_wildcard_origin_patterns() {
+        let regex = origin_regex("https://*.example.com").ok();
+
+        let matches = |origin: &str| regex.as_ref().is_some_and(|r| r.is_match(origin));
+        assert!(matches("https://app.example.com"));
+        assert!(matches("https://a.b.example.com"));
+        assert!(!matches("https://example.com.evil.io"));
+        assert!(!matches("http://app.example.com"));
+        assert!(!matches("https://evil.io/.example.com"));
+    }
+
+    #[test]
+    fn should_match_policy_paths() {
+        assert!(matches_path("/v1/partners", "/v1/partners"));
+        assert!(matches_path("/v1/partners/42", "/v1/partners"));
+        assert!(!matches_path("/v1/partnership", "/v1/partners"));
+    }
+
+    #[actix_web::test]
+    async fn should_apply_the_policy_attached_to_the_path() {
+        let policies = CorsPolicies {
+            default: build_cors(&cors_settings("https://*.example.com")),
+            policies: vec![(
+                "/v1/partners".into(),
+                Rc::new(build_cors(&cors_settings("https://portal.partner.com"))),
+            )],
+        };
+        let app = init_service(
+            App::new()
+                .wrap(policies)
+                .route("/v1/partners", web::get().to(HttpResponse::Ok))
+                .route("/v1/users", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // Returns the `Access-Control-Allow-Origin` header of a cross-origin request
+        let allowed_origin = async |path: &str, origin: &str| {
+            let request = TestRequest::get()
+                .uri(path)
+                .insert_header((header::ORIGIN, origin))
+                .to_request();
+            call_service(&app, request)
+                .await
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        let partner = "https://portal.partner.com";
+        let app_origin = "https://app.example.com";
+        assert_eq!(
+            allowed_origin("/v1/users", app_origin).await.as_deref(),
+            Some(app_origin)
+        );
+        assert_eq!(allowed_origin("/v1/users", partner).await, None);
+        assert_eq!(
+            allowed_origin("/v1/partners/", partner).await.as_deref(),
+            Some(partner)
+        );
+        assert_eq!(allowed_origin("/v1/partners", app_origin).await, None);
+    }
+}
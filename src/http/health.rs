@@ -1,17 +1,13 @@
-use serde::Serialize;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::OpenApi;
 
-use actix_web::{HttpResponse, get, web::ServiceConfig};
+use actix_web::{
+    HttpResponse, get,
+    web::{Path, ServiceConfig},
+};
 use serde_json::to_string_pretty;
 
-/// Basic health-check response model.
-///
-/// Returned by the `/health` endpoint to indicate the
-/// operational status of the service.
-#[derive(Serialize, ToSchema)]
-struct HealthResponse {
-    status: String,
-}
+use crate::Server;
+use crate::health::{Health, HealthReport, HealthStatus, check, check_group};
 
 /// OpenAPI documentation definition for the service.
 ///
@@ -23,10 +19,11 @@ struct HealthResponse {
         title = "🌐 API Server",
     ),
     paths(
-        health
+        health,
+        health_group
     ),
     components(
-        schemas(HealthResponse)
+        schemas(HealthReport, Health, HealthStatus)
     ),
     tags(
         (name = "⚙️ Rest API", description = "Rest API OpenApi Documentation.")
@@ -36,10 +33,16 @@ pub(crate) struct HealthApiDoc;
 
 /// Health-check endpoint for the API.
 ///
-/// This endpoint provides a simple mechanism for external systems or load
-/// balancers to verify that the server is running correctly. When invoked,
-/// it returns a JSON payload indicating the application's status, along
-/// with an additional custom header.
+/// This endpoint provides a mechanism for external systems or load balancers to
+/// verify that the server and its dependencies are working correctly. It runs the
+/// health indicators (databases, BigQuery, Redis, disk space and the indicators
+/// registered by the application) and returns the aggregated `status` with the
+/// health of each component, according to the `health.show-details` setting.
+///
+/// When the aggregated status is `DOWN` or `OUT_OF_SERVICE`, the endpoint answers
+/// with **HTTP 503 Service Unavailable**. The status is `OUT_OF_SERVICE` while the
+/// server starts and while it drains its requests during a graceful shutdown
+/// (`readiness-state` component).
 ///
 /// # Response
 /// Returns an **HTTP 200 OK** response with:
@@ -50,7 +53,10 @@ pub(crate) struct HealthApiDoc;
 ///
 /// ```json
 /// {
-///   "status": "UP"
+///   "status": "UP",
+///   "components": {
+///     "redis": { "status": "UP" }
+///   }
 /// }
 /// ```
 ///
@@ -71,18 +77,80 @@ pub(crate) struct HealthApiDoc;
     //path = "/actuator/health",
     tag = "✅ Server Health Check",
     responses(
-        (status = 200, description= "API Server Health Check Status", body = HealthResponse),       
+        (status = 200, description= "API Server Health Check Status", body = HealthReport),
+        (status = 503, description= "One or more server components are down", body = HealthReport),
     )
 )]
 #[get("/actuator/health")]
 async fn health() -> HttpResponse {
-    let body = to_string_pretty(&HealthResponse {
-        status: "UP".to_string(),
-    });
-    HttpResponse::Ok()
+    let server = Server::global().ok();
+    let indicators = server
+        .map(|server| server.health_indicators())
+        .unwrap_or_default();
+    let settings = server.and_then(|server| server.settings().health.as_ref());
+
+    health_response(&check(&indicators, settings).await)
+}
+
+/// Health group endpoint, used by the Kubernetes probes.
+///
+/// Aggregates the components of the given health group: `liveness` (the process
+/// is running), `readiness` (the server is started, not draining, and its data
+/// sources are reachable), `startup` (the startup hooks completed) or any group
+/// declared in the `health.groups` settings.
+///
+/// Answers **HTTP 404 Not Found** for an unknown group, and **HTTP 503 Service
+/// Unavailable** when the group status is `DOWN` or `OUT_OF_SERVICE`.
+///
+/// # Example
+/// ```text
+/// GET /actuator/health/readiness
+/// → 503 Service Unavailable
+/// → {
+///     "status": "OUT_OF_SERVICE",
+///     "components": {
+///       "db.primary": { "status": "UP" },
+///       "readiness-state": { "status": "OUT_OF_SERVICE" }
+///     }
+///   }
+/// ```
+#[utoipa::path(
+    tag = "✅ Server Health Check",
+    params(
+        ("group" = String, Path, description = "Health group: liveness, readiness, startup or a configured group."),
+    ),
+    responses(
+        (status = 200, description= "Health group status", body = HealthReport),
+        (status = 404, description= "Unknown health group"),
+        (status = 503, description= "One or more components of the group are down", body = HealthReport),
+    )
+)]
+#[get("/actuator/health/{group}")]
+async fn health_group(group: Path<String>) -> HttpResponse {
+    let server = Server::global().ok();
+    let indicators = server
+        .map(|server| server.health_indicators())
+        .unwrap_or_default();
+    let settings = server.and_then(|server| server.settings().health.as_ref());
+
+    match check_group(&indicators, settings, &group).await {
+        Some(report) => health_response(&report),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Serializes the health report, answering 503 when the status is unavailable.
+fn health_response(report: &HealthReport) -> HttpResponse {
+    let mut response = if report.status.is_unavailable() {
+        HttpResponse::ServiceUnavailable()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    response
         .content_type("application/json")
         .append_header(("api-server", "on-line"))
-        .body(body.unwrap_or_default())
+        .body(to_string_pretty(report).unwrap_or_default())
 }
 
 /// Configures the base server settings by registering core services.
@@ -97,7 +165,7 @@ async fn health() -> HttpResponse {
 ///   routes and services are registered.
 ///
 /// # Behavior
-/// - Registers the `/health` endpoint via the `health` service.
+/// - Registers the `/actuator/health` and `/actuator/health/{group}` endpoints.
 /// - Intended as the foundation for additional globally available routes.
 ///
 /// # Notes
@@ -105,5 +173,5 @@ async fn health() -> HttpResponse {
 /// and should contain only fundamental, globally accessible routes.
 pub(crate) fn configure_server_base(cfg: &mut ServiceConfig) {
     // Add health check endpoint
-    cfg.service(health);
+    cfg.service(health).service(health_group);
 }
@@ -3,7 +3,21 @@
 //!
 //! # Submodules
 //!
+//! - `access_log` — module that provides the access log middleware of the servers.
+//! - `actuator` — module that provides the management endpoints of the health-check server.
+//! - `client` — module that provides the outbound HTTP clients.
+//! - `cors` — module that provides the CORS policies of the main server.
 //! - `health` — module that provides the health check endpoint.
+//! - `request_id` — module that provides the request id middleware of the main server.
+//! - `tls` — module that provides the TLS configuration of the servers.
+//! - `trace` — module that provides the request tracing middleware of the main server.
 //! - `web` — module that provides the web server over HTTP.
+pub mod access_log;
+pub mod actuator;
+pub mod client;
+pub mod cors;
 pub mod health;
+pub mod request_id;
+pub mod tls;
+pub mod trace;
 pub mod web;
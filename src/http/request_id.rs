@@ -0,0 +1,285 @@
+//! # Request ID Module
+//!
+//! This module provides the request id middleware of the main server.
+//!
+//! Each request is identified by the id sent by the caller in the `X-Request-Id`
+//! header, then in the `X-Correlation-Id` header. A random id is generated when
+//! neither is sent or when the value is not a valid id (up to 128 letters, digits,
+//! `-`, `_`, `.` or `:`).
+//!
+//! The id is:
+//!
+//! - available to the handlers through the [`RequestId`] extractor;
+//! - returned in the `X-Request-Id` response header and in the access log;
+//! - added as `request_id` to the JSON error bodies (`4xx` and `5xx` responses);
+//! - reported by the log records written while the request is handled;
+//! - forwarded in the `X-Request-Id` header by the outbound HTTP clients.
+
+use std::fmt::{self, Display};
+use std::future::{Ready, ready};
+
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::{
+        StatusCode,
+        header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue},
+    },
+    middleware::Next,
+};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::logging;
+
+/// Header holding the request id.
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Header holding the correlation id, accepted when `X-Request-Id` is not sent.
+const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Maximum length of an id sent by the caller.
+const MAX_LENGTH: usize = 128;
+
+/// Id of the request being handled.
+///
+/// # Example
+/// ```rust,ignore
+/// use rust_microservice::RequestId;
+///
+/// #[get("/v1/orders")]
+/// async fn orders(request_id: RequestId) -> impl Responder {
+///     info!("Listing orders for request {request_id}.");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Returns the id.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /// Retrieves the id inserted by the request id middleware. Fails with
+    /// `500 Internal Server Error` outside the main server.
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<RequestId>()
+                .cloned()
+                .ok_or_else(|| ErrorInternalServerError("The request id is not set.")),
+        )
+    }
+}
+
+/// Identifies the request, then handles it with its id reported by the log records.
+pub(crate) async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let id = incoming_id(req.headers()).unwrap_or_else(generate_id);
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    match logging::scope_request_id(id.clone(), next.call(req)).await {
+        Ok(response) => {
+            let (request, response) = response.into_parts();
+            let response = with_request_id(response.map_into_boxed_body(), &id);
+            Ok(ServiceResponse::new(request, response))
+        }
+        // The error is converted to a response when the request completes
+        Err(error) => Err(RequestIdError { error, id }.into()),
+    }
+}
+
+/// Error of an inner service, answered with the request id.
+#[derive(Debug, Error)]
+#[error("{error}")]
+struct RequestIdError {
+    error: actix_web::Error,
+    id: String,
+}
+
+impl ResponseError for RequestIdError {
+    fn status_code(&self) -> StatusCode {
+        self.error.as_response_error().status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        with_request_id(self.error.error_response(), &self.id)
+    }
+}
+
+/// Returns the valid id sent by the caller, if any.
+fn incoming_id(headers: &HeaderMap) -> Option<String> {
+    [REQUEST_ID_HEADER, CORRELATION_ID_HEADER]
+        .into_iter()
+        .filter_map(|name| headers.get(name)?.to_str().ok())
+        .map(str::trim)
+        .find(|id| is_valid_id(id))
+        .map(str::to_string)
+}
+
+/// Returns `true` if the id can be written as is in headers and log records.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Generates a random 128-bit id, as 32 hexadecimal digits.
+fn generate_id() -> String {
+    format!("{:032x}", fastrand::u128(..))
+}
+
+/// Adds the `X-Request-Id` header to a response and the `request_id` field to its
+/// JSON error body. Streamed bodies are unchanged.
+fn with_request_id(mut response: HttpResponse, id: &str) -> HttpResponse {
+    if let Ok(value) = HeaderValue::from_str(id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    if !is_json_error(&response) {
+        return response;
+    }
+
+    let (response, body) = response.into_parts();
+    match body.try_into_bytes() {
+        Ok(bytes) => {
+            let bytes = match serde_json::from_slice(&bytes) {
+                Ok(Value::Object(mut object)) => {
+                    object.insert("request_id".into(), id.into());
+                    serde_json::to_vec(&object).map_or(bytes, Into::into)
+                }
+                _ => bytes,
+            };
+            response.set_body(bytes).map_into_boxed_body()
+        }
+        Err(body) => response.set_body(body),
+    }
+}
+
+/// Returns `true` for the error responses with a JSON body.
+fn is_json_error(response: &HttpResponse) -> bool {
+    let status = response.status();
+    (status.is_client_error() || status.is_server_error())
+        && response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| content_type.contains("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::{MessageBody, to_bytes};
+    use actix_web::dev::{ServiceRequest, ServiceResponse};
+    use actix_web::middleware::{Next, from_fn};
+    use actix_web::test::{
+        TestRequest, call_service, init_service, read_body_json, try_call_service,
+    };
+    use actix_web::{App, HttpResponse, web};
+    use serde_json::{Value, json};
+
+    use super::{REQUEST_ID_HEADER, RequestId, request_id};
+    use crate::security::oauth2::OAuth2Error;
+
+    async fn deny(
+        _: ServiceRequest,
+        _: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+        Err::<ServiceResponse, _>(OAuth2Error::MissingToken.into())
+    }
+
+    #[actix_web::test]
+    async fn should_echo_or_generate_request_id() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(request_id))
+                .route(
+                    "/v1/orders",
+                    web::get().to(|id: RequestId| async move { id.to_string() }),
+                )
+                .route(
+                    "/v1/invalid",
+                    web::get().to(|| async {
+                        HttpResponse::BadRequest().json(json!({ "detail": "Invalid order." }))
+                    }),
+                )
+                .service(
+                    web::resource("/v1/secured")
+                        .wrap(from_fn(deny))
+                        .route(web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        // Echoes the correlation id sent by the caller
+        let request = TestRequest::get()
+            .uri("/v1/orders")
+            .insert_header(("X-Correlation-Id", "b7ad6b71"))
+            .to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(
+            response.headers().get(REQUEST_ID_HEADER),
+            Some(&"b7ad6b71".parse().unwrap_or_else(|e| panic!("{e:?}")))
+        );
+
+        // Replaces an invalid id and adds it to the error body
+        let request = TestRequest::get()
+            .uri("/v1/invalid")
+            .insert_header((REQUEST_ID_HEADER, "forged\" id"))
+            .to_request();
+        let response = call_service(&app, request).await;
+        let id = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or_default();
+        assert_eq!(id.len(), 32);
+
+        let body: Value = read_body_json(response).await;
+        assert_eq!(
+            body,
+            json!({ "detail": "Invalid order.", "request_id": id })
+        );
+
+        // Answers the errors of the inner middlewares with the id
+        let request = TestRequest::get()
+            .uri("/v1/secured")
+            .insert_header((REQUEST_ID_HEADER, "b7ad6b71"))
+            .to_request();
+        let response = match try_call_service(&app, request).await {
+            Ok(_) => panic!("The inner middleware should fail."),
+            Err(e) => e.error_response(),
+        };
+        assert_eq!(
+            response.headers().get(REQUEST_ID_HEADER),
+            Some(&"b7ad6b71".parse().unwrap_or_else(|e| panic!("{e:?}")))
+        );
+
+        let body = to_bytes(response.into_body())
+            .await
+            .unwrap_or_else(|e| panic!("{e}"));
+        let body: Value = serde_json::from_slice(&body).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(body["request_id"], "b7ad6b71");
+    }
+}
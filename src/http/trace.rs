@@ -0,0 +1,74 @@
+//! # Trace Module
+//!
+//! This module provides the request tracing middleware of the main server. Each
+//! request runs in a server span named after its method and route, continuing the
+//! trace of the W3C `traceparent` header when the caller sends one.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::HeaderMap,
+    middleware::Next,
+};
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use tracing::{Instrument, field::Empty, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Reads the trace context from the request headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Runs the request in a server span continuing the trace of the caller.
+///
+/// The span records the method, the matched route and the response status, and is
+/// marked as failed on server errors.
+pub(crate) async fn trace_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req.headers()))
+    });
+
+    let method = req.method().to_string();
+    let route = req.match_pattern();
+    let name = match &route {
+        Some(route) => format!("{method} {route}"),
+        None => method.clone(),
+    };
+
+    let span = info_span!(
+        "HTTP request",
+        otel.name = %name,
+        otel.kind = "server",
+        otel.status_code = Empty,
+        http.request.method = %method,
+        http.route = route.as_deref().unwrap_or_default(),
+        url.path = %req.path(),
+        http.response.status_code = Empty,
+    );
+    let _ = span.set_parent(parent);
+
+    let result = next.call(req).instrument(span.clone()).await;
+
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+
+    result
+}
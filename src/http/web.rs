@@ -21,23 +21,36 @@
 //!
 //! `fnconfig`: Optional callback function used to configure the main Actix-Web
 //! `ServiceConfig`, where routes and middleware are attached.
+//!
+//! `lifecycle`: Application hooks run before the listeners open, once the servers
+//! are ready and during the graceful shutdown.
 
+use crate::http::access_log::configure_access_log;
+use crate::http::actuator::{ActuatorApiDoc, configure_actuator};
+use crate::http::client;
+use crate::http::cors::{CorsPolicies, configure_cors};
 use crate::http::health::{HealthApiDoc, configure_server_base};
-use crate::metrics::SysInfoCollector;
+use crate::http::request_id::request_id;
+use crate::http::tls::{capture_peer_certificate, configure_tls};
+use crate::http::trace::trace_request;
+use crate::lifecycle::{self, LifecycleHooks, LifecycleState, Phase};
+use crate::metrics::{DEFAULT_SAMPLING_INTERVAL, SysInfoCollector};
 use crate::settings::Settings;
-use actix_cors::Cors;
-use actix_web::middleware::Condition;
+use crate::telemetry;
+use actix_web::dev::Server;
+use actix_web::middleware::{Condition, from_fn};
 use actix_web::web::ServiceConfig;
-use actix_web::{App, HttpServer, middleware::Logger};
+use actix_web::{App, HttpServer};
 use actix_web_prom::{PrometheusMetrics, PrometheusMetricsBuilder};
 use colored::Colorize;
 use compose_rs::{Compose, ComposeCommand};
 use prometheus::Registry;
 use prometheus::process_collector::ProcessCollector;
+use std::time::Duration;
 use thiserror::Error;
 use utoipa::OpenApi;
 
-use tokio::join;
+use tokio::{join, select};
 use utoipa_swagger_ui::{Config, SwaggerUi};
 
 /// Initializes and starts the main API server and the health-check server.
@@ -53,6 +66,7 @@ use utoipa_swagger_ui::{Config, SwaggerUi};
 ///   optional Docker Compose usage.
 /// - `fnconfig`: Optional callback function used to configure the main
 ///   Actix-Web [`ServiceConfig`], where routes and middleware are attached.
+/// - `lifecycle`: Application hooks run at the server lifecycle transitions.
 ///
 /// # Returns
 /// Returns an `io::Result` containing:
@@ -69,14 +83,23 @@ use utoipa_swagger_ui::{Config, SwaggerUi};
 ///   1. **Main API Server**
 ///      - Uses the host and port defined in the settings.
 ///      - Applies CORS configuration.
+///      - Runs each request in a trace span when `tracing` is configured.
 ///      - Invokes the user-supplied `fnconfig` callback to register routes.
 ///   2. **Health Check Server**
 ///      - Runs independently with a dedicated port.
-///      - Registers only the base server configuration (e.g., `/health`).
+///      - Registers the base server configuration (e.g., `/actuator/health`) and
+///        the management endpoints (`/actuator/info`, `env`, `configprops`).
+/// - Both servers listen over HTTPS when `server.tls` is configured (the
+///   health-check server unless `tls.health-check` is `false`).
 ///
+/// - Runs the `startup` hooks before opening the listeners and the `ready` hooks
+///   once both servers accept requests.
 /// - Applies worker thread settings if provided.
 /// - Prints the listening ports to the console.
 /// - Awaits both the main server and the health server concurrently.
+/// - Handles `SIGTERM` and `Ctrl+C` with a graceful shutdown: reports not ready,
+///   waits the drain period, stops the main server, runs the `shutdown` hooks
+///   and stops the health server.
 ///
 /// # Notes
 /// - This function blocks the current thread until both servers shut down.
@@ -85,6 +108,7 @@ use utoipa_swagger_ui::{Config, SwaggerUi};
 pub(crate) async fn bootstrap_server(
     settings: &Settings,
     fnconfig: Option<fn(&mut ServiceConfig)>,
+    lifecycle: &LifecycleHooks,
 ) -> Result<Option<Compose>> {
     let server_config = settings
         .server
@@ -107,6 +131,33 @@ pub(crate) async fn bootstrap_server(
         .clone()
         .ok_or_else(|| HttpServerError::Configuration("Missing server host.".into()))?;
 
+    // Configure TLS (HTTPS listeners)
+    let tls = configure_tls(settings).map_err(|e| HttpServerError::Configuration(e.to_string()))?;
+    let (main_tls, health_tls) = match tls {
+        Some(tls) => (Some(tls.main), tls.health),
+        None => (None, None),
+    };
+
+    // Install the OpenTelemetry trace pipeline
+    telemetry::init(settings).map_err(|e| HttpServerError::Configuration(e.to_string()))?;
+    let tracing_enabled = telemetry::is_enabled(settings);
+
+    // Configure the access log of both servers
+    let access_log = configure_access_log(settings)
+        .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
+    let main_access_log = access_log.clone();
+
+    // Graceful shutdown timings
+    let shutdown = server_config.shutdown.as_ref();
+    let drain_period = Duration::from_secs(shutdown.and_then(|s| s.drain_period).unwrap_or(0));
+    let shutdown_timeout = shutdown.and_then(|s| s.timeout).unwrap_or(60);
+
+    // Run the application startup hooks
+    lifecycle
+        .run(Phase::Startup)
+        .await
+        .map_err(|e| HttpServerError::Bootstrap(e.to_string()))?;
+
     // Configure Prometheus Metrics
     let (health_metrics_enabled, prometheus_health) = configure_prometheus(settings, true)?;
     let (metrics_enabled, prometheus) = configure_prometheus(settings, false)?;
@@ -123,22 +174,35 @@ pub(crate) async fn bootstrap_server(
         // Create the Prometheus condition based on settings
         let metrics_condition = Condition::new(metrics_enabled, prometheus.clone());
 
+        // Create the request tracing condition based on settings
+        let tracing_condition = Condition::new(tracing_enabled, from_fn(trace_request));
+
         App::new()
             .wrap(cors_config)
             .wrap(metrics_condition)
-            .wrap(Logger::default())
+            .wrap(main_access_log.clone())
+            .wrap(from_fn(request_id))
+            .wrap(tracing_condition)
             .configure(fnconfig.unwrap_or(|_| {}))
     })
-    .bind((host.clone(), server_config.port))
+    .on_connect(capture_peer_certificate);
+    main_server_builder = match main_tls {
+        Some(config) => {
+            main_server_builder.bind_rustls_0_23((host.clone(), server_config.port), config)
+        }
+        None => main_server_builder.bind((host.clone(), server_config.port)),
+    }
     .map_err(|e| HttpServerError::Bootstrap(e.to_string()))?
     .workers(server_config.workers.unwrap_or(num_threads))
-    .shutdown_timeout(60);
+    .shutdown_timeout(shutdown_timeout)
+    .disable_signals();
 
     // Configure the Health Server
     //
     let mut health_server_builder = HttpServer::new(move || {
         // Configure OpenApi Doc
-        let health_openapi = HealthApiDoc::openapi();
+        let mut health_openapi = HealthApiDoc::openapi();
+        health_openapi.merge(ActuatorApiDoc::openapi());
 
         // Create the Prometheus condition based on settings
         let metrics_condition = Condition::new(health_metrics_enabled, prometheus_health.clone());
@@ -146,17 +210,25 @@ pub(crate) async fn bootstrap_server(
         // Create the Health Check and Metrics Server App
         App::new()
             .wrap(metrics_condition)
+            .wrap(access_log.clone())
             .configure(configure_server_base)
+            .configure(configure_actuator)
             .service(
                 SwaggerUi::new("/actuator/swagger-ui/{_:.*}")
                     .url("/actuator/api-docs/openapi.json", health_openapi)
                     .config(Config::default().validator_url("none")),
             )
-    })
-    .bind((host, server_config.health_check_port))
+    });
+    health_server_builder = match health_tls {
+        Some(config) => {
+            health_server_builder.bind_rustls_0_23((host, server_config.health_check_port), config)
+        }
+        None => health_server_builder.bind((host, server_config.health_check_port)),
+    }
     .map_err(|e| HttpServerError::Bootstrap(e.to_string()))?
     .workers(server_config.health_check_workers.unwrap_or(num_threads))
-    .shutdown_timeout(60);
+    .shutdown_timeout(shutdown_timeout)
+    .disable_signals();
 
     // Configure server workers if provided
     if let Some(workers) = settings.server.as_ref().and_then(|s| s.workers) {
@@ -174,12 +246,74 @@ pub(crate) async fn bootstrap_server(
         server_config.health_check_port.to_string().bright_blue()
     );
 
-    let (_, _) = join!(health_server, main_server);
+    graceful_shutdown(lifecycle.clone(), drain_period, main_server, health_server).await;
 
     Ok(compose)
     //})
 }
 
+/// Drives the lifecycle of the running servers.
+///
+/// Runs the `ready` hooks, then waits for a shutdown signal or for one of the
+/// servers to stop on its own. On a shutdown signal, the server reports not ready
+/// for the drain period before the main server stops accepting connections and
+/// completes its in-flight requests. The `shutdown` hooks then run and the
+/// health-check server is stopped last, so probes see the server draining. When a
+/// server stops on its own, the other one is stopped without draining.
+async fn graceful_shutdown(
+    lifecycle: LifecycleHooks,
+    drain_period: Duration,
+    mut main_server: Server,
+    mut health_server: Server,
+) {
+    let main_handle = main_server.handle();
+    let health_handle = health_server.handle();
+
+    let signal = async {
+        lifecycle::set_state(LifecycleState::Ready);
+        let _ = lifecycle.run(Phase::Ready).await;
+        lifecycle::shutdown_signal().await;
+    };
+
+    let (main_running, health_running) = select! {
+        _ = signal => (true, true),
+        result = &mut main_server => {
+            log_server_stopped("main", result);
+            (false, true)
+        }
+        result = &mut health_server => {
+            log_server_stopped("health-check", result);
+            (true, false)
+        }
+    };
+
+    lifecycle::set_state(LifecycleState::Draining);
+    if main_running && health_running {
+        tracing::info!(
+            "{} {}s.",
+            "Shutdown requested. Draining requests for".bright_green(),
+            drain_period.as_secs().to_string().bright_blue()
+        );
+        tokio::time::sleep(drain_period).await;
+    }
+
+    if main_running {
+        let (_, _) = join!(main_handle.stop(true), &mut main_server);
+    }
+    let _ = lifecycle.run(Phase::Shutdown).await;
+    if health_running {
+        let (_, _) = join!(health_handle.stop(true), &mut health_server);
+    }
+}
+
+/// Logs a server that stopped without a shutdown signal.
+fn log_server_stopped(name: &str, result: std::io::Result<()>) {
+    match result {
+        Ok(()) => tracing::warn!("The {name} server stopped. Shutting down."),
+        Err(e) => tracing::error!("The {name} server failed: {e}. Shutting down."),
+    }
+}
+
 /// Configures a Prometheus metrics collector based on the provided settings.
 ///
 /// This function takes the application settings as input and returns a tuple
@@ -205,8 +339,12 @@ fn configure_prometheus(settings: &Settings, base: bool) -> Result<(bool, Promet
         .and_then(|m| m.app_name.clone())
         .unwrap_or_else(|| "api".to_string());
 
+    let sampling_interval = metrics_cfg
+        .and_then(|m| m.sampling_interval)
+        .map_or(DEFAULT_SAMPLING_INTERVAL, Duration::from_secs);
+
     // Metrics registry
-    let registry = build_metrics_registry(&metrics_app_name)?;
+    let registry = build_metrics_registry(&metrics_app_name, sampling_interval)?;
 
     let endpoint = if base {
         "/actuator/metrics"
@@ -230,13 +368,15 @@ fn configure_prometheus(settings: &Settings, base: bool) -> Result<(bool, Promet
 /// SysInfoCollector. The ProcessCollector is used to expose process
 /// metrics, such as memory and CPU usage. The SysInfoCollector is used
 /// to expose system metrics, such as CPU count, memory usage, and
-/// network connections.
+/// network connections. The metrics of the named outbound HTTP clients
+/// are registered as well. The system metrics are sampled in the
+/// background at the given interval.
 ///
 /// # Errors
 ///
-/// This function will return an error if either the ProcessCollector or
-/// SysInfoCollector cannot be registered with the registry.
-fn build_metrics_registry(app_name: &str) -> Result<Registry> {
+/// This function will return an error if any of the collectors cannot be
+/// registered with the registry.
+fn build_metrics_registry(app_name: &str, sampling_interval: Duration) -> Result<Registry> {
     let pid = std::process::id() as i32;
     let registry = Registry::default();
 
@@ -244,13 +384,17 @@ fn build_metrics_registry(app_name: &str) -> Result<Registry> {
         .register(Box::new(ProcessCollector::new(pid, app_name.to_string())))
         .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
 
-    let collector = SysInfoCollector::with_process_and_namespace(pid, app_name.to_string())
-        .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
+    let collector =
+        SysInfoCollector::with_process_and_namespace(pid, app_name.to_string(), sampling_interval)
+            .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
 
     registry
         .register(Box::new(collector))
         .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
 
+    client::register_metrics(&registry, app_name)
+        .map_err(|e| HttpServerError::Configuration(e.to_string()))?;
+
     Ok(registry)
 }
 
@@ -371,54 +515,6 @@ fn log_compose_status(compose: &Compose) {
     }
 }
 
-/// Builds and returns a CORS configuration based on the server settings.
-///
-/// This function reads the CORS options defined in the application
-/// configuration and applies rules for allowed origins and headers.
-/// When no CORS configuration is provided, a permissive default policy
-/// is applied.
-///
-/// # Parameters
-/// - `settings`: Reference to the application settings used to load
-///   CORS rules.
-///
-/// # Returns
-/// A configured [`Cors`] instance ready to be applied to an Actix-Web
-/// application.
-fn configure_cors(settings: &Settings) -> Cors {
-    if let Some(cors_config) = settings.server.as_ref().and_then(|sc| sc.cors.as_ref()) {
-        let mut cors = Cors::default();
-
-        // Configure CORS origins
-        if let Some(pattern) = &cors_config.allowed_origins_pattern {
-            let origins = pattern.split(',').collect::<Vec<&str>>();
-            if origins.len() == 1 && origins[0].trim() == "*" {
-                cors = cors.allow_any_origin();
-            } else {
-                for origin in origins {
-                    cors = cors.allowed_origin(origin.trim());
-                }
-            }
-        };
-
-        // Configure CORS Allowed Headers
-        if let Some(allowed_headers) = &cors_config.allowed_headers {
-            let headers = allowed_headers.split(',').collect::<Vec<&str>>();
-            if headers.len() == 1 && headers[0].trim() == "*" {
-                cors = cors.allow_any_header()
-            } else {
-                for header in headers {
-                    cors = cors.allowed_header(header.trim());
-                }
-            }
-        }
-
-        cors
-    } else {
-        Cors::permissive()
-    }
-}
-
 /// Represents the middleware wrappers applied to the HTTP server.
 ///
 /// This structure groups cross-cutting concerns that are attached to the
@@ -433,7 +529,8 @@ fn configure_cors(settings: &Settings) -> Cors {
 ///   Configuration and instance responsible for exporting Prometheus metrics.
 ///
 /// - `cors`:
-///   Cross-Origin Resource Sharing (CORS) configuration applied to incoming requests.
+///   Cross-Origin Resource Sharing (CORS) policies applied to incoming requests (the
+///   default policy and the named policies attached to specific paths).
 ///
 /// # Usage
 ///
@@ -456,7 +553,7 @@ fn configure_cors(settings: &Settings) -> Cors {
 pub struct ServerWrappers {
     pub metrics_enabled: bool,
     pub prometheus: PrometheusMetrics,
-    pub cors: Cors,
+    pub cors: CorsPolicies,
 }
 
 /// Creates a `ServerWrappers` instance from the given `Settings`.
@@ -501,3 +598,44 @@ pub enum HttpServerError {
     #[error("Error initializing the HTTP server: {0}")]
     Bootstrap(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use actix_web::App;
+
+    use super::graceful_shutdown;
+    use crate::lifecycle::{LifecycleHooks, Phase};
+    use crate::test::mock;
+
+    #[actix_web::test]
+    async fn should_shut_down_when_a_server_stops_on_its_own() {
+        let (_, main_server) = mock::bind(App::new);
+        let (_, health_server) = mock::bind(App::new);
+        let main_handle = main_server.handle();
+        let shutdowns = Arc::new(AtomicUsize::new(0));
+
+        let mut lifecycle = LifecycleHooks::default();
+        lifecycle.register(Phase::Ready, move || {
+            let main_handle = main_handle.clone();
+            async move {
+                main_handle.stop(false).await;
+                Ok::<_, String>(())
+            }
+        });
+        let counter = shutdowns.clone();
+        lifecycle.register(Phase::Shutdown, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok::<_, String>(()) }
+        });
+
+        let shutdown = graceful_shutdown(lifecycle, Duration::ZERO, main_server, health_server);
+        let result = tokio::time::timeout(Duration::from_secs(10), shutdown).await;
+
+        assert!(result.is_ok());
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    }
+}
@@ -72,21 +72,41 @@
 
 mod cmd;
 mod data;
+mod health;
 mod http;
+mod lifecycle;
+mod logging;
 mod metrics;
 mod security;
 mod server;
 pub mod settings;
+mod telemetry;
 pub mod test;
 
 #[derive(rust_embed::Embed)]
 #[folder = "assets"]
 pub(crate) struct Asset;
 
+pub use health::Health;
+pub use health::HealthIndicator;
+pub use health::HealthStatus;
+pub use http::client::BearerPropagationMiddleware;
+pub use http::client::ClientCredentialsMiddleware;
+pub use http::client::ClientCredentialsProvider;
+pub use http::client::HttpClient;
+pub use http::client::HttpClientError;
+pub use http::client::HttpClientRegistry;
+pub use http::client::RequestIdPropagationMiddleware;
+pub use http::client::TokenExchangeMiddleware;
+pub use http::client::TokenExchangeProvider;
+pub use http::cors::CorsPolicies;
+pub use http::request_id::RequestId;
 pub use http::web::ServerWrappers;
 pub use http::web::create_server_wrappers as server_wrappers;
+pub use security::oauth2::AuthenticatedUser;
 pub use security::oauth2::LoginForm;
 pub use security::oauth2::Token;
+pub use server::LocalBoxFuture;
 pub use server::Result;
 pub use server::Server;
 pub use server::ServerError;
@@ -245,6 +265,50 @@ pub use rust_microservice_macros::database;
 ///
 /// `authorize = "hasAllRoles(ROLE_ADMIN, ROLE_USER)"`
 ///
+/// 4. `Expressions`: combines the functions below with `and` / `&&`, `or` / `||`,
+///    `not` / `!` and parentheses.
+///
+/// | Function                  | Description                                             |
+/// | ------------------------- | ------------------------------------------------------- |
+/// | `hasRole(role)`           | The user has the role.                                  |
+/// | `hasAnyRole(role, ...)`   | The user has at least one of the roles.                 |
+/// | `hasAllRoles(role, ...)`  | The user has all the roles.                             |
+/// | `hasScope(scope)`         | The token was granted the scope.                        |
+/// | `hasAnyScope(scope, ...)` | The token was granted at least one of the scopes.       |
+/// | `hasClaim(name)`          | The token has the claim.                                |
+/// | `hasClaim(name, value)`   | The claim is equal to (or, for arrays, contains) value. |
+/// | `isAuthenticated()`       | Any valid token.                                        |
+/// | `permitAll()`             | Public endpoint. A token is optional, never rejected.   |
+/// | `denyAll()`               | Always denied.                                          |
+///
+/// `authorize = "hasRole(ROLE_ADMIN) or (hasScope('read:users') and hasClaim('tenant', 'acme'))"`
+///
+/// The expression is validated at compile time: an unknown function, a wrong number
+/// of arguments or a syntax error fails the build.
+///
+/// ```compile_fail
+/// use rust_microservice::secured;
+/// use actix_web::{HttpResponse, get};
+///
+/// #[secured(method = "get", path = "/v1/user", authorize = "hasRol(ROLE_ADMIN)")]
+/// pub async fn list_users_endpoint() -> HttpResponse {
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+///
+/// ### **Authenticated user**
+///
+/// After the token is validated, the middleware stores the decoded claims in the
+/// request extensions. Handlers can receive them by declaring an
+/// [`AuthenticatedUser`] parameter, which exposes the subject, roles, scopes and
+/// the raw claim map. While the handler runs, the user is also returned by
+/// [`AuthenticatedUser::current`], which the named HTTP clients use to forward or
+/// exchange its bearer token (`authentication: propagate` or `token-exchange`).
+///
+/// Endpoints granted to everyone (e.g. `permitAll()`) never reject the request. A
+/// valid token, when sent, still exposes the user, so these handlers should declare
+/// an `Option<AuthenticatedUser>` parameter.
+///
 /// ## Examples
 ///
 /// ### **`Single role`**:
@@ -293,4 +357,29 @@ pub use rust_microservice_macros::database;
 ///    HttpResponse::Ok().finish()
 /// }
 /// ```
+///
+/// ### **`Authenticated user`**:
+///
+/// ```no_run
+/// use rust_microservice::{AuthenticatedUser, secured};
+/// use actix_web::{HttpResponse, get};
+///
+/// #[secured(method = "get", path = "/v1/me", authorize = "ROLE_USER")]
+/// pub async fn me_endpoint(user: AuthenticatedUser) -> HttpResponse {
+///     HttpResponse::Ok().body(user.sub.unwrap_or_default())
+/// }
+/// ```
+///
+/// ### **`Optional user`**:
+///
+/// ```no_run
+/// use rust_microservice::{AuthenticatedUser, secured};
+/// use actix_web::{HttpResponse, get};
+///
+/// #[secured(method = "get", path = "/v1/greeting", authorize = "permitAll()")]
+/// pub async fn greeting_endpoint(user: Option<AuthenticatedUser>) -> HttpResponse {
+///     let name = user.and_then(|user| user.sub).unwrap_or("anonymous".into());
+///     HttpResponse::Ok().body(format!("Hello, {name}!"))
+/// }
+/// ```
 pub use rust_microservice_macros::secured;
@@ -0,0 +1,244 @@
+//! # Lifecycle Module
+//!
+//! This module runs the application hooks registered on the [`Server`](crate::Server)
+//! at the lifecycle transitions, and keeps the lifecycle state reported by the
+//! health endpoint.
+//!
+//! ## Phases
+//!
+//! | Phase      | Registration  | When                                                                   |
+//! | ---------- | ------------- | ---------------------------------------------------------------------- |
+//! | `startup`  | `on_startup`  | Before the listeners are opened. A failing hook aborts the startup.    |
+//! | `ready`    | `on_ready`    | Once both servers accept requests.                                     |
+//! | `shutdown` | `on_shutdown` | After the in-flight requests completed, before the data sources close. |
+//!
+//! The hooks of a phase run sequentially, in registration order. Failures of the
+//! `ready` and `shutdown` hooks are logged and the next hooks still run.
+//!
+//! ## Shutdown Sequence
+//!
+//! 1. `SIGTERM` or `Ctrl+C` is received and the state becomes `Draining`: the
+//!    readiness probe answers `OUT_OF_SERVICE`.
+//! 2. After `server.shutdown.drain-period`, the main server stops accepting
+//!    connections and waits up to `server.shutdown.timeout` for the in-flight requests.
+//! 3. The `on_shutdown` hooks run, the health-check server stops and the data sources
+//!    are closed.
+//!
+//! When one of the servers stops on its own, the other one is stopped without
+//! draining and the `on_shutdown` hooks still run.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::server::LocalBoxFuture;
+
+/// Current lifecycle state of the server.
+static STATE: AtomicU8 = AtomicU8::new(LifecycleState::Starting as u8);
+
+/// A type-erased lifecycle hook.
+type Hook = Arc<dyn Fn() -> LocalBoxFuture<'static, std::result::Result<(), String>> + Send + Sync>;
+
+/// Lifecycle phases running application hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Startup,
+    Ready,
+    Shutdown,
+}
+
+impl Phase {
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Startup => "startup",
+            Phase::Ready => "ready",
+            Phase::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// Lifecycle state of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum LifecycleState {
+    /// The startup hooks are running or the listeners are not open yet.
+    Starting = 0,
+
+    /// The servers accept requests.
+    Ready = 1,
+
+    /// A shutdown was requested: the server reports not ready and drains its requests.
+    Draining = 2,
+
+    /// The servers are stopped.
+    Stopped = 3,
+}
+
+/// Returns the current lifecycle state of the server.
+pub(crate) fn state() -> LifecycleState {
+    match STATE.load(Ordering::Acquire) {
+        0 => LifecycleState::Starting,
+        1 => LifecycleState::Ready,
+        2 => LifecycleState::Draining,
+        _ => LifecycleState::Stopped,
+    }
+}
+
+/// Sets the lifecycle state of the server.
+pub(crate) fn set_state(state: LifecycleState) {
+    STATE.store(state as u8, Ordering::Release);
+}
+
+/// Hooks registered by the application, per lifecycle phase.
+#[derive(Clone, Default)]
+pub(crate) struct LifecycleHooks {
+    startup: Vec<Hook>,
+    ready: Vec<Hook>,
+    shutdown: Vec<Hook>,
+}
+
+impl LifecycleHooks {
+    /// Registers an asynchronous hook run at the given phase.
+    pub(crate) fn register<F, Fut, E>(&mut self, phase: Phase, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<(), E>> + 'static,
+        E: Display,
+    {
+        let hook: Hook = Arc::new(move || {
+            let future = hook();
+            Box::pin(async move { future.await.map_err(|e| e.to_string()) })
+        });
+
+        match phase {
+            Phase::Startup => self.startup.push(hook),
+            Phase::Ready => self.ready.push(hook),
+            Phase::Shutdown => self.shutdown.push(hook),
+        }
+    }
+
+    /// Runs the hooks of the given phase in registration order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first failing `startup` hook, the remaining ones are
+    /// skipped. Failures of the other phases are logged and never returned.
+    pub(crate) async fn run(&self, phase: Phase) -> Result<()> {
+        let hooks = match phase {
+            Phase::Startup => &self.startup,
+            Phase::Ready => &self.ready,
+            Phase::Shutdown => &self.shutdown,
+        };
+
+        if !hooks.is_empty() {
+            info!("Running {} {} hook(s).", hooks.len(), phase.name());
+        }
+
+        for hook in hooks {
+            if let Err(message) = hook().await {
+                let error = LifecycleError::Hook(phase.name(), message);
+                if phase == Phase::Startup {
+                    return Err(error);
+                }
+                warn!("{error}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Completes when the process receives `SIGTERM` or `Ctrl+C` (`SIGINT`).
+pub(crate) async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+                return;
+            }
+            Err(e) => warn!("Unable to listen for SIGTERM: {e}"),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        // Never shut down on a signal registration failure
+        warn!("Unable to listen for Ctrl+C: {e}");
+        std::future::pending::<()>().await;
+    }
+}
+
+/// A type alias for a `Result` with the `LifecycleError` error type.
+pub type Result<T, E = LifecycleError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum LifecycleError {
+    #[error("The {0} hook failed: {1}")]
+    Hook(&'static str, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::{LifecycleHooks, Phase};
+
+    #[tokio::test]
+    async fn should_run_hooks_in_registration_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut hooks = LifecycleHooks::default();
+        for name in ["first", "failing", "last"] {
+            let calls = calls.clone();
+            hooks.register(Phase::Shutdown, move || {
+                let calls = calls.clone();
+                async move {
+                    calls.lock().unwrap_or_else(|e| e.into_inner()).push(name);
+                    if name == "failing" {
+                        Err("flush failed")
+                    } else {
+                        Ok(())
+                    }
+                }
+            });
+        }
+
+        assert!(hooks.run(Phase::Shutdown).await.is_ok());
+        assert_eq!(
+            *calls.lock().unwrap_or_else(|e| e.into_inner()),
+            vec!["first", "failing", "last"]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_abort_startup_on_failing_hook() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut hooks = LifecycleHooks::default();
+        hooks.register(Phase::Startup, || async { Err("not ready") });
+        let after = count.clone();
+        hooks.register(Phase::Startup, move || {
+            let after = after.clone();
+            async move {
+                after.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, String>(())
+            }
+        });
+
+        let result = hooks.run(Phase::Startup).await;
+
+        assert_eq!(
+            result.err().map(|e| e.to_string()),
+            Some("The startup hook failed: not ready".into())
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,602 @@
+//! # Logging Module
+//!
+//! This module installs the process logger and changes its log levels at runtime,
+//! through the `/actuator/loggers` endpoint, without restarting the server.
+//!
+//! The initial levels come from the `RUST_LOG` environment variable (defaults to
+//! `info,actix_web=error,actix_web_prom=error`). A level set at runtime overrides
+//! the `RUST_LOG` directive of the same logger until it is reset. The `ROOT` logger
+//! holds the level of the modules without a more specific directive.
+//!
+//! Loggers are module paths, matched by prefix as in `RUST_LOG`: the level of
+//! `sea_orm` applies to `sea_orm::driver` unless it has its own level.
+//!
+//! ## Formats
+//!
+//! The `logging.format` setting selects the `pretty` output (colored, aligned
+//! columns) or the `json` output (one object per line, for log aggregators):
+//!
+//! ```json
+//! {"timestamp":"2026-10-16T08:12:00.123+00:00","level":"INFO","target":"orders::api","message":"Order created.","service":"orders","trace_id":"4bf92f3577b34da6a3ce929d0e0e4736","span_id":"00f067aa0ba902b7","request_id":"b7ad6b71"}
+//! ```
+//!
+//! `trace_id` and `span_id` are set inside a span exported with OpenTelemetry, and
+//! `request_id` while a request of the main server is handled. The fields of the
+//! JSON access log entries are added to their records. The colors are
+//! removed from the JSON messages, and from the `pretty` output when the standard
+//! error is not a terminal.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use chrono::SecondsFormat;
+use colored::Colorize;
+use env_logger::fmt::Formatter;
+use env_logger::{Builder, Env, Logger};
+use log::{LevelFilter, Log, Metadata, Record, warn};
+use opentelemetry::trace::TraceContextExt;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utoipa::ToSchema;
+
+use crate::http::access_log::ACCESS_LOG_TARGET;
+use crate::settings::Settings;
+
+/// Name of the logger holding the default level.
+pub(crate) const ROOT: &str = "ROOT";
+
+/// Environment variable holding the initial log directives.
+const FILTER_ENV: &str = "RUST_LOG";
+
+/// Log directives used when `RUST_LOG` is not set.
+pub(crate) const DEFAULT_FILTER: &str = "info,actix_web=error,actix_web_prom=error";
+
+/// Levels accepted by the loggers, from the least to the most verbose.
+pub(crate) const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+/// The installed process logger.
+static LOGGER: OnceLock<&'static RuntimeLogger> = OnceLock::new();
+
+tokio::task_local! {
+    /// Id of the request handled by the current task.
+    static REQUEST_ID: String;
+}
+
+/// Configured and effective level of a logger.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct LoggerLevels {
+    /// Level set by `RUST_LOG` or at runtime for this exact logger.
+    #[schema(value_type = Option<String>)]
+    pub configured_level: Option<LevelFilter>,
+
+    /// Level applied to the logger, inherited from its closest configured parent.
+    #[schema(value_type = String)]
+    pub effective_level: LevelFilter,
+}
+
+/// Log directives from `RUST_LOG` and the levels set at runtime.
+#[derive(Debug, Default)]
+struct Directives {
+    base: BTreeMap<String, LevelFilter>,
+    overrides: BTreeMap<String, LevelFilter>,
+}
+
+impl Directives {
+    /// Parses `RUST_LOG` style directives (`info,sea_orm=debug`), ignoring the
+    /// `/regex` message filter.
+    fn parse(spec: &str) -> Self {
+        let spec = spec.split('/').next().unwrap_or_default();
+        let base = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .filter_map(|directive| match directive.split_once('=') {
+                Some((name, level)) => Some((name.trim().to_string(), level.trim().parse().ok()?)),
+                None => match directive.parse() {
+                    Ok(level) => Some((ROOT.to_string(), level)),
+                    Err(_) => Some((directive.to_string(), LevelFilter::Trace)),
+                },
+            })
+            .collect();
+
+        Directives {
+            base,
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the level configured for the exact logger, if any.
+    fn configured(&self, name: &str) -> Option<LevelFilter> {
+        self.overrides
+            .get(name)
+            .or_else(|| self.base.get(name))
+            .copied()
+    }
+
+    /// Returns the level of the closest configured logger, then the `ROOT` level.
+    fn effective(&self, name: &str) -> LevelFilter {
+        let parent = self
+            .names()
+            .into_iter()
+            .filter(|parent| *parent != ROOT && name.starts_with(parent))
+            .max_by_key(|parent| parent.len());
+
+        parent
+            .and_then(|parent| self.configured(parent))
+            .or_else(|| self.configured(ROOT))
+            .unwrap_or(if self.base.is_empty() && self.overrides.is_empty() {
+                LevelFilter::Error
+            } else {
+                LevelFilter::Off
+            })
+    }
+
+    /// Returns the names of the configured loggers.
+    fn names(&self) -> BTreeSet<&str> {
+        self.base
+            .keys()
+            .chain(self.overrides.keys())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Returns the levels of a logger.
+    fn levels(&self, name: &str) -> LoggerLevels {
+        LoggerLevels {
+            configured_level: self.configured(name),
+            effective_level: self.effective(name),
+        }
+    }
+}
+
+/// Output options of the log records.
+#[derive(Debug, Clone)]
+struct LogOptions {
+    json: bool,
+    service: Option<String>,
+    module_width: usize,
+}
+
+/// State of the runtime logger.
+struct LoggerState {
+    logger: Logger,
+    directives: Directives,
+    options: LogOptions,
+}
+
+impl LoggerState {
+    /// Rebuilds the `env_logger` from `RUST_LOG`, the runtime levels and the options.
+    fn rebuild(&mut self) {
+        self.logger = build(&self.options, &self.directives.overrides);
+        log::set_max_level(self.logger.filter());
+    }
+}
+
+/// Logger delegating to an `env_logger` rebuilt when a level or the format changes.
+struct RuntimeLogger {
+    state: RwLock<LoggerState>,
+}
+
+impl RuntimeLogger {
+    fn read(&self) -> RwLockReadGuard<'_, LoggerState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, LoggerState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Log for RuntimeLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.read().logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        self.read().logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.read().logger.flush();
+    }
+}
+
+/// Installs the process logger with the `pretty` format.
+///
+/// The module paths are padded to `module_width` characters. Colors are disabled
+/// when the standard error is not a terminal.
+///
+/// # Errors
+///
+/// Returns [`LoggingError::AlreadyInstalled`] if a logger is already installed.
+pub(crate) fn init(module_width: usize) -> Result<()> {
+    if !io::stderr().is_terminal() {
+        colored::control::set_override(false);
+    }
+
+    let options = LogOptions {
+        json: false,
+        service: None,
+        module_width,
+    };
+    let directives =
+        Directives::parse(&std::env::var(FILTER_ENV).unwrap_or_else(|_| DEFAULT_FILTER.into()));
+    let logger = build(&options, &directives.overrides);
+    let max_level = logger.filter();
+
+    // The logger lives for the whole process
+    let runtime: &'static RuntimeLogger = Box::leak(Box::new(RuntimeLogger {
+        state: RwLock::new(LoggerState {
+            logger,
+            directives,
+            options,
+        }),
+    }));
+    log::set_logger(runtime).map_err(|_| LoggingError::AlreadyInstalled)?;
+    log::set_max_level(max_level);
+    let _ = LOGGER.set(runtime);
+
+    Ok(())
+}
+
+/// Applies the `logging` settings to the installed logger.
+///
+/// The settings are ignored, with a warning, when the process logger was
+/// installed by the host application.
+///
+/// # Errors
+///
+/// Returns an error if the `format` or `colors` setting is invalid.
+pub(crate) fn configure(settings: &Settings) -> Result<()> {
+    apply(LOGGER.get().copied(), settings)
+}
+
+/// Applies the `logging` settings to the runtime logger, if it is installed.
+fn apply(logger: Option<&RuntimeLogger>, settings: &Settings) -> Result<()> {
+    let config = settings.logging.as_ref();
+    let json = match config.and_then(|c| c.format.as_deref()).unwrap_or("pretty") {
+        "pretty" => false,
+        "json" => true,
+        format => return Err(LoggingError::Format(format.into())),
+    };
+
+    // JSON records are never colored
+    let colors = match config.and_then(|c| c.colors.as_deref()).unwrap_or("auto") {
+        "auto" => !json && io::stderr().is_terminal(),
+        "always" => !json,
+        "never" => false,
+        colors => return Err(LoggingError::Colors(colors.into())),
+    };
+
+    let Some(logger) = logger else {
+        warn!("A logger is already installed, the logging settings are ignored.");
+        return Ok(());
+    };
+
+    colored::control::set_override(colors);
+    let mut state = logger.write();
+    state.options.json = json;
+    state.options.service = Some(settings.service_name());
+    state.rebuild();
+
+    Ok(())
+}
+
+/// Returns the levels of the configured loggers, `ROOT` included.
+pub(crate) fn loggers() -> Result<BTreeMap<String, LoggerLevels>> {
+    let logger = LOGGER.get().ok_or(LoggingError::NotInstalled)?;
+    let state = logger.read();
+
+    let mut names = state.directives.names();
+    names.insert(ROOT);
+
+    Ok(names
+        .into_iter()
+        .map(|name| (name.to_string(), state.directives.levels(name)))
+        .collect())
+}
+
+/// Returns the levels of a logger, configured or not.
+pub(crate) fn logger(name: &str) -> Result<LoggerLevels> {
+    let logger = LOGGER.get().ok_or(LoggingError::NotInstalled)?;
+    Ok(logger.read().directives.levels(name))
+}
+
+/// Sets the level of a logger, or resets it to its `RUST_LOG` level with `None`.
+pub(crate) fn set_level(name: &str, level: Option<LevelFilter>) -> Result<()> {
+    let logger = LOGGER.get().ok_or(LoggingError::NotInstalled)?;
+    let mut state = logger.write();
+
+    match level {
+        Some(level) => state.directives.overrides.insert(name.to_string(), level),
+        None => state.directives.overrides.remove(name),
+    };
+    state.rebuild();
+
+    Ok(())
+}
+
+/// Runs a future with the request id reported by its log records.
+pub(crate) async fn scope_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Returns the id of the request handled by the current task, if any.
+pub(crate) fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Creates the `env_logger` with the `RUST_LOG` directives, the runtime levels and
+/// the record format.
+fn build(options: &LogOptions, overrides: &BTreeMap<String, LevelFilter>) -> Logger {
+    let mut builder = Builder::from_env(Env::default().default_filter_or(DEFAULT_FILTER));
+
+    let options = options.clone();
+    builder.format(move |buf, record| {
+        if options.json {
+            writeln!(buf, "{}", Value::Object(json_record(record, &options)))
+        } else {
+            write_pretty(buf, record, options.module_width)
+        }
+    });
+
+    for (name, level) in overrides {
+        if name == ROOT {
+            builder.filter_level(*level);
+        } else {
+            builder.filter_module(name, *level);
+        }
+    }
+
+    builder.build()
+}
+
+/// Writes a colored, human-readable record.
+fn write_pretty(buf: &mut Formatter, record: &Record<'_>, module_width: usize) -> io::Result<()> {
+    let level = match record.level() {
+        log::Level::Info => record.level().as_str().bright_green(),
+        log::Level::Debug => record.level().as_str().bright_blue(),
+        log::Level::Trace => record.level().as_str().bright_cyan(),
+        log::Level::Warn => record.level().as_str().bright_yellow(),
+        log::Level::Error => record.level().as_str().bright_red(),
+    };
+
+    let datetime = chrono::Local::now()
+        .format("%d-%m-%YT%H:%M:%S%.3f%:z")
+        .to_string()
+        .white();
+
+    // Align timestamp, level, and module path
+    writeln!(
+        buf,
+        "{:<24}  {:<5} [{:<module_width$}] - {}",
+        datetime,                                         // Timestamp
+        level,                                            // Log level
+        record.module_path().unwrap_or("unknown").blue(), // Module path
+        record.args()                                     // Log message
+    )
+}
+
+/// Creates the JSON object of a record, with the service name, the trace context
+/// and the request id when available.
+fn json_record(record: &Record<'_>, options: &LogOptions) -> Map<String, Value> {
+    let mut entry = Map::new();
+    entry.insert(
+        "timestamp".into(),
+        chrono::Local::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, false)
+            .into(),
+    );
+    entry.insert("level".into(), record.level().as_str().into());
+    entry.insert(
+        "target".into(),
+        record.module_path().unwrap_or(record.target()).into(),
+    );
+    let message = strip_ansi(&record.args().to_string());
+
+    // The JSON access log entries are merged into the record
+    let fields = (record.target() == ACCESS_LOG_TARGET)
+        .then(|| serde_json::from_str::<Map<String, Value>>(&message).ok())
+        .flatten();
+    match fields {
+        Some(fields) => {
+            entry.insert("message".into(), "HTTP request".into());
+            for (name, value) in fields {
+                entry.entry(name).or_insert(value);
+            }
+        }
+        None => {
+            entry.insert("message".into(), message.into());
+        }
+    }
+
+    if let Some(service) = &options.service {
+        entry.insert("service".into(), service.as_str().into());
+    }
+
+    // The trace context of the current span, when exported with OpenTelemetry
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if span_context.is_valid() {
+        entry.insert(
+            "trace_id".into(),
+            span_context.trace_id().to_string().into(),
+        );
+        entry.insert("span_id".into(), span_context.span_id().to_string().into());
+    }
+
+    if let Some(request_id) = current_request_id() {
+        entry.insert("request_id".into(), request_id.into());
+    }
+
+    entry
+}
+
+/// Removes the ANSI escape sequences (colors) from a message.
+fn strip_ansi(message: &str) -> String {
+    let mut stripped = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skips the sequence up to its final byte
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// A type alias for a `Result` with the `LoggingError` error type.
+pub type Result<T, E = LoggingError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Unsupported log format: {0}. Expected pretty or json.")]
+    Format(String),
+
+    #[error("Unsupported log colors: {0}. Expected auto, always or never.")]
+    Colors(String),
+
+    #[error("A logger is already installed.")]
+    AlreadyInstalled,
+
+    #[error("The runtime logger is not installed.")]
+    NotInstalled,
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, LevelFilter, Record};
+    use serde_json::{Value, json};
+
+    use super::{ACCESS_LOG_TARGET, Directives, LogOptions, REQUEST_ID, ROOT, apply, json_record};
+    use crate::settings::Settings;
+
+    #[test]
+    fn should_resolve_effective_level_from_closest_logger() {
+        let mut directives = Directives::parse("info,sea_orm=warn,actix_web=error/timeout");
+        directives
+            .overrides
+            .insert("sea_orm::driver".into(), LevelFilter::Debug);
+
+        assert_eq!(directives.effective(ROOT), LevelFilter::Info);
+        assert_eq!(directives.effective("app::orders"), LevelFilter::Info);
+        assert_eq!(directives.effective("sea_orm::query"), LevelFilter::Warn);
+        assert_eq!(
+            directives.effective("sea_orm::driver::postgres"),
+            LevelFilter::Debug
+        );
+        assert_eq!(directives.configured("sea_orm::query"), None);
+        assert_eq!(directives.configured("actix_web"), Some(LevelFilter::Error));
+    }
+
+    #[test]
+    fn should_ignore_settings_when_another_logger_is_installed() {
+        let settings: Settings = serde_json::from_value(json!({
+            "logging": { "format": "json" }
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert!(apply(None, &settings).is_ok());
+
+        let settings: Settings = serde_json::from_value(json!({
+            "logging": { "format": "xml" }
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert!(apply(None, &settings).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_write_json_record_without_colors() {
+        let options = LogOptions {
+            json: true,
+            service: Some("orders".into()),
+            module_width: 60,
+        };
+
+        let mut entry = REQUEST_ID
+            .scope("b7ad6b71".into(), async {
+                json_record(
+                    &Record::builder()
+                        .args(format_args!("\u{1b}[31mOrder failed.\u{1b}[0m"))
+                        .level(Level::Warn)
+                        .target("orders::api")
+                        .module_path(Some("orders::api"))
+                        .build(),
+                    &options,
+                )
+            })
+            .await;
+
+        assert!(entry.remove("timestamp").is_some());
+        assert_eq!(
+            Value::Object(entry),
+            json!({
+                "level": "WARN",
+                "target": "orders::api",
+                "message": "Order failed.",
+                "service": "orders",
+                "request_id": "b7ad6b71"
+            })
+        );
+    }
+
+    #[test]
+    fn should_merge_only_access_log_entries() {
+        let options = LogOptions {
+            json: true,
+            service: None,
+            module_width: 60,
+        };
+        let record = |target: &str| {
+            let mut entry = json_record(
+                &Record::builder()
+                    .args(format_args!(r#"{{"method":"GET","status":200}}"#))
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+                &options,
+            );
+            entry.remove("timestamp");
+            Value::Object(entry)
+        };
+
+        assert_eq!(
+            record(ACCESS_LOG_TARGET),
+            json!({
+                "level": "INFO",
+                "target": ACCESS_LOG_TARGET,
+                "message": "HTTP request",
+                "method": "GET",
+                "status": 200
+            })
+        );
+        assert_eq!(
+            record("orders::api"),
+            json!({
+                "level": "INFO",
+                "target": "orders::api",
+                "message": r#"{"method":"GET","status":200}"#
+            })
+        );
+    }
+}
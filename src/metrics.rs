@@ -5,52 +5,250 @@
 //!
 //! The metrics collected by this module are:
 //!
-//! - CPU usage percentage
-//! - Memory usage percentage
-//! - Number of open file descriptors
-//! - Number of open network connections
+//! - CPU usage percentage, in total and per core
+//! - System memory usage
+//! - Network received and transmitted bytes per interface
+//! - Process CPU usage (the process memory, file descriptors and threads are exposed by
+//!   the Prometheus `ProcessCollector`)
+//! - Container CPU and memory limits, read from the cgroup (v1 or v2)
 //!
-//! The metrics are exposed as Prometheus counters and can be scraped
-//! by the Prometheus server.
-//!
-//! The module provides a single function, `register_metrics`, which
-//! registers all the necessary metrics with the Prometheus registry.
-//!
-//! The metrics are collected using the `sysinfo` crate, which provides
-//! a safe and easy-to-use API for collecting system metrics.
+//! The values are sampled with the `sysinfo` crate in a background thread, at the
+//! `metrics.sampling-interval`, and the Prometheus scrapes return the last sample
+//! without blocking. The sampler is shared by the collectors of the process and stops
+//! once they are all dropped.
 //!
 //! The metrics are exposed using the `prometheus` crate, which provides
 //! a safe and easy-to-use API for exposing metrics to the Prometheus server.
 
 use prometheus::{
-    IntGauge, IntGaugeVec, Opts,
+    Gauge, IntGauge, IntGaugeVec, Opts,
     core::{Collector, Desc},
     process_collector::pid_t,
     proto,
 };
+use std::fs;
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::thread;
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Networks, RefreshKind, System};
+use std::time::Duration;
+use sysinfo::{
+    CpuRefreshKind, MemoryRefreshKind, Networks, Pid, ProcessRefreshKind, ProcessesToUpdate,
+    RefreshKind, System,
+};
 
 /// Number of metrics exposed by this collector.
-const METRICS_NUMBER: usize = 8;
+const METRICS_NUMBER: usize = 11;
+
+/// Interval between two samples when `metrics.sampling-interval` is not set.
+pub(crate) const DEFAULT_SAMPLING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Root of the cgroup file system.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
 
-/// A Prometheus collector that exposes system CPU information.
+/// Memory limits from this value are reported by cgroup v1 when no limit is set.
+const CGROUP_V1_UNLIMITED: u64 = 1 << 62;
+
+/// Last sample, shared by the collectors of the process.
+static SHARED_SAMPLE: Mutex<Weak<RwLock<Sample>>> = Mutex::new(Weak::new());
+
+/// Values of a sample, returned by the scrapes until the next sample.
+#[derive(Debug, Default, Clone)]
+struct Sample {
+    cpu_count: usize,
+    total_cpu_usage: f32,
+    cpu_usage: Vec<(String, f32)>,
+    networks: Vec<(String, u64, u64)>,
+    system_memory_usage: u64,
+    system_total_memory: u64,
+    process_cpu_usage: Option<f32>,
+    container: ContainerLimits,
+}
+
+/// Resources allowed to the container by its cgroup. `None` when unlimited.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ContainerLimits {
+    /// CPU limit, in cores.
+    cpu: Option<f64>,
+    /// Memory limit, in bytes.
+    memory: Option<u64>,
+}
+
+impl ContainerLimits {
+    /// Reads the limits of the cgroup v2, then of the cgroup v1.
+    fn read() -> Self {
+        let read = |path: &str| fs::read_to_string(format!("{CGROUP_ROOT}/{path}")).ok();
+
+        let cpu = match read("cpu.max") {
+            Some(content) => parse_cpu_max(&content),
+            None => read("cpu/cpu.cfs_quota_us")
+                .zip(read("cpu/cpu.cfs_period_us"))
+                .and_then(|(quota, period)| parse_cfs_quota(&quota, &period)),
+        };
+
+        let memory = read("memory.max")
+            .or_else(|| read("memory/memory.limit_in_bytes"))
+            .and_then(|content| parse_memory_limit(&content));
+
+        ContainerLimits { cpu, memory }
+    }
+}
+
+/// Parses the cgroup v2 `cpu.max` file (`<quota> <period>` or `max <period>`) into
+/// cores.
+fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut values = content.split_whitespace();
+    let quota = values.next()?.parse::<f64>().ok()?;
+    let period = values.next()?.parse::<f64>().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Parses the cgroup v1 CFS quota and period into cores. A negative quota means
+/// no limit.
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota = quota.trim().parse::<f64>().ok()?;
+    let period = period.trim().parse::<f64>().ok()?;
+    (quota > 0.0 && period > 0.0).then(|| quota / period)
+}
+
+/// Parses a cgroup memory limit (`max` or bytes).
+fn parse_memory_limit(content: &str) -> Option<u64> {
+    content
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|limit| *limit < CGROUP_V1_UNLIMITED)
+}
+
+/// Samples the system and the process with `sysinfo`.
+struct Sampler {
+    pid: Pid,
+    system: System,
+    networks: Networks,
+}
+
+impl Sampler {
+    /// Creates a sampler and takes the baseline of the CPU usages.
+    fn new(pid: pid_t) -> Self {
+        let mut sampler = Sampler {
+            pid: Pid::from(pid as usize),
+            system: System::new_with_specifics(
+                RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
+            ),
+            networks: Networks::new_with_refreshed_list(),
+        };
+        sampler.refresh_process();
+        sampler
+    }
+
+    /// Refreshes the CPU usage of the process.
+    fn refresh_process(&mut self) {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[self.pid]),
+            true,
+            ProcessRefreshKind::nothing().with_cpu(),
+        );
+    }
+
+    /// Takes a sample. CPU usages are measured since the previous sample.
+    fn sample(&mut self) -> Sample {
+        self.system.refresh_cpu_all();
+        self.system
+            .refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
+        self.networks.refresh(true);
+        self.refresh_process();
+
+        let cpu_usage = self
+            .system
+            .cpus()
+            .iter()
+            .map(|cpu| {
+                let index = cpu
+                    .name()
+                    .strip_prefix("cpu")
+                    .and_then(|number| number.parse::<u32>().ok())
+                    .unwrap_or(0);
+                (format!("CPU{:02}", index + 1), cpu.cpu_usage())
+            })
+            .collect();
+
+        let networks = self
+            .networks
+            .iter()
+            .map(|(name, data)| (name.clone(), data.received(), data.transmitted()))
+            .collect();
+
+        Sample {
+            cpu_count: self.system.cpus().len(),
+            total_cpu_usage: self.system.global_cpu_usage(),
+            cpu_usage,
+            networks,
+            system_memory_usage: self.system.used_memory(),
+            system_total_memory: self.system.total_memory(),
+            process_cpu_usage: self
+                .system
+                .process(self.pid)
+                .map(|process| process.cpu_usage()),
+            container: ContainerLimits::read(),
+        }
+    }
+}
+
+/// Returns the last sample of the process, starting the background sampler when no
+/// collector is alive.
+fn shared_sample(pid: pid_t, interval: Duration) -> Result<Arc<RwLock<Sample>>, prometheus::Error> {
+    let mut shared = SHARED_SAMPLE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(sample) = shared.upgrade() {
+        return Ok(sample);
+    }
+
+    let sample = Arc::new(RwLock::new(Sample::default()));
+    let target = Arc::downgrade(&sample);
+    let interval = interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+
+    thread::Builder::new()
+        .name("metrics-sampler".into())
+        .spawn(move || {
+            let mut sampler = Sampler::new(pid);
+
+            // The first CPU usages need the minimum interval after the baseline
+            thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+            loop {
+                let next = sampler.sample();
+                match target.upgrade() {
+                    Some(sample) => *sample.write().unwrap_or_else(|e| e.into_inner()) = next,
+                    None => break,
+                }
+                thread::sleep(interval);
+            }
+        })
+        .map_err(|e| prometheus::Error::Msg(e.to_string()))?;
+
+    *shared = Arc::downgrade(&sample);
+    Ok(sample)
+}
+
+/// A Prometheus collector that exposes system, process and container information.
 ///
-/// `SysInfoCollector` collects CPU-related metrics for a given process ID
-/// and publishes them under a configurable namespace.
+/// `SysInfoCollector` returns the last sample taken by the background sampler
+/// for a given process ID and publishes it under a configurable namespace.
 ///
 /// ## Exposed Metrics
 ///
-/// - `process_total_cpu_usage`  
-///   Total CPU usage expressed as a percentage.  
+/// - `process_total_cpu_usage`
+///   Total CPU usage expressed as a percentage.
 ///   On multi-core systems, this value may exceed 100%. To normalize the
 ///   value to the range `0–100%`, divide it by the number of CPUs.
 ///
-/// - `process_system_cpu_count`  
+/// - `process_system_cpu_count`
 ///   Total number of CPUs detected on the system.
+///
+/// - `process_cpu_usage`
+///   CPU usage of the process (in %), up to 100% per core.
+///
+/// - `container_cpu_limit_cores`, `container_memory_limit_bytes`
+///   Limits of the cgroup, only exposed when set.
 pub(crate) struct SysInfoCollector {
-    /// Process identifier associated with this collector.
-    _pid: pid_t,
+    /// Last sample of the process.
+    sample: Arc<RwLock<Sample>>,
     /// Metric descriptors required by the Prometheus `Collector` trait.
     descs: Vec<Desc>,
     /// Counter representing the total CPU usage.
@@ -67,6 +265,12 @@ pub(crate) struct SysInfoCollector {
     system_memory_usage: IntGauge,
     // Couter that represents the System total available memory.
     system_total_memory: IntGauge,
+    /// CPU usage of the process.
+    process_cpu_usage: Gauge,
+    /// CPU limit of the container.
+    container_cpu_limit: Gauge,
+    /// Memory limit of the container.
+    container_memory_limit: IntGauge,
 }
 
 impl SysInfoCollector {
@@ -77,14 +281,17 @@ impl SysInfoCollector {
     /// `pid` - The process ID to associate with this collector.
     /// `namespace` - The Prometheus namespace under which the metrics
     ///   will be exposed.
+    /// `interval` - The interval between two samples, used when the
+    ///   background sampler is not running yet.
     ///
     /// # Returns
     ///
     /// A fully initialized `SysInfoCollector` with all metric descriptors
     /// registered.
     pub fn with_process_and_namespace<S: Into<String>>(
-        _pid: pid_t,
+        pid: pid_t,
         namespace: S,
+        interval: Duration,
     ) -> Result<SysInfoCollector, prometheus::Error> {
         let namespace = namespace.into();
         let mut descs = Vec::new();
@@ -155,8 +362,29 @@ impl SysInfoCollector {
         ))?;
         collect_descs(&system_total_memory);
 
+        // Process CPU usage
+        let process_cpu_usage = Gauge::with_opts(opts(
+            "process_cpu_usage",
+            "CPU utilization (in %) of the process. Up to 100% per core.",
+        ))?;
+        collect_descs(&process_cpu_usage);
+
+        // Container CPU limit
+        let container_cpu_limit = Gauge::with_opts(opts(
+            "container_cpu_limit_cores",
+            "CPU limit of the container cgroup in cores.",
+        ))?;
+        collect_descs(&container_cpu_limit);
+
+        // Container memory limit
+        let container_memory_limit = IntGauge::with_opts(opts(
+            "container_memory_limit_bytes",
+            "Memory limit of the container cgroup in bytes.",
+        ))?;
+        collect_descs(&container_memory_limit);
+
         Ok(SysInfoCollector {
-            _pid,
+            sample: shared_sample(pid, interval)?,
             descs,
             total_cpu_usage,
             cpu_usage,
@@ -165,6 +393,9 @@ impl SysInfoCollector {
             network_transmitted,
             system_memory_usage,
             system_total_memory,
+            process_cpu_usage,
+            container_cpu_limit,
+            container_memory_limit,
         })
     }
 
@@ -182,7 +413,11 @@ impl SysInfoCollector {
         namespace: S,
     ) -> Result<SysInfoCollector, prometheus::Error> {
         let pid = std::process::id();
-        SysInfoCollector::with_process_and_namespace(pid as i32, namespace)
+        SysInfoCollector::with_process_and_namespace(
+            pid as i32,
+            namespace,
+            DEFAULT_SAMPLING_INTERVAL,
+        )
     }
 }
 
@@ -196,59 +431,44 @@ impl Collector for SysInfoCollector {
     /// Collects the current metric values and returns them as
     /// Prometheus `MetricFamily` instances.
     ///
-    /// This method refreshes CPU information using `sysinfo`, updates
-    /// the internal counters, and exports the resulting metrics.
+    /// This method copies the last sample of the background sampler into
+    /// the internal counters, and exports the resulting metrics. It never
+    /// waits for a new sample.
     fn collect(&self) -> Vec<proto::MetricFamily> {
-        let mut networks = Networks::new_with_refreshed_list();
-
-        let mut sys = System::new_with_specifics(
-            RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
-        );
-
-        // CPU usage is a differential value (based on the difference between two measurements).
-        // The first call provides the baseline.
-        sys.refresh_cpu_all();
-
-        // Wait for the minimum required interval to get an accurate reading.
-        thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-
-        // Refresh CPUs again to get the actual usage value.
-        sys.refresh_cpu_all();
+        let sample = self
+            .sample
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
 
         // CPU Count
-        self.cpu_count.set(sys.cpus().len() as i64);
+        self.cpu_count.set(sample.cpu_count as i64);
 
         // Total CPU usage
-        self.total_cpu_usage.set(sys.global_cpu_usage() as i64);
+        self.total_cpu_usage.set(sample.total_cpu_usage as i64);
 
         // Per-core CPU usage
-        sys.cpus().iter().for_each(|cpu| {
-            let number = cpu
-                .name()
-                .strip_prefix("cpu")
-                .expect("Invalid CPU index in metrics.");
-            let core_index: u32 = number.parse().unwrap_or(0);
-            let core_label = format!("CPU{:02}", core_index + 1);
-            let gauge = self.cpu_usage.with_label_values(&[&core_label]);
-            gauge.set(cpu.cpu_usage() as i64);
-        });
+        for (core, usage) in &sample.cpu_usage {
+            self.cpu_usage.with_label_values(&[core]).set(*usage as i64);
+        }
 
         // Network interfaces name, total data received and total data transmitted
-        networks.refresh(false);
-        for (interface_name, data) in &networks {
-            let received_gauge = self.network_received.with_label_values(&[&interface_name]);
-            received_gauge.set(data.received() as i64);
-
-            let transmitted_gauge = self
-                .network_transmitted
-                .with_label_values(&[&interface_name]);
-            transmitted_gauge.set(data.transmitted() as i64);
+        self.network_received.reset();
+        self.network_transmitted.reset();
+        for (interface_name, received, transmitted) in &sample.networks {
+            self.network_received
+                .with_label_values(&[interface_name])
+                .set(*received as i64);
+            self.network_transmitted
+                .with_label_values(&[interface_name])
+                .set(*transmitted as i64);
         }
 
         // System memory
-        sys.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
-        self.system_memory_usage.set(sys.used_memory() as i64);
-        self.system_total_memory.set(sys.total_memory() as i64);
+        self.system_memory_usage
+            .set(sample.system_memory_usage as i64);
+        self.system_total_memory
+            .set(sample.system_total_memory as i64);
 
         // collect MetricFamilys.
         let mut mfs = Vec::with_capacity(METRICS_NUMBER);
@@ -259,6 +479,39 @@ impl Collector for SysInfoCollector {
         mfs.extend(self.network_transmitted.collect());
         mfs.extend(self.system_memory_usage.collect());
         mfs.extend(self.system_total_memory.collect());
+
+        // Process CPU usage, when the process was sampled
+        if let Some(cpu_usage) = sample.process_cpu_usage {
+            self.process_cpu_usage.set(f64::from(cpu_usage));
+            mfs.extend(self.process_cpu_usage.collect());
+        }
+
+        // Container limits, when set
+        if let Some(cpu) = sample.container.cpu {
+            self.container_cpu_limit.set(cpu);
+            mfs.extend(self.container_cpu_limit.collect());
+        }
+        if let Some(memory) = sample.container.memory {
+            self.container_memory_limit.set(memory as i64);
+            mfs.extend(self.container_memory_limit.collect());
+        }
+
         mfs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cfs_quota, parse_cpu_max, parse_memory_limit};
+
+    #[test]
+    fn should_parse_cgroup_limits() {
+        assert_eq!(parse_cpu_max("150000 100000\n"), Some(1.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cfs_quota("50000\n", "100000\n"), Some(0.5));
+        assert_eq!(parse_cfs_quota("-1\n", "100000\n"), None);
+        assert_eq!(parse_memory_limit("536870912\n"), Some(536_870_912));
+        assert_eq!(parse_memory_limit("max\n"), None);
+        assert_eq!(parse_memory_limit("9223372036854771712\n"), None);
+    }
+}
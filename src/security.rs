@@ -1,14 +1,33 @@
 pub mod oauth2 {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashSet;
+    use std::future::{Ready, ready};
+    use std::str::FromStr;
 
+    use actix_web::http::{StatusCode, header};
+    use actix_web::{
+        FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError, dev::Payload,
+    };
     use colored::Colorize;
-    use jsonwebtoken::{Algorithm, DecodingKey, TokenData, Validation, decode, decode_header};
-    use regex::Regex;
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
     use serde::{Deserialize, Serialize};
+    use serde_json::{Map, Value};
     use thiserror::Error;
     use tracing::warn;
 
-    use crate::settings::Settings;
+    use crate::security::authorize::Expression;
+    use crate::settings::{OAuth2Configuration, RoleMapping, Settings};
+
+    /// Default clock skew tolerance (in seconds) applied to the `exp` and `nbf` claims.
+    pub(crate) const DEFAULT_LEEWAY: u64 = 60;
+
+    /// Default claim paths holding the user roles.
+    const DEFAULT_ROLE_CLAIMS: &[&str] = &["resource_access.*.roles"];
+
+    /// Default prefix added to the user roles.
+    const DEFAULT_ROLE_PREFIX: &str = "ROLE_";
+
+    /// Default prefix added to the roles mapped from the token scopes.
+    const DEFAULT_SCOPE_PREFIX: &str = "SCOPE_";
 
     /// A type alias for a `Result` with the `ServerError` error type.
     pub type Result<T, E = OAuth2Error> = std::result::Result<T, E>;
@@ -156,6 +175,9 @@ pub mod oauth2 {
         #[error("Invalid OAuth2 configuration: {0}")]
         Configuration(String),
 
+        #[error("Missing bearer token.")]
+        MissingToken,
+
         #[error("Invalid JWT token: {0}")]
         InvalidJwt(String),
 
@@ -173,12 +195,114 @@ pub mod oauth2 {
 
         #[error("Invalid roles: {0}")]
         InvalidRoles(String),
+
+        #[error("Identity provider unavailable: {0}")]
+        Unavailable(String),
+    }
+
+    impl OAuth2Error {
+        /// Creates an `Unavailable` error for an identity provider endpoint that cannot
+        /// be reached or returns an unexpected response, and logs it.
+        pub(crate) fn unavailable(endpoint: &str, error: impl std::fmt::Display) -> Self {
+            warn!("Identity provider endpoint {endpoint} unavailable: {error}");
+            OAuth2Error::Unavailable(error.to_string())
+        }
+
+        /// Returns the RFC 6750 error code of the `WWW-Authenticate` header, or `None`
+        /// when the request did not carry any token.
+        fn bearer_error(&self) -> Option<&'static str> {
+            match self {
+                OAuth2Error::MissingToken => None,
+                OAuth2Error::InvalidRoles(_) => Some("insufficient_scope"),
+                _ => Some("invalid_token"),
+            }
+        }
+
+        /// Returns the problem detail exposed to the client. Internal errors are
+        /// not described to avoid leaking the server configuration.
+        fn detail(&self) -> String {
+            match self {
+                OAuth2Error::InvalidRoles(_) => {
+                    "The user doesn't have the permissions required by this resource.".into()
+                }
+                OAuth2Error::Configuration(_) | OAuth2Error::RoleAuthorizationParse(_) => {
+                    "The authorization of this resource is not available.".into()
+                }
+                OAuth2Error::Unavailable(_) => {
+                    "The identity provider is temporarily unavailable.".into()
+                }
+                OAuth2Error::InvalidPublicKey(_) => {
+                    "The token is not signed with a known key.".into()
+                }
+                other => other.to_string(),
+            }
+        }
+    }
+
+    /// Problem details body (RFC 9457) returned when the authentication or the
+    /// authorization of a request fails.
+    #[derive(Debug, Serialize)]
+    struct ProblemDetails {
+        #[serde(rename = "type")]
+        kind: &'static str,
+        title: String,
+        status: u16,
+        detail: String,
+    }
+
+    /// Converts the OAuth2 errors into HTTP responses:
+    ///
+    /// - `401 Unauthorized` with a `WWW-Authenticate: Bearer` header (RFC 6750) for
+    ///   missing, expired or invalid tokens.
+    /// - `403 Forbidden` with `error="insufficient_scope"` when the user doesn't
+    ///   satisfy the `authorize` rule.
+    /// - `500 Internal Server Error` for configuration errors.
+    /// - `503 Service Unavailable` when the identity provider cannot be reached.
+    ///
+    /// The body is an `application/problem+json` document.
+    impl ResponseError for OAuth2Error {
+        fn status_code(&self) -> StatusCode {
+            match self {
+                OAuth2Error::InvalidRoles(_) => StatusCode::FORBIDDEN,
+                OAuth2Error::Configuration(_) | OAuth2Error::RoleAuthorizationParse(_) => {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+                OAuth2Error::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::UNAUTHORIZED,
+            }
+        }
+
+        fn error_response(&self) -> HttpResponse {
+            let status = self.status_code();
+            let detail = self.detail();
+            let mut response = HttpResponse::build(status);
+
+            if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+                let challenge = match self.bearer_error() {
+                    Some(error) => format!(
+                        "Bearer error=\"{error}\", error_description=\"{}\"",
+                        detail.replace(['"', '\\'], "'")
+                    ),
+                    None => "Bearer".into(),
+                };
+                response.insert_header((header::WWW_AUTHENTICATE, challenge));
+            }
+
+            response
+                .content_type("application/problem+json")
+                .json(ProblemDetails {
+                    kind: "about:blank",
+                    title: status.canonical_reason().unwrap_or_default().into(),
+                    status: status.as_u16(),
+                    detail,
+                })
+        }
     }
 
     #[derive(Debug, Serialize, Deserialize)]
     struct Claims {
-        // Optional. Audience
-        aud: Option<String>,
+        // Optional. Audience (a single string or an array of strings)
+        aud: Option<Value>,
 
         // Required (validate_exp defaults to true in validation).
         // Expiration time (as UTC timestamp)
@@ -196,68 +320,204 @@ pub mod oauth2 {
         // Optional. Subject (whom token refers to)
         sub: Option<String>,
 
-        // Optional. Auth Scopes
-        scope: Option<String>,
+        // Optional. Auth Scopes (a space-separated string or an array of strings)
+        scope: Option<Value>,
+
+        // Optional. Auth Scopes, as named by Azure AD and Okta
+        scp: Option<Value>,
+    }
+
+    /// The authenticated principal of a request protected by `#[secured]`.
+    ///
+    /// The middleware generated by the `secured` macro inserts the principal into the
+    /// request extensions after the JWT has been validated, so handlers can receive it
+    /// as a regular Actix-Web extractor instead of parsing the `Authorization` header.
+    ///
+    /// # Fields
+    ///
+    /// * `sub` — Subject of the token (usually the user id).
+    /// * `roles` — Roles granted to the user, in the `ROLE_*` format used by `authorize`.
+    /// * `scopes` — Scopes granted to the token (`scope` or `scp` claim, as a
+    ///   space-separated string or an array).
+    /// * `claims` — All the claims of the token, including the non-standard ones.
+    ///
+    /// The bearer token the user was authenticated with is kept (see [`token`]) so
+    /// the outbound HTTP clients can forward or exchange it. It is neither serialized
+    /// nor printed by `Debug`.
+    ///
+    /// While the secured handler runs, the principal is also available through
+    /// [`AuthenticatedUser::current`], without passing it down to the services.
+    ///
+    /// [`token`]: AuthenticatedUser::token
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_microservice::{AuthenticatedUser, secured};
+    /// use actix_web::{HttpResponse, get};
+    ///
+    /// #[secured(method = "get", path = "/v1/me", authorize = "ROLE_USER")]
+    /// pub async fn me_endpoint(user: AuthenticatedUser) -> HttpResponse {
+    ///     let email = user.claim("email").and_then(|v| v.as_str()).unwrap_or_default();
+    ///     HttpResponse::Ok().body(format!("{:?} <{}>", user.sub, email))
+    /// }
+    /// ```
+    #[derive(Clone, Serialize)]
+    pub struct AuthenticatedUser {
+        pub sub: Option<String>,
+        pub roles: HashSet<String>,
+        pub scopes: HashSet<String>,
+        pub claims: Map<String, Value>,
+        #[serde(skip)]
+        pub(crate) token: Option<String>,
+    }
+
+    tokio::task_local! {
+        /// Principal of the secured request handled by the current task.
+        static CURRENT_USER: AuthenticatedUser;
+    }
 
-        // Optional. Resource Access
-        resource_access: Option<HashMap<String, Realm>>,
+    impl std::fmt::Debug for AuthenticatedUser {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("AuthenticatedUser")
+                .field("sub", &self.sub)
+                .field("roles", &self.roles)
+                .field("scopes", &self.scopes)
+                .field("claims", &self.claims)
+                .finish_non_exhaustive()
+        }
     }
 
-    impl Claims {
-        /// Returns an optional HashSet of roles if the JWT token contains a
-        /// "resource_access" claim.
+    impl AuthenticatedUser {
+        /// Builds the principal from the decoded token claims, mapping the roles
+        /// with the given role mapping configuration.
+        fn from_claims(claims: Map<String, Value>, mapping: Option<&RoleMapping>) -> Result<Self> {
+            let parsed = serde_json::from_value::<Claims>(Value::Object(claims.clone()))
+                .map_err(|e| OAuth2Error::JWTDecode(e.to_string()))?;
+
+            let scopes = [parsed.scope, parsed.scp]
+                .iter()
+                .flatten()
+                .flat_map(|value| match value {
+                    Value::String(scopes) => scopes.split_whitespace().collect(),
+                    Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                    _ => Vec::new(),
+                })
+                .map(str::to_string)
+                .collect::<HashSet<_>>();
+
+            Ok(AuthenticatedUser {
+                roles: map_roles(&claims, &scopes, mapping),
+                scopes,
+                sub: parsed.sub,
+                claims,
+                token: None,
+            })
+        }
+
+        /// Returns the principal of the secured request handled by the current task.
         ///
-        /// The roles are extracted from the "resource_access" claim in the
-        /// JWT token, which is a map of resource names to Realm objects.
-        /// The roles are then flattened and collected into a HashSet.
+        /// The principal is set by the `secured` middleware for the duration of the
+        /// handler. Tasks spawned by the handler don't inherit it.
+        pub fn current() -> Option<AuthenticatedUser> {
+            CURRENT_USER.try_with(|user| user.clone()).ok()
+        }
+
+        /// Runs the given future with this principal as the current user.
         ///
-        /// If the JWT token does not contain a "resource_access" claim, or
-        /// if the claim is empty, an empty Option is returned.
-        pub fn get_roles(&self) -> Option<HashSet<String>> {
-            Some(
-                self.resource_access
-                    .as_ref()?
-                    .values()
-                    .flat_map(|v| v.roles.clone())
-                    .map(|role| {
-                        format!(
-                            "ROLE_{}",
-                            role.to_uppercase().replace("-", "_").replace(" ", "_")
-                        )
-                    })
-                    .collect(),
-            )
+        /// Used by the `secured` middleware; can also be used to propagate the
+        /// principal to a spawned task.
+        pub async fn scope<F: Future>(self, future: F) -> F::Output {
+            CURRENT_USER.scope(self, future).await
+        }
+
+        /// Returns the bearer token the user was authenticated with, or `None` when
+        /// authenticated with an API key.
+        pub fn token(&self) -> Option<&str> {
+            self.token.as_deref()
+        }
+
+        /// Returns `true` if the user has the given role (e.g. `ROLE_ADMIN`).
+        pub fn has_role(&self, role: &str) -> bool {
+            self.roles.contains(role)
+        }
+
+        /// Returns `true` if the token was granted the given scope.
+        pub fn has_scope(&self, scope: &str) -> bool {
+            self.scopes.contains(scope)
+        }
+
+        /// Returns the value of a claim of the token, if present.
+        pub fn claim(&self, name: &str) -> Option<&Value> {
+            self.claims.get(name)
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
-    pub struct Realm {
-        // Optional Additional claims
-        roles: Vec<String>,
+    impl FromRequest for AuthenticatedUser {
+        type Error = actix_web::Error;
+        type Future = Ready<std::result::Result<Self, Self::Error>>;
+
+        /// Retrieves the principal inserted by the `secured` middleware. Fails with
+        /// `401 Unauthorized` when the endpoint is not protected by `#[secured]`.
+        fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            ready(
+                req.extensions()
+                    .get::<AuthenticatedUser>()
+                    .cloned()
+                    .ok_or_else(|| OAuth2Error::MissingToken.into()),
+            )
+        }
     }
 
     /// Validates a JWT token and ensures that the roles in the token match the provided list.
     ///
+    /// When the token references a `kid` that is not cached yet, the JWKS is fetched
+    /// again from the identity provider (rate limited) before validating the signature.
+    /// When `token-validation` is `introspection`, the token is validated by the
+    /// identity provider introspection endpoint instead.
+    ///
     /// # Parameters
     /// - `token`: The JWT token to validate.
     /// - `settings`: The configuration settings for the server.
     /// - `roles`: The list of roles to check against the JWT token.
     ///
     /// # Returns
-    /// A `Result` containing a `()`` if the JWT token is valid and the roles match.
-    /// Returns an error if the JWT token is invalid or the roles do not match.
+    /// A `Result` containing the [`AuthenticatedUser`] if the JWT token is valid and the
+    /// roles match. Returns an error if the JWT token is invalid or the roles do not match.
     ///
     /// # Errors
     /// This method will return an error if:
     /// - The JWT token is invalid.
     /// - The roles in the JWT token do not match the provided list.
-    pub(crate) fn validate_jwt(token: &str, settings: &Settings, authorize: String) -> Result<()> {
+    pub(crate) async fn validate_jwt(
+        token: &str,
+        settings: &Settings,
+        authorize: String,
+    ) -> Result<AuthenticatedUser> {
+        // Opaque tokens are validated by the identity provider
+        if let Some(config) = settings.get_oauth2_config()
+            && super::introspection::is_enabled(&config)
+        {
+            let claims = super::introspection::introspect(token, &config).await?;
+            let mapping = config.role_mapping.as_ref();
+            let mut user = AuthenticatedUser::from_claims(claims, mapping)?;
+            validate_jwt_roles(&user, authorize, mapping)?;
+
+            user.token = Some(token.to_string());
+            return Ok(user);
+        }
+
         // Validate JWT and retrieve the `kid` header
         let (kid, algorithm) = validate_jwt_header(token)?;
 
-        validate_jwt_with_roles(token, kid.as_str(), algorithm, authorize, settings)?;
+        // Refresh the JWKS when the key id is unknown (key rotation)
+        super::jwks::resolve_key(kid.as_str()).await;
 
-        Ok(())
+        let mut user =
+            validate_jwt_with_roles(token, kid.as_str(), algorithm, authorize, settings)?;
+        user.token = Some(token.to_string());
+
+        Ok(user)
     }
 
     /// Validates the JWT header and retrieves the `kid` and `Algorithm` fields.
@@ -291,7 +551,8 @@ pub mod oauth2 {
     ///
     /// This method takes in a JWT token, a kid, an algorithm, a list of roles, and a settings object.
     /// It first retrieves the public key based on the `kid` and then uses it to decode the JWT token.
-    /// After decoding, it validates that the issuer URI within the token matches the one configured in the server settings.
+    /// After decoding, it validates that the issuer URI within the token matches the one configured in the server settings,
+    /// as well as the audience, `nbf` claim, algorithm and clock skew rules described in [`create_validation`].
     /// Finally, it checks that the roles in the token match the provided list.
     ///
     /// # Parameters
@@ -302,8 +563,8 @@ pub mod oauth2 {
     /// - `settings`: The settings object containing the server configuration.
     ///
     /// # Returns
-    /// A `Result` containing a `()` if the JWT token is valid and the roles match.
-    /// Returns an error if the JWT token is invalid or the roles do not match.
+    /// A `Result` containing the [`AuthenticatedUser`] if the JWT token is valid and the
+    /// roles match. Returns an error if the JWT token is invalid or the roles do not match.
     ///
     /// # Errors
     /// This method will return an error if:
@@ -317,237 +578,2049 @@ pub mod oauth2 {
         algorithm: Algorithm,
         authorize: String,
         settings: &Settings,
-    ) -> Result<()> {
+    ) -> Result<AuthenticatedUser> {
         // Retrieves the public key based on the `kid`
         let public_key = settings.get_auth2_public_key(kid).ok_or_else(|| {
             warn!("Public key not found for key id: {kid}.");
-            OAuth2Error::InvalidPublicKey("Public key not found for key id: {kid}.".into())
+            OAuth2Error::InvalidPublicKey(format!("Public key not found for key id: {kid}."))
         })?;
         let decoded_public_key = &DecodingKey::try_from(&public_key).map_err(|e| {
             warn!("Invalid public key. \n{:?}", &public_key);
             OAuth2Error::InvalidPublicKey(e.to_string())
         })?;
 
-        // Retrieves the issuer URI within the server configuration
-        let issuer = settings
-            .get_oauth2_config()
-            .ok_or_else(|| {
-                warn!("Security not configured.");
-                OAuth2Error::Configuration("Security not configured..".into())
-            })?
-            .issuer_uri
-            .ok_or_else(|| {
-                warn!("Issuer URI not configured.");
-                OAuth2Error::Configuration("Issuer URI not configured.".into())
-            })?;
-
-        // Creates a validation struct for the JWT
-        let validation = {
-            let mut validation = Validation::new(algorithm);
-            validation.set_issuer(&[issuer.as_str()]);
-            validation.validate_exp = true;
-            validation
-        };
+        // Retrieves the OAuth2 configuration and creates the validation rules for the JWT
+        let config = settings.get_oauth2_config().ok_or_else(|| {
+            warn!("Security not configured.");
+            OAuth2Error::Configuration("Security not configured..".into())
+        })?;
+        let validation = create_validation(algorithm, &config)?;
 
-        // Decodes the JWT into a HashMap
-        let decoded_token =
-            decode::<Claims>(token, decoded_public_key, &validation).map_err(|e| {
+        // Decodes the JWT into a claim map
+        let decoded_token = decode::<Map<String, Value>>(token, decoded_public_key, &validation)
+            .map_err(|e| {
                 warn!("Invalid token. {}", e.to_string());
                 OAuth2Error::JWTDecode(e.to_string())
             })?;
 
-        validate_jwt_roles(&decoded_token, authorize)?;
+        let mapping = config.role_mapping.as_ref();
+        let user = AuthenticatedUser::from_claims(decoded_token.claims, mapping)?;
+        validate_jwt_roles(&user, authorize, mapping)?;
 
-        Ok(())
+        Ok(user)
     }
 
-    /// Validates the roles in the given JWT token against the provided authorization string.
+    /// Creates the `Validation` rules applied when decoding a JWT.
     ///
-    /// The `authorize` string must be in the following format:
-    /// `method role1,role2,...,roleN` or `ROLE1,ROLE2,...,ROLEN`
-    ///
-    /// The `method` parameter can be either "hasanyrole" or "hasallroles".
-    /// If "hasanyrole" is specified, the function will return an error if any of the required roles are not found in the JWT token.
-    /// If "hasallroles" is specified, the function will return an error if all of the required roles are not found in the JWT token.
+    /// The issuer is always required. The audience, `nbf` claim, accepted algorithms and
+    /// clock skew leeway are taken from the OAuth2 configuration.
     ///
     /// # Parameters
-    /// - `token`: The JWT token to validate the roles against.
-    /// - `authorize`: The authorization string to parse.
+    /// - `algorithm`: The algorithm declared in the JWT header.
+    /// - `config`: The OAuth2 configuration of the server.
     ///
     /// # Returns
-    /// A `Result` containing a unit if the roles match the provided authorization string.
-    /// Returns an error if the roles do not match the provided authorization string.
+    /// A `Result` containing the `Validation` to use with the token.
     ///
     /// # Errors
     /// This method will return an error if:
-    /// - The authorization string is invalid.
-    /// - The roles in the JWT token do not match the provided authorization string.
-    fn validate_jwt_roles(token: &TokenData<Claims>, authorize: String) -> Result<()> {
-        let (method, roles) = get_authorize_role_method(authorize)?;
-
-        match method.as_str() {
-            "hasanyrole" => has_any_role(token, roles)?,
-            "hasallroles" => has_all_role(token, roles)?,
-            _ => {
-                if !method.is_empty() {
-                    return Err(OAuth2Error::InvalidRoles(format!(
-                        "Invalid role authorization method: {}",
-                        method.bright_blue()
-                    )));
-                } else {
-                    // Validate Single Role
-                    has_any_role(token, roles)?;
-                }
+    /// - The issuer URI is not configured in the server settings.
+    /// - An accepted algorithm in the server settings is invalid.
+    /// - The algorithm of the token is not accepted.
+    fn create_validation(algorithm: Algorithm, config: &OAuth2Configuration) -> Result<Validation> {
+        let issuer = config.issuer_uri.as_ref().ok_or_else(|| {
+            warn!("Issuer URI not configured.");
+            OAuth2Error::Configuration("Issuer URI not configured.".into())
+        })?;
+
+        // Rejects tokens signed with an algorithm that is not accepted
+        if let Some(algorithms) = &config.algorithms {
+            let accepted = algorithms
+                .iter()
+                .map(|a| {
+                    Algorithm::from_str(a.trim()).map_err(|_| {
+                        OAuth2Error::Configuration(format!("Invalid JWT algorithm: {a}."))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if !accepted.contains(&algorithm) {
+                warn!("Token algorithm {:?} is not accepted.", algorithm);
+                return Err(OAuth2Error::InvalidJwt(format!(
+                    "Token algorithm {:?} is not accepted.",
+                    algorithm
+                )));
             }
         }
 
-        Ok(())
-    }
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[issuer.as_str()]);
+        validation.validate_exp = true;
+        validation.validate_nbf = config.validate_nbf.unwrap_or(true);
+        validation.leeway = config.leeway.unwrap_or(DEFAULT_LEEWAY);
 
-    /// Checks if all of the roles in the given `roles` vector are present in the JWT token.
-    ///
-    /// # Parameters
-    /// - `token`: The JWT token to check the roles against.
-    /// - `roles`: A vector of roles to check against the JWT token.
-    ///
-    /// # Returns
-    /// A `Result` containing a unit if all of the required roles are found in the JWT token.
-    /// Returns an error if any of the required roles are not found in the JWT token.
-    ///
-    /// # Errors
-    /// This method will return an error if none of the required roles are found in the JWT token.
-    fn has_all_role(token: &TokenData<Claims>, roles: Vec<String>) -> Result<()> {
-        let token_roles = token
-            .claims
-            .get_roles()
-            .ok_or_else(|| OAuth2Error::InvalidRoles("User doesn't have any roles.".into()))?;
-        for role in &roles {
-            if !token_roles.contains(role) {
-                return Err(OAuth2Error::InvalidRoles(format!(
-                    "No required role was found for the current user. Required roles: {}. Current roles: {}",
-                    role.bright_blue(),
-                    token_roles
-                        .iter()
-                        .map(|r| r.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                        .bright_green()
-                )));
+        // The audience is only enforced when expected audiences are configured
+        match config.audiences.as_ref().filter(|a| !a.is_empty()) {
+            Some(audiences) => {
+                validation.set_audience(audiences);
+                validation.set_required_spec_claims(&["exp", "iss", "aud"]);
             }
+            None => validation.validate_aud = false,
         }
 
-        Ok(())
+        Ok(validation)
     }
 
-    /// Checks if any of the roles in the given `roles` vector is present in the JWT token.
+    /// Builds the user roles from the token claims.
+    ///
+    /// The roles are read from the claim paths of the role mapping configuration
+    /// (`resource_access.*.roles` by default) and, when enabled, from the token scopes.
+    /// Each role is prefixed and normalized so it can be compared with the roles of
+    /// the `authorize` rules.
     ///
     /// # Parameters
-    /// - `token`: The JWT token to check the roles against.
-    /// - `roles`: A vector of roles to check against the JWT token.
+    /// - `claims`: The decoded token claims.
+    /// - `scopes`: The scopes granted to the token.
+    /// - `mapping`: The role mapping configuration, if any.
     ///
     /// # Returns
-    /// A `Result` containing a unit if any of the required roles are found in the JWT token.
-    /// Returns an error if none of the required roles are found in the JWT token.
+    /// The set of normalized roles of the user.
+    fn map_roles(
+        claims: &Map<String, Value>,
+        scopes: &HashSet<String>,
+        mapping: Option<&RoleMapping>,
+    ) -> HashSet<String> {
+        let paths = mapping
+            .and_then(|m| m.claims.clone())
+            .unwrap_or_else(|| DEFAULT_ROLE_CLAIMS.iter().map(|c| c.to_string()).collect());
+        let clients = mapping.and_then(|m| m.clients.as_deref());
+        let prefix = mapping
+            .and_then(|m| m.prefix.as_deref())
+            .unwrap_or(DEFAULT_ROLE_PREFIX);
+        let case = mapping.and_then(|m| m.case.as_deref());
+
+        let mut roles = paths
+            .iter()
+            .flat_map(|path| find_claim_values(claims, path, clients))
+            .map(|role| normalize_role(prefix, role, case))
+            .collect::<HashSet<_>>();
+
+        if mapping.and_then(|m| m.include_scopes).unwrap_or(false) {
+            let scope_prefix = mapping
+                .and_then(|m| m.scope_prefix.as_deref())
+                .unwrap_or(DEFAULT_SCOPE_PREFIX);
+            roles.extend(
+                scopes
+                    .iter()
+                    .map(|scope| normalize_role(scope_prefix, scope, case)),
+            );
+        }
+
+        roles
+    }
+
+    /// Returns the string values found at the given claim path.
     ///
-    /// # Errors
-    /// This method will return an error if none of the required roles are found in the JWT token.
-    fn has_any_role(token: &TokenData<Claims>, roles: Vec<String>) -> Result<()> {
-        let token_roles = token
-            .claims
-            .get_roles()
-            .ok_or_else(|| OAuth2Error::InvalidRoles("User doesn't have any roles.".into()))?;
-        for role in &roles {
-            if token_roles.contains(role) {
-                return Ok(());
-            }
-        }
-
-        Err(OAuth2Error::InvalidRoles(format!(
-            "No required role was found for the current user. Required roles: {}. Current roles: {}",
-            roles.join(", ").bright_blue(),
-            token_roles
-                .iter()
-                .map(|r| r.to_string())
-                .collect::<Vec<String>>()
-                .join(", ")
-                .bright_green()
-        )))
+    /// The path segments are separated by `.` and the `*` segment matches every key of
+    /// an object (restricted to `clients` when set) or every item of an array. A claim
+    /// whose name is exactly the given path (e.g. `cognito:groups`) is used as is.
+    fn find_claim_values<'a>(
+        claims: &'a Map<String, Value>,
+        path: &str,
+        clients: Option<&[String]>,
+    ) -> Vec<&'a str> {
+        let values = match claims.get(path) {
+            Some(value) => vec![value],
+            None => {
+                let mut values = Vec::new();
+                for (index, segment) in path.split('.').enumerate() {
+                    let children = |map: &'a Map<String, Value>| -> Vec<&'a Value> {
+                        match segment {
+                            "*" => map
+                                .iter()
+                                .filter(|(key, _)| clients.is_none_or(|c| c.contains(key)))
+                                .map(|(_, value)| value)
+                                .collect(),
+                            key => map.get(key).into_iter().collect(),
+                        }
+                    };
+
+                    values = if index == 0 {
+                        children(claims)
+                    } else {
+                        values
+                            .into_iter()
+                            .flat_map(|value| match value {
+                                Value::Object(map) => children(map),
+                                Value::Array(items) if segment == "*" => items.iter().collect(),
+                                _ => Vec::new(),
+                            })
+                            .collect()
+                    };
+                }
+                values
+            }
+        };
+
+        values
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::String(role) => vec![role.as_str()],
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Prefixes the role, replaces dashes and spaces by `_` and applies the configured
+    /// case normalization. Other characters, like `.` or `:`, are kept.
+    fn normalize_role(prefix: &str, role: &str, case: Option<&str>) -> String {
+        let role = role.trim().replace(['-', ' '], "_");
+        apply_case(&format!("{prefix}{role}"), case)
+    }
+
+    /// Applies the case normalization (`upper`, `lower` or `preserve`) to the role.
+    pub(crate) fn apply_case(role: &str, case: Option<&str>) -> String {
+        match case.map(str::to_lowercase).as_deref() {
+            Some("lower") => role.to_lowercase(),
+            Some("preserve") => role.to_string(),
+            _ => role.to_uppercase(),
+        }
     }
 
-    /// Retrieves the authorization method and roles from the given string.
+    /// Validates the authenticated user against the provided authorization expression.
     ///
-    /// The authorization string must be in the following format:
-    /// `method role1,role2,...,roleN` or `ROLE1,ROLE2,...,ROLEN`
+    /// The expression syntax is described in the [`authorize`](super::authorize) module,
+    /// e.g. `hasAnyRole(ROLE_ADMIN, ROLE_USER) and hasScope('read:users')`.
     ///
     /// # Parameters
-    /// - `authorize`: The authorization string to parse.
+    /// - `user`: The authenticated user to validate the expression against.
+    /// - `authorize`: The authorization expression to parse.
+    /// - `mapping`: The role mapping configuration, used to normalize the required roles.
     ///
     /// # Returns
-    /// A `Result` containing a tuple of the authorization method and roles if the string is valid.
-    /// Returns an error if the string is invalid.
+    /// A `Result` containing a unit if the user satisfies the authorization expression.
     ///
     /// # Errors
-    /// This method will return an error if the authorization string is invalid.
-    fn get_authorize_role_method(authorize: String) -> Result<(String, Vec<String>)> {
-        let pattern = Regex::new(
-            r"(?i)^\s*(?:(\w+)\s*\(\s*(ROLE_\w+(?:\s*,\s*ROLE_\w+)*)\s*\)|(ROLE_\w+))\s*$",
-        )
-        .map_err(|e| OAuth2Error::RoleAuthorizationParse(e.to_string()))?;
-
-        let caps = pattern.captures(&authorize).ok_or_else(|| {
-            OAuth2Error::RoleAuthorizationParse("Invalid role authorization format.".into())
-        })?;
-
-        // Grup 1: method (opcional)
-        let method = caps
-            .get(1)
-            .map(|m| m.as_str().to_lowercase())
-            .unwrap_or_default();
-
-        // Grup 2: roles with method
-        // Grup 3: one role without method
-        let roles_raw = caps
-            .get(2)
-            .or_else(|| caps.get(3))
-            .map(|r| r.as_str())
-            .ok_or_else(|| OAuth2Error::RoleAuthorizationParse("Roles not found.".into()))?;
-
-        let roles = roles_raw
-            .split(',')
-            .map(|r| r.trim().to_uppercase())
-            .collect::<Vec<_>>();
-
-        // Method and roles cannot be empty at the same time
-        if method.is_empty() && roles.is_empty() {
-            return Err(OAuth2Error::RoleAuthorizationParse(
-                "Authorization method and role not found.".into(),
-            ));
-        }
+    /// This method will return an error if:
+    /// - The authorization expression is invalid.
+    /// - The user doesn't satisfy the authorization expression.
+    pub(crate) fn validate_jwt_roles(
+        user: &AuthenticatedUser,
+        authorize: String,
+        mapping: Option<&RoleMapping>,
+    ) -> Result<()> {
+        let expression = Expression::cached(&authorize)?;
+        let case = mapping.and_then(|m| m.case.as_deref());
 
-        // Roles cannot be empty if the method is not empty
-        if !method.is_empty() && roles.is_empty() {
-            return Err(OAuth2Error::RoleAuthorizationParse(
-                "Authorization method without roles.".into(),
-            ));
+        if !expression.evaluate(user, case) {
+            return Err(OAuth2Error::InvalidRoles(format!(
+                "The current user doesn't satisfy the authorization rule: {}. Current roles: {}",
+                authorize.bright_blue(),
+                user.roles
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                    .bright_green()
+            )));
         }
 
-        Ok((method, roles))
+        Ok(())
     }
 
     #[cfg(test)]
     mod tests {
-        use super::get_authorize_role_method;
+        use super::{AuthenticatedUser, OAuth2Error, create_validation, map_roles, normalize_role};
+        use crate::security::authorize::Expression;
+        use crate::settings::{OAuth2Configuration, RoleMapping};
+        use actix_web::ResponseError;
+        use actix_web::http::{StatusCode, header};
+        use jsonwebtoken::Algorithm;
+        use serde_json::json;
 
         #[test]
         fn should_parse_method_and_roles_from_authorize_string() {
             let authorize = "hasAnyRole(ROLE_ADMIN, ROLE_USER)".to_string();
 
-            let result = get_authorize_role_method(authorize);
+            let result = Expression::parse(&authorize);
 
             assert!(result.is_ok());
-            let (method, roles) = result.unwrap_or_default();
-            assert_eq!(method, "hasanyrole");
-            assert_eq!(roles, vec!["ROLE_ADMIN", "ROLE_USER"]);
+            assert_eq!(
+                result.ok(),
+                Some(Expression::HasAnyRole(vec![
+                    "ROLE_ADMIN".into(),
+                    "ROLE_USER".into()
+                ]))
+            );
+        }
+
+        #[test]
+        fn should_build_authenticated_user_from_claims() {
+            let claims = json!({
+                "sub": "user-1",
+                "email": "user@example.com",
+                "scope": "openid profile",
+                "resource_access": { "api": { "roles": ["admin", "read-only"] } }
+            });
+            let claims = claims.as_object().cloned().unwrap_or_default();
+
+            let user = AuthenticatedUser::from_claims(claims, None);
+
+            assert!(user.is_ok());
+            let user = user.unwrap_or_else(|e| panic!("{e}"));
+            assert_eq!(user.sub.as_deref(), Some("user-1"));
+            assert!(user.has_role("ROLE_ADMIN"));
+            assert!(user.has_role("ROLE_READ_ONLY"));
+            assert!(user.has_scope("profile"));
+            assert_eq!(user.claim("email"), Some(&json!("user@example.com")));
+        }
+
+        #[test]
+        fn should_read_scopes_from_a_string_or_an_array() {
+            for claims in [
+                json!({ "scope": "read:users write:users" }),
+                json!({ "scope": ["read:users", "write:users"] }),
+                json!({ "scp": ["read:users", "write:users"] }),
+                json!({ "scp": "read:users write:users" }),
+            ] {
+                let claims = claims.as_object().cloned().unwrap_or_default();
+
+                let user = AuthenticatedUser::from_claims(claims, None);
+
+                assert!(user.is_ok());
+                let user = user.unwrap_or_else(|e| panic!("{e}"));
+                assert_eq!(user.scopes.len(), 2);
+                assert!(user.has_scope("read:users"));
+                assert!(user.has_scope("write:users"));
+            }
+        }
+
+        #[tokio::test]
+        async fn should_expose_current_user_within_scope() {
+            let claims = json!({ "sub": "user-1" })
+                .as_object()
+                .cloned()
+                .unwrap_or_default();
+            let mut user =
+                AuthenticatedUser::from_claims(claims, None).unwrap_or_else(|e| panic!("{e}"));
+            user.token = Some("secret-token".into());
+
+            assert!(!format!("{user:?}").contains("secret-token"));
+            assert!(AuthenticatedUser::current().is_none());
+
+            let token = user
+                .scope(async { AuthenticatedUser::current().and_then(|u| u.token) })
+                .await;
+            assert_eq!(token.as_deref(), Some("secret-token"));
+        }
+
+        #[test]
+        fn should_map_oauth2_errors_to_bearer_challenges() {
+            let forbidden = OAuth2Error::InvalidRoles("ROLE_ADMIN".into()).error_response();
+            let expired = OAuth2Error::JWTDecode("ExpiredSignature".into()).error_response();
+            let missing = OAuth2Error::MissingToken.error_response();
+            let unavailable = OAuth2Error::Unavailable("timeout".into()).error_response();
+            let unknown_key =
+                OAuth2Error::InvalidPublicKey("Public key not found for key id: k1.".into());
+            let challenge = |response: &actix_web::HttpResponse| {
+                response
+                    .headers()
+                    .get(header::WWW_AUTHENTICATE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+                    .unwrap_or_default()
+            };
+
+            assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+            assert!(challenge(&forbidden).starts_with("Bearer error=\"insufficient_scope\""));
+            assert_eq!(expired.status(), StatusCode::UNAUTHORIZED);
+            assert!(challenge(&expired).starts_with("Bearer error=\"invalid_token\""));
+            assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(challenge(&missing), "Bearer");
+            assert_eq!(unavailable.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(
+                unknown_key.detail(),
+                "The token is not signed with a known key."
+            );
+            assert!(
+                challenge(&unknown_key.error_response())
+                    .contains("error_description=\"The token is not signed with a known key.\"")
+            );
+            assert!(
+                unavailable
+                    .headers()
+                    .get(header::WWW_AUTHENTICATE)
+                    .is_none()
+            );
+            assert_eq!(
+                missing
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok()),
+                Some("application/problem+json")
+            );
+        }
+
+        #[test]
+        fn should_map_roles_from_configured_claims() {
+            let claims = json!({
+                "scope": "openid read:users",
+                "realm_access": { "roles": ["offline_access"] },
+                "resource_access": {
+                    "rust-client": { "roles": ["admin"] },
+                    "other-client": { "roles": ["auditor"] }
+                },
+                "cognito:groups": ["Support Team"]
+            });
+            let claims = claims.as_object().cloned().unwrap_or_default();
+            let mapping = serde_json::from_value::<RoleMapping>(json!({
+                "claims": ["realm_access.roles", "resource_access.*.roles", "cognito:groups"],
+                "clients": ["rust-client"],
+                "include-scopes": true
+            }));
+            assert!(mapping.is_ok());
+            let mapping = mapping.unwrap_or_else(|e| panic!("{e}"));
+
+            let scopes = ["openid", "read:users"].map(String::from).into();
+            let roles = map_roles(&claims, &scopes, Some(&mapping));
+
+            let mut roles = roles.into_iter().collect::<Vec<_>>();
+            roles.sort();
+            assert_eq!(
+                roles,
+                vec![
+                    "ROLE_ADMIN",
+                    "ROLE_OFFLINE_ACCESS",
+                    "ROLE_SUPPORT_TEAM",
+                    "SCOPE_OPENID",
+                    "SCOPE_READ:USERS"
+                ]
+            );
+        }
+
+        #[test]
+        fn should_keep_role_characters_other_than_dashes_and_spaces() {
+            assert_eq!(normalize_role("ROLE_", "app.read", None), "ROLE_APP.READ");
+            assert_eq!(normalize_role("ROLE_", "org:admin", None), "ROLE_ORG:ADMIN");
+            assert_eq!(
+                normalize_role("ROLE_", " app-admin ", None),
+                "ROLE_APP_ADMIN"
+            );
+            assert_eq!(
+                normalize_role("ROLE_", "Support Team", None),
+                "ROLE_SUPPORT_TEAM"
+            );
+        }
+
+        #[test]
+        fn should_create_validation_from_oauth2_configuration() {
+            let config = serde_json::from_value::<OAuth2Configuration>(json!({
+                "issuer-uri": "http://localhost/realms/test",
+                "audiences": ["rust-client"],
+                "algorithms": ["RS256"],
+                "leeway": 5
+            }));
+            assert!(config.is_ok());
+            let config = config.unwrap_or_else(|e| panic!("{e}"));
+
+            let validation = create_validation(Algorithm::RS256, &config);
+            assert!(validation.is_ok());
+            let validation = validation.unwrap_or_else(|e| panic!("{e}"));
+            assert!(validation.validate_nbf);
+            assert_eq!(validation.leeway, 5);
+            assert!(
+                validation
+                    .aud
+                    .is_some_and(|aud| aud.contains("rust-client"))
+            );
+
+            assert!(create_validation(Algorithm::HS256, &config).is_err());
+        }
+    }
+}
+
+pub mod jwks {
+    //! # JWKS Store
+    //!
+    //! Keeps an in-memory copy of the identity provider JSON Web Key Set so that
+    //! tokens signed with rotated keys keep validating without a restart.
+    //!
+    //! The store is refreshed in the background when the cached copy expires. The
+    //! cache lifetime is taken from the `Cache-Control: max-age` header returned by
+    //! the JWKS endpoint, falling back to `security.oauth2.jwks-cache-ttl`. When a
+    //! token arrives with an unknown `kid`, the key set is fetched again on demand,
+    //! at most once per `security.oauth2.jwks-min-refresh-interval`.
+    //!
+    //! Once the store is initialized, it is the only source of keys: a key removed
+    //! from the identity provider key set stops validating tokens at the next refresh.
+
+    use std::sync::{LazyLock, OnceLock, RwLock};
+    use std::time::{Duration, Instant};
+
+    use colored::Colorize;
+    use jsonwebtoken::jwk::{Jwk, JwkSet};
+    use reqwest::header::CACHE_CONTROL;
+    use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+    use tokio::sync::Mutex;
+    use tracing::{debug, info, warn};
+
+    use crate::security::oauth2::{OAuth2Error, Result};
+    use crate::settings::Settings;
+
+    /// Default cache lifetime (in seconds) used when the JWKS endpoint does not
+    /// send a `Cache-Control: max-age` directive.
+    const DEFAULT_CACHE_TTL: u64 = 300;
+
+    /// Default minimum interval (in seconds) between two JWKS fetches.
+    const DEFAULT_MIN_REFRESH_INTERVAL: u64 = 30;
+
+    /// Maximum cache lifetime of the key set, one day.
+    const MAX_CACHE_TTL: Duration = Duration::from_secs(86_400);
+
+    /// Global JWKS store, initialized once during server startup.
+    static JWKS_STORE: OnceLock<JwksStore> = OnceLock::new();
+
+    /// HTTP client shared by the JWKS fetches.
+    static CLIENT: LazyLock<ClientWithMiddleware> =
+        LazyLock::new(|| ClientBuilder::new(reqwest::Client::new()).build());
+
+    /// Cached key set and its expiration instant.
+    struct CachedKeys {
+        jwks: JwkSet,
+        expires_at: Instant,
+    }
+
+    /// Background-refreshed cache of the identity provider JSON Web Key Set.
+    pub(crate) struct JwksStore {
+        jwks_uri: String,
+        keys: RwLock<CachedKeys>,
+        /// Instant of the last fetch triggered by an unknown `kid`. The lock also
+        /// serializes the fetches.
+        last_unknown_kid_fetch: Mutex<Option<Instant>>,
+        default_ttl: Duration,
+        min_refresh_interval: Duration,
+    }
+
+    impl JwksStore {
+        /// Creates a new store for the given JWKS endpoint.
+        ///
+        /// # Parameters
+        /// - `jwks_uri`: The JWKS endpoint of the identity provider.
+        /// - `initial`: Keys known before the first fetch (e.g. from the configuration file).
+        /// - `default_ttl`: Cache lifetime used when no `Cache-Control` header is returned.
+        /// - `min_refresh_interval`: Minimum interval between two fetches triggered by
+        ///   unknown key ids, and between two retries of a failed scheduled refresh.
+        pub(crate) fn new(
+            jwks_uri: String,
+            initial: Option<JwkSet>,
+            default_ttl: Duration,
+            min_refresh_interval: Duration,
+        ) -> Self {
+            JwksStore {
+                jwks_uri,
+                keys: RwLock::new(CachedKeys {
+                    jwks: initial.unwrap_or(JwkSet { keys: vec![] }),
+                    expires_at: Instant::now(),
+                }),
+                last_unknown_kid_fetch: Mutex::new(None),
+                default_ttl,
+                min_refresh_interval,
+            }
+        }
+
+        /// Returns the cached key for the given `kid`, if any.
+        pub(crate) fn find(&self, kid: &str) -> Option<Jwk> {
+            self.keys.read().ok()?.jwks.find(kid).cloned()
+        }
+
+        /// Returns the remaining lifetime of the cached key set.
+        fn expires_in(&self) -> Duration {
+            self.keys
+                .read()
+                .map(|keys| keys.expires_at.saturating_duration_since(Instant::now()))
+                .unwrap_or_default()
+        }
+
+        /// Fetches the key set from the JWKS endpoint and replaces the cached copy.
+        /// The cached copy expires at least once a day.
+        ///
+        /// # Returns
+        /// The cache lifetime applied to the fetched key set.
+        ///
+        /// # Errors
+        /// Returns `OAuth2Error::InvalidPublicKey` if the endpoint cannot be reached or
+        /// its response cannot be parsed.
+        pub(crate) async fn refresh(&self) -> Result<Duration> {
+            let _guard = self.last_unknown_kid_fetch.lock().await;
+            self.fetch().await
+        }
+
+        /// Fetches the key set again because a token referenced an unknown `kid`.
+        ///
+        /// Concurrent callers are serialized, and the fetch is skipped when the key
+        /// became available meanwhile or when the last fetch triggered by an unknown
+        /// `kid` happened less than `min_refresh_interval` ago. Scheduled refreshes
+        /// don't count, so a key rotated just after one is still fetched.
+        pub(crate) async fn refresh_for_unknown_kid(&self, kid: &str) {
+            let mut last_fetch = self.last_unknown_kid_fetch.lock().await;
+
+            if self.find(kid).is_some() {
+                return;
+            }
+
+            if let Some(instant) = *last_fetch
+                && instant.elapsed() < self.min_refresh_interval
+            {
+                debug!("Skipping JWKS refresh for key id {kid}: rate limit not elapsed.");
+                return;
+            }
+
+            info!(
+                "Unknown key id {}. Refreshing JWKs from {}",
+                kid.bright_blue(),
+                self.jwks_uri.bright_blue()
+            );
+
+            *last_fetch = Some(Instant::now());
+            if let Err(e) = self.fetch().await {
+                warn!("Failed to refresh JWKs: {}", e);
+            }
+        }
+
+        /// Performs the HTTP request and updates the cache. Must be called while
+        /// holding the `last_unknown_kid_fetch` lock.
+        async fn fetch(&self) -> Result<Duration> {
+            let response = CLIENT
+                .get(&self.jwks_uri)
+                .send()
+                .await
+                .map_err(|e| OAuth2Error::InvalidPublicKey(e.to_string()))?
+                .error_for_status()
+                .map_err(|e| OAuth2Error::InvalidPublicKey(e.to_string()))?;
+
+            let max_age = response
+                .headers()
+                .get(CACHE_CONTROL)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_max_age);
+
+            let jwks = response
+                .json::<JwkSet>()
+                .await
+                .map_err(|e| OAuth2Error::InvalidPublicKey(e.to_string()))?;
+
+            let ttl = max_age
+                .map(|ttl| ttl.max(self.min_refresh_interval))
+                .unwrap_or(self.default_ttl)
+                .min(MAX_CACHE_TTL);
+
+            debug!(
+                "Fetched {} JWKs. Cache expires in {} seconds.",
+                jwks.keys.len(),
+                ttl.as_secs()
+            );
+
+            let mut keys = self
+                .keys
+                .write()
+                .map_err(|e| OAuth2Error::InvalidPublicKey(e.to_string()))?;
+            let now = Instant::now();
+            keys.jwks = jwks;
+            keys.expires_at = now.checked_add(ttl).unwrap_or(now);
+
+            Ok(ttl)
+        }
+
+        /// Refreshes the key set every time the cached copy expires. Failed fetches
+        /// are retried after `min_refresh_interval`.
+        async fn run_refresh_loop(&'static self) {
+            loop {
+                tokio::time::sleep(self.expires_in().max(self.min_refresh_interval)).await;
+
+                if let Err(e) = self.refresh().await {
+                    warn!("Failed to refresh JWKs: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Extracts the `max-age` directive from a `Cache-Control` header value.
+    ///
+    /// Returns `Some(Duration::ZERO)` when the response must not be cached
+    /// (`no-cache` or `no-store`).
+    fn parse_max_age(cache_control: &str) -> Option<Duration> {
+        let mut max_age = None;
+
+        for directive in cache_control.split(',').map(|d| d.trim().to_lowercase()) {
+            if directive == "no-cache" || directive == "no-store" {
+                return Some(Duration::ZERO);
+            }
+
+            if let Some(seconds) = directive.strip_prefix("max-age=") {
+                max_age = seconds
+                    .trim_matches('"')
+                    .parse()
+                    .ok()
+                    .map(Duration::from_secs);
+            }
+        }
+
+        max_age
+    }
+
+    /// Creates the global JWKS store from the OAuth2 settings, fetches the key set
+    /// and starts the background refresh task.
+    ///
+    /// Nothing is done when OAuth2 is disabled or no `jwks-uri` is configured. A
+    /// failure of the initial fetch is logged and retried in the background; keys
+    /// declared in the configuration file remain available meanwhile. Later calls
+    /// keep the first store and its refresh task.
+    pub(crate) async fn initialize(settings: &Settings) {
+        let Some(oauth2) = settings.get_oauth2_config() else {
+            return;
+        };
+        let Some(jwks_uri) = oauth2.jwks_uri.clone() else {
+            return;
+        };
+        if !oauth2.enabled.unwrap_or(false) {
+            return;
+        }
+
+        let store = JwksStore::new(
+            jwks_uri.clone(),
+            oauth2.jwks.clone(),
+            Duration::from_secs(oauth2.jwks_cache_ttl.unwrap_or(DEFAULT_CACHE_TTL)),
+            Duration::from_secs(
+                oauth2
+                    .jwks_min_refresh_interval
+                    .unwrap_or(DEFAULT_MIN_REFRESH_INTERVAL),
+            ),
+        );
+
+        if JWKS_STORE.set(store).is_err() {
+            debug!("JWKS store is already initialized. The new instance will be ignored.");
+            return;
+        }
+
+        let Some(store) = JWKS_STORE.get() else {
+            return;
+        };
+
+        info!("Fetching JWKs Certs from {}", jwks_uri.bright_blue());
+        if let Err(e) = store.refresh().await {
+            warn!("Failed to fetch JWKs: {}", e);
+        }
+
+        tokio::spawn(store.run_refresh_loop());
+    }
+
+    /// Returns the key for the given `kid`.
+    ///
+    /// The key is taken from the global JWKS store when it is initialized, and from
+    /// the `configured` keys otherwise.
+    pub(crate) fn find_key(kid: &str, configured: Option<&JwkSet>) -> Option<Jwk> {
+        find_key_in(JWKS_STORE.get(), kid, configured)
+    }
+
+    /// Returns the key for the given `kid` from the `store` when set, and from the
+    /// `configured` keys otherwise.
+    fn find_key_in(
+        store: Option<&JwksStore>,
+        kid: &str,
+        configured: Option<&JwkSet>,
+    ) -> Option<Jwk> {
+        match store {
+            Some(store) => store.find(kid),
+            None => configured?.find(kid).cloned(),
+        }
+    }
+
+    /// Makes sure the key for the given `kid` is cached, fetching the key set again
+    /// when it is unknown.
+    pub(crate) async fn resolve_key(kid: &str) {
+        if let Some(store) = JWKS_STORE.get()
+            && store.find(kid).is_none()
+        {
+            store.refresh_for_unknown_kid(kid).await;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        use actix_web::{App, HttpResponse, web};
+        use jsonwebtoken::jwk::JwkSet;
+        use serde_json::json;
+
+        use super::{JwksStore, MAX_CACHE_TTL, find_key_in, parse_max_age};
+        use crate::test::mock;
+
+        #[test]
+        fn should_find_keys_only_in_the_store_once_initialized() {
+            let key = json!({ "kty": "oct", "kid": "k1", "alg": "HS256", "k": "c2VjcmV0" });
+            let jwks: JwkSet =
+                serde_json::from_value(json!({ "keys": [key] })).unwrap_or_else(|e| panic!("{e}"));
+            let store = JwksStore::new(
+                "http://localhost/certs".into(),
+                Some(jwks.clone()),
+                Duration::from_secs(300),
+                Duration::from_secs(30),
+            );
+
+            assert!(find_key_in(None, "k1", Some(&jwks)).is_some());
+            assert!(find_key_in(Some(&store), "k1", Some(&jwks)).is_some());
+
+            // The identity provider no longer publishes the key
+            if let Ok(mut keys) = store.keys.write() {
+                keys.jwks = JwkSet { keys: vec![] };
+            }
+            assert!(find_key_in(Some(&store), "k1", Some(&jwks)).is_none());
+        }
+
+        #[actix_web::test]
+        async fn should_cap_the_cache_lifetime_of_the_key_set() {
+            let base_uri = mock::serve(|| {
+                App::new().route(
+                    "/certs",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .insert_header(("Cache-Control", format!("max-age={}", u64::MAX)))
+                            .json(json!({ "keys": [] }))
+                    }),
+                )
+            });
+            let uri = format!("{base_uri}/certs");
+
+            let store =
+                JwksStore::new(uri, None, Duration::from_secs(300), Duration::from_secs(30));
+            let ttl = store.refresh().await.unwrap_or_else(|e| panic!("{e}"));
+
+            assert_eq!(ttl, MAX_CACHE_TTL);
+            assert!(store.expires_in() <= MAX_CACHE_TTL);
+        }
+
+        #[actix_web::test]
+        async fn should_fetch_unknown_kids_right_after_a_scheduled_refresh() {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let counter = requests.clone();
+            let base_uri = mock::serve(move || {
+                let counter = counter.clone();
+                App::new().route(
+                    "/certs",
+                    web::get().to(move || {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        async { HttpResponse::Ok().json(json!({ "keys": [] })) }
+                    }),
+                )
+            });
+            let uri = format!("{base_uri}/certs");
+
+            let store =
+                JwksStore::new(uri, None, Duration::from_secs(300), Duration::from_secs(30));
+            store.refresh().await.unwrap_or_else(|e| panic!("{e}"));
+
+            store.refresh_for_unknown_kid("rotated").await;
+            assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+            // Unknown key ids are still rate limited among themselves
+            store.refresh_for_unknown_kid("forged").await;
+            assert_eq!(requests.load(Ordering::SeqCst), 2);
+        }
+
+        #[test]
+        fn should_parse_max_age_from_cache_control() {
+            assert_eq!(
+                parse_max_age("public, max-age=600, must-revalidate"),
+                Some(Duration::from_secs(600))
+            );
+            assert_eq!(parse_max_age("no-store"), Some(Duration::ZERO));
+            assert_eq!(parse_max_age("public"), None);
+        }
+    }
+}
+
+pub mod introspection {
+    //! # Token Introspection
+    //!
+    //! Validates opaque (reference) tokens with the identity provider introspection
+    //! endpoint, as defined by RFC 7662. It is enabled with
+    //! `security.oauth2.token-validation: introspection`.
+    //!
+    //! The server authenticates with the `client` credentials of the OAuth2 settings.
+    //! Active responses are cached in memory, keyed by the SHA-256 hash of the token,
+    //! for `security.oauth2.introspection-cache-ttl` seconds (at most one day) and never
+    //! beyond the token expiration.
+
+    use std::collections::HashMap;
+    use std::sync::{LazyLock, RwLock};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+    use serde_json::{Map, Value};
+    use sha2::{Digest, Sha256};
+    use tracing::{debug, warn};
+
+    use crate::security::oauth2::{DEFAULT_LEEWAY, OAuth2Error, Result};
+    use crate::settings::OAuth2Configuration;
+
+    /// Default lifetime (in seconds) of the cached introspection responses.
+    const DEFAULT_CACHE_TTL: u64 = 60;
+
+    /// Maximum lifetime (in seconds) of the cached introspection responses, one day.
+    const MAX_CACHE_TTL: u64 = 86_400;
+
+    /// Maximum number of cached introspection responses.
+    const MAX_CACHE_ENTRIES: usize = 10_000;
+
+    /// Cached claims of an active token and their expiration instant.
+    struct CachedIntrospection {
+        claims: Map<String, Value>,
+        expires_at: Instant,
+    }
+
+    /// Introspection responses cache, keyed by the SHA-256 hash of the token.
+    static CACHE: LazyLock<RwLock<HashMap<String, CachedIntrospection>>> =
+        LazyLock::new(|| RwLock::new(HashMap::new()));
+
+    /// HTTP client shared by the introspection requests.
+    static CLIENT: LazyLock<ClientWithMiddleware> =
+        LazyLock::new(|| ClientBuilder::new(reqwest::Client::new()).build());
+
+    /// Returns `true` if the tokens must be validated with the introspection endpoint.
+    pub(crate) fn is_enabled(config: &OAuth2Configuration) -> bool {
+        config
+            .token_validation
+            .as_deref()
+            .is_some_and(|mode| mode.eq_ignore_ascii_case("introspection"))
+    }
+
+    /// Validates the token with the introspection endpoint and returns its claims.
+    ///
+    /// # Parameters
+    /// - `token`: The access token sent by the client.
+    /// - `config`: The OAuth2 configuration of the server.
+    ///
+    /// # Returns
+    /// The claims of the active token, taken from the cache when available.
+    ///
+    /// # Errors
+    /// This method will return an error if:
+    /// - The introspection endpoint is not configured or cannot be reached.
+    /// - The token is not active.
+    /// - The token issuer, audience, expiration or not before claims are invalid.
+    pub(crate) async fn introspect(
+        token: &str,
+        config: &OAuth2Configuration,
+    ) -> Result<Map<String, Value>> {
+        let key = format!("{:x}", Sha256::digest(token.as_bytes()));
+
+        if let Some(claims) = cached(&key) {
+            return Ok(claims);
+        }
+
+        let claims = request(token, config).await?;
+        validate_claims(&claims, config)?;
+
+        let mut ttl = Duration::from_secs(
+            config
+                .introspection_cache_ttl
+                .unwrap_or(DEFAULT_CACHE_TTL)
+                .min(MAX_CACHE_TTL),
+        );
+        if let Some(exp) = claims.get("exp").and_then(Value::as_u64) {
+            ttl = ttl.min(Duration::from_secs(exp.saturating_sub(now())));
+        }
+
+        let now = Instant::now();
+        if !ttl.is_zero()
+            && let Some(expires_at) = now.checked_add(ttl)
+            && let Ok(mut cache) = CACHE.write()
+        {
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.retain(|_, entry| entry.expires_at > now);
+            }
+            if cache.len() < MAX_CACHE_ENTRIES {
+                cache.insert(
+                    key,
+                    CachedIntrospection {
+                        claims: claims.clone(),
+                        expires_at,
+                    },
+                );
+            }
+        }
+
+        Ok(claims)
+    }
+
+    /// Returns the cached claims for the token hash, if not expired.
+    fn cached(key: &str) -> Option<Map<String, Value>> {
+        let cache = CACHE.read().ok()?;
+        let entry = cache.get(key)?;
+
+        (entry.expires_at > Instant::now()).then(|| entry.claims.clone())
+    }
+
+    /// Sends the introspection request to the identity provider.
+    async fn request(token: &str, config: &OAuth2Configuration) -> Result<Map<String, Value>> {
+        let uri = config.introspection_uri.as_ref().ok_or_else(|| {
+            warn!("Introspection URI not configured.");
+            OAuth2Error::Configuration("Introspection URI not configured.".into())
+        })?;
+        let client_id = config
+            .client
+            .as_ref()
+            .and_then(|c| c.id.clone())
+            .ok_or_else(|| OAuth2Error::Configuration("OAuth2 client ID not configured.".into()))?;
+        let client_secret = config.client.as_ref().and_then(|c| c.secret.clone());
+
+        debug!("Introspecting token with {uri}");
+
+        let response = CLIENT
+            .post(uri)
+            .basic_auth(client_id, client_secret)
+            .form(&[("token", token), ("token_type_hint", "access_token")])
+            .send()
+            .await
+            .map_err(|e| OAuth2Error::unavailable(uri, e))?
+            .error_for_status()
+            .map_err(|e| OAuth2Error::unavailable(uri, e))?;
+
+        response
+            .json::<Map<String, Value>>()
+            .await
+            .map_err(|e| OAuth2Error::unavailable(uri, e))
+    }
+
+    /// Validates the introspection response of a token.
+    fn validate_claims(claims: &Map<String, Value>, config: &OAuth2Configuration) -> Result<()> {
+        if claims.get("active").and_then(Value::as_bool) != Some(true) {
+            return Err(OAuth2Error::Unauthorized("Token is not active.".into()));
+        }
+
+        if let (Some(issuer), Some(iss)) = (
+            config.issuer_uri.as_deref(),
+            claims.get("iss").and_then(Value::as_str),
+        ) && issuer != iss
+        {
+            return Err(OAuth2Error::InvalidJwt(format!(
+                "Invalid token issuer: {iss}."
+            )));
+        }
+
+        let leeway = config.leeway.unwrap_or(DEFAULT_LEEWAY);
+        if let Some(exp) = claims.get("exp").and_then(Value::as_u64)
+            && exp.saturating_add(leeway) < now()
+        {
+            return Err(OAuth2Error::InvalidJwt("Token has expired.".into()));
+        }
+        if config.validate_nbf.unwrap_or(true)
+            && let Some(nbf) = claims.get("nbf").and_then(Value::as_u64)
+            && nbf > now().saturating_add(leeway)
+        {
+            return Err(OAuth2Error::InvalidJwt("Token is not valid yet.".into()));
+        }
+
+        if let Some(audiences) = config.audiences.as_ref().filter(|a| !a.is_empty()) {
+            let accepted = match claims.get("aud") {
+                Some(Value::String(aud)) => audiences.contains(aud),
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|aud| audiences.iter().any(|a| a == aud)),
+                _ => false,
+            };
+            if !accepted {
+                return Err(OAuth2Error::InvalidJwt("Invalid token audience.".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current UTC timestamp in seconds.
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use actix_web::{App, HttpResponse, web};
+        use serde_json::json;
+
+        use super::{introspect, validate_claims};
+        use crate::security::oauth2::OAuth2Error;
+        use crate::settings::OAuth2Configuration;
+        use crate::test::mock;
+
+        #[test]
+        fn should_reject_inactive_or_foreign_introspected_token() {
+            let config = serde_json::from_value::<OAuth2Configuration>(json!({
+                "issuer-uri": "http://localhost/realms/test",
+                "audiences": ["rust-client"]
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+            let claims = |value: serde_json::Value| value.as_object().cloned().unwrap_or_default();
+
+            assert!(validate_claims(&claims(json!({ "active": false })), &config).is_err());
+            assert!(
+                validate_claims(
+                    &claims(json!({ "active": true, "aud": ["other-client"] })),
+                    &config
+                )
+                .is_err()
+            );
+            assert!(
+                validate_claims(
+                    &claims(json!({
+                        "active": true,
+                        "iss": "http://localhost/realms/test",
+                        "aud": ["account", "rust-client"]
+                    })),
+                    &config
+                )
+                .is_ok()
+            );
+        }
+
+        #[test]
+        fn should_not_overflow_with_extreme_leeway_or_timestamps() {
+            let config = serde_json::from_value::<OAuth2Configuration>(json!({
+                "leeway": u64::MAX
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+            let claims = json!({ "active": true, "exp": u64::MAX, "nbf": u64::MAX })
+                .as_object()
+                .cloned()
+                .unwrap_or_default();
+
+            assert!(validate_claims(&claims, &config).is_ok());
+        }
+
+        #[actix_web::test]
+        async fn should_cap_the_cache_lifetime_of_introspection_responses() {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let counter = requests.clone();
+            let base_uri = mock::serve(move || {
+                let counter = counter.clone();
+                App::new().route(
+                    "/introspect",
+                    web::post().to(move || {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        async { HttpResponse::Ok().json(json!({ "active": true })) }
+                    }),
+                )
+            });
+            let uri = format!("{base_uri}/introspect");
+
+            let config = serde_json::from_value::<OAuth2Configuration>(json!({
+                "introspection-uri": uri,
+                "introspection-cache-ttl": u64::MAX,
+                "client": { "id": "rust-client", "secret": "secret" }
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+            for _ in 0..2 {
+                assert!(introspect("cached-opaque-token", &config).await.is_ok());
+            }
+            assert_eq!(requests.load(Ordering::SeqCst), 1);
+        }
+
+        #[actix_web::test]
+        async fn should_report_an_unavailable_introspection_endpoint() {
+            let base_uri = mock::serve(|| {
+                App::new().route(
+                    "/introspect",
+                    web::post().to(|| async { HttpResponse::BadGateway().body("<html></html>") }),
+                )
+            });
+            let uri = format!("{base_uri}/introspect");
+
+            let config = serde_json::from_value::<OAuth2Configuration>(json!({
+                "introspection-uri": uri,
+                "client": { "id": "rust-client", "secret": "secret" }
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+            let result = introspect("unavailable-opaque-token", &config).await;
+
+            assert!(matches!(result, Err(OAuth2Error::Unavailable(_))));
+        }
+    }
+}
+
+pub mod api_key {
+    //! # API Keys
+    //!
+    //! Authenticates the callers that send a static API key (configured in
+    //! `security.api-keys`) instead of an OAuth2 bearer token. The key is compared
+    //! with the configured SHA-256 digests and the matching entry becomes an
+    //! [`AuthenticatedUser`] checked by the same `authorize` rules as the tokens.
+
+    use std::collections::HashSet;
+
+    use serde_json::{Map, Value};
+    use sha2::{Digest, Sha256};
+    use tracing::warn;
+
+    use crate::security::oauth2::{AuthenticatedUser, OAuth2Error, Result, apply_case};
+    use crate::settings::{ApiKeys, Settings};
+
+    /// Default request header carrying the API key.
+    const DEFAULT_HEADER_NAME: &str = "X-API-Key";
+
+    /// Returns the API keys configuration, if enabled.
+    fn config(settings: &Settings) -> Option<&ApiKeys> {
+        settings
+            .security
+            .as_ref()?
+            .api_keys
+            .as_ref()
+            .filter(|api_keys| api_keys.enabled.unwrap_or(false))
+    }
+
+    /// Returns the request header carrying the API key, if the API key
+    /// authentication is enabled.
+    pub(crate) fn header_name(settings: &Settings) -> Option<&str> {
+        Some(
+            config(settings)?
+                .header_name
+                .as_deref()
+                .unwrap_or(DEFAULT_HEADER_NAME),
+        )
+    }
+
+    /// Authenticates the API key and validates the authorization expression.
+    ///
+    /// # Parameters
+    /// - `key`: The API key sent by the client.
+    /// - `settings`: The configuration settings for the server.
+    /// - `authorize`: The authorization expression of the endpoint.
+    ///
+    /// # Returns
+    /// The [`AuthenticatedUser`] of the key owner.
+    ///
+    /// # Errors
+    /// This method will return an error if the key is unknown or disabled, or if
+    /// its roles don't satisfy the authorization expression.
+    pub(crate) fn validate_api_key(
+        key: &str,
+        settings: &Settings,
+        authorize: String,
+    ) -> Result<AuthenticatedUser> {
+        let digest = format!("{:x}", Sha256::digest(key.as_bytes()));
+
+        let api_key = config(settings)
+            .and_then(|c| c.keys.as_ref())
+            .into_iter()
+            .flatten()
+            .filter(|k| k.enabled.unwrap_or(true))
+            .find(|k| constant_time_eq(k.hash.trim().to_lowercase().as_bytes(), digest.as_bytes()))
+            .ok_or_else(|| {
+                warn!("Invalid API key.");
+                OAuth2Error::Unauthorized("Invalid API key.".into())
+            })?;
+
+        let mapping = settings
+            .get_oauth2_config()
+            .and_then(|config| config.role_mapping);
+        let case = mapping.as_ref().and_then(|m| m.case.as_deref());
+
+        let mut claims = Map::new();
+        claims.insert("sub".into(), Value::String(api_key.name.clone()));
+        claims.insert("auth_type".into(), Value::String("api_key".into()));
+
+        let user = AuthenticatedUser {
+            sub: Some(api_key.name.clone()),
+            roles: api_key
+                .roles
+                .iter()
+                .flatten()
+                .map(|role| apply_case(role, case))
+                .collect(),
+            scopes: HashSet::new(),
+            claims,
+            token: None,
+        };
+
+        super::oauth2::validate_jwt_roles(&user, authorize, mapping.as_ref())?;
+
+        Ok(user)
+    }
+
+    /// Compares two byte slices in constant time.
+    fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+        left.len() == right.len()
+            && left.iter().zip(right).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use serde_json::json;
+
+        use super::validate_api_key;
+        use crate::settings::Settings;
+
+        #[test]
+        fn should_authenticate_hashed_api_key() {
+            // SHA-256 of "password"
+            let settings = serde_json::from_value::<Settings>(json!({
+                "security": {
+                    "api-keys": {
+                        "enabled": true,
+                        "keys": [{
+                            "name": "billing-job",
+                            "hash": "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+                            "roles": ["ROLE_BATCH"]
+                        }]
+                    }
+                }
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+            let user = validate_api_key("password", &settings, "hasRole(ROLE_BATCH)".into());
+            assert_eq!(user.ok().and_then(|u| u.sub), Some("billing-job".into()));
+
+            assert!(validate_api_key("password", &settings, "ROLE_ADMIN".into()).is_err());
+            assert!(validate_api_key("wrong", &settings, "ROLE_BATCH".into()).is_err());
+        }
+    }
+}
+
+pub mod client_certificate {
+    //! # Client Certificates
+    //!
+    //! Authenticates the callers presenting a client certificate verified on the mTLS
+    //! connection (`server.tls.client-ca`). The certificate subject and subject
+    //! alternative names are matched against the principals configured in
+    //! `security.client-certificates` and the matching entry becomes an
+    //! [`AuthenticatedUser`] checked by the same `authorize` rules as the tokens.
+
+    use std::collections::HashSet;
+
+    use rustls::pki_types::CertificateDer;
+    use serde_json::{Map, Value};
+    use tracing::warn;
+    use x509_parser::extensions::GeneralName;
+
+    use crate::security::oauth2::{AuthenticatedUser, OAuth2Error, Result, apply_case};
+    use crate::settings::{CertificatePrincipal, ClientCertificates, Settings};
+
+    /// End-entity certificate presented by the client of the connection, stored in
+    /// the connection data when the TLS handshake verified it.
+    #[derive(Debug, Clone)]
+    pub(crate) struct PeerCertificate(pub CertificateDer<'static>);
+
+    /// Identity read from a client certificate.
+    #[derive(Debug)]
+    struct CertificateIdentity {
+        subject: String,
+        sans: Vec<String>,
+    }
+
+    /// Returns the client certificates configuration, if enabled.
+    fn config(settings: &Settings) -> Option<&ClientCertificates> {
+        settings
+            .security
+            .as_ref()?
+            .client_certificates
+            .as_ref()
+            .filter(|certificates| certificates.enabled.unwrap_or(false))
+    }
+
+    /// Returns `true` if the client certificate authentication is enabled.
+    pub(crate) fn is_enabled(settings: &Settings) -> bool {
+        config(settings).is_some()
+    }
+
+    /// Authenticates the client certificate and validates the authorization expression.
+    ///
+    /// # Parameters
+    /// - `certificate`: The client certificate verified on the connection.
+    /// - `settings`: The configuration settings for the server.
+    /// - `authorize`: The authorization expression of the endpoint.
+    ///
+    /// # Returns
+    /// The [`AuthenticatedUser`] of the matching principal.
+    ///
+    /// # Errors
+    /// This method will return an error if the certificate cannot be parsed or
+    /// matches no enabled principal, or if the principal roles don't satisfy the
+    /// authorization expression.
+    pub(crate) fn validate_client_certificate(
+        certificate: &PeerCertificate,
+        settings: &Settings,
+        authorize: String,
+    ) -> Result<AuthenticatedUser> {
+        let identity = parse_identity(&certificate.0)?;
+
+        let principal = config(settings)
+            .and_then(|c| c.principals.as_ref())
+            .into_iter()
+            .flatten()
+            .filter(|p| p.enabled.unwrap_or(true))
+            .find(|p| matches_principal(p, &identity))
+            .ok_or_else(|| {
+                warn!("Unknown client certificate {}.", identity.subject);
+                OAuth2Error::Unauthorized("Unknown client certificate.".into())
+            })?;
+
+        let mapping = settings
+            .get_oauth2_config()
+            .and_then(|config| config.role_mapping);
+        let case = mapping.as_ref().and_then(|m| m.case.as_deref());
+
+        let mut claims = Map::new();
+        claims.insert("sub".into(), Value::String(principal.name.clone()));
+        claims.insert(
+            "auth_type".into(),
+            Value::String("client_certificate".into()),
+        );
+        claims.insert(
+            "certificate_subject".into(),
+            Value::String(identity.subject.clone()),
+        );
+        claims.insert(
+            "certificate_sans".into(),
+            Value::Array(identity.sans.into_iter().map(Value::String).collect()),
+        );
+
+        let user = AuthenticatedUser {
+            sub: Some(principal.name.clone()),
+            roles: principal
+                .roles
+                .iter()
+                .flatten()
+                .map(|role| apply_case(role, case))
+                .collect(),
+            scopes: HashSet::new(),
+            claims,
+            token: None,
+        };
+
+        super::oauth2::validate_jwt_roles(&user, authorize, mapping.as_ref())?;
+
+        Ok(user)
+    }
+
+    /// Reads the subject and the subject alternative names of the certificate.
+    fn parse_identity(certificate: &CertificateDer<'_>) -> Result<CertificateIdentity> {
+        let (_, certificate) = x509_parser::parse_x509_certificate(certificate)
+            .map_err(|e| OAuth2Error::Unauthorized(format!("Invalid client certificate: {e}")))?;
+
+        let sans = certificate
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|extension| {
+                extension
+                    .value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(value)
+                        | GeneralName::URI(value)
+                        | GeneralName::RFC822Name(value) => Some(value.to_string()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(CertificateIdentity {
+            subject: certificate.subject().to_string(),
+            sans,
+        })
+    }
+
+    /// Returns `true` if the certificate matches the subject and SAN of the principal.
+    /// A principal without subject nor SAN matches no certificate.
+    fn matches_principal(principal: &CertificatePrincipal, identity: &CertificateIdentity) -> bool {
+        if principal.subject.is_none() && principal.san.is_none() {
+            return false;
+        }
+
+        let subject_matches = principal
+            .subject
+            .as_deref()
+            .is_none_or(|subject| normalize_dn(subject) == normalize_dn(&identity.subject));
+        let san_matches = principal
+            .san
+            .as_deref()
+            .is_none_or(|san| identity.sans.iter().any(|value| value == san.trim()));
+
+        subject_matches && san_matches
+    }
+
+    /// Normalizes a distinguished name, ignoring the attribute order, the spacing
+    /// and the case of the attribute types.
+    fn normalize_dn(dn: &str) -> Vec<String> {
+        let mut attributes = dn
+            .split([',', '+'])
+            .filter_map(|attribute| attribute.split_once('='))
+            .map(|(name, value)| format!("{}={}", name.trim().to_uppercase(), value.trim()))
+            .collect::<Vec<_>>();
+        attributes.sort();
+        attributes
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use rustls::pki_types::CertificateDer;
+        use rustls::pki_types::pem::PemObject;
+        use serde_json::json;
+
+        use super::{PeerCertificate, validate_client_certificate};
+        use crate::settings::Settings;
+
+        /// Self-signed test certificate (O=Example, CN=billing) with a SPIFFE URI and
+        /// a DNS subject alternative name.
+        const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIB7DCCAZKgAwIBAgIUJE+s1oGxfGXKHNivzNr6mz2VvgUwCgYIKoZIzj0EAwIw
+JDEQMA4GA1UECgwHRXhhbXBsZTEQMA4GA1UEAwwHYmlsbGluZzAgFw0yNjEwMTYx
+OTExMjdaGA8yMTI2MDkyMjE5MTEyN1owJDEQMA4GA1UECgwHRXhhbXBsZTEQMA4G
+A1UEAwwHYmlsbGluZzBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABIaD519LDW6M
+xNzvCYZtw4VWpoAG9ux1UObgGdfijK8lZ+zdRHuXUTEWJlkKAp898y4vMH7WQk98
+ErOQW3xQTBujgZ8wgZwwHQYDVR0OBBYEFGNzEepZDgW2NEsy42xVlvGjJ2xpMB8G
+A1UdIwQYMBaAFGNzEepZDgW2NEsy42xVlvGjJ2xpMA8GA1UdEwEB/wQFMAMBAf8w
+SQYDVR0RBEIwQIYsc3BpZmZlOi8vY2x1c3Rlci5sb2NhbC9ucy9iaWxsaW5nL3Nh
+L2JpbGxpbmeCEGJpbGxpbmcuaW50ZXJuYWwwCgYIKoZIzj0EAwIDSAAwRQIgLZwZ
+h1G5U+H4K2aPXiNwyuzSLKqT+q/jxpN0XXQF8O8CIQCsmcab7rDsgc08wq8znJCu
+smMecYZfytTN56clEx7lUQ==
+-----END CERTIFICATE-----
+";
+
+        #[test]
+        fn should_map_client_certificate_to_principal() {
+            let settings = serde_json::from_value::<Settings>(json!({
+                "security": {
+                    "client-certificates": {
+                        "enabled": true,
+                        "principals": [
+                            {
+                                "name": "billing-service",
+                                "san": "spiffe://cluster.local/ns/billing/sa/billing",
+                                "roles": ["ROLE_BATCH"]
+                            },
+                            {
+                                "name": "billing-admin",
+                                "subject": "CN=billing, O=Other",
+                                "roles": ["ROLE_ADMIN"]
+                            }
+                        ]
+                    }
+                }
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+            let certificate = PeerCertificate(
+                CertificateDer::from_pem_slice(CERTIFICATE.as_bytes())
+                    .unwrap_or_else(|e| panic!("{e}")),
+            );
+
+            let user =
+                validate_client_certificate(&certificate, &settings, "ROLE_BATCH".into()).ok();
+            assert_eq!(
+                user.as_ref().and_then(|u| u.sub.as_deref()),
+                Some("billing-service")
+            );
+            assert_eq!(
+                user.as_ref()
+                    .and_then(|u| u.claim("certificate_subject"))
+                    .and_then(|v| v.as_str()),
+                Some("O=Example, CN=billing")
+            );
+
+            assert!(
+                validate_client_certificate(&certificate, &settings, "ROLE_ADMIN".into()).is_err()
+            );
+        }
+
+        #[test]
+        fn should_match_subject_ignoring_attribute_order() {
+            let principal = serde_json::from_value(json!({
+                "name": "billing",
+                "subject": "cn=billing,O=Example"
+            }))
+            .unwrap_or_else(|e| panic!("{e}"));
+            let identity = super::CertificateIdentity {
+                subject: "O=Example, CN=billing".into(),
+                sans: Vec::new(),
+            };
+
+            assert!(super::matches_principal(&principal, &identity));
+        }
+    }
+}
+
+pub mod authorize {
+    //! # Authorization Expressions
+    //!
+    //! Parses and evaluates the `authorize` rules declared with `#[secured]`.
+    //!
+    //! An expression combines the functions below with `and` / `&&`, `or` / `||`,
+    //! `not` / `!` and parentheses. Function names are case-insensitive and the
+    //! arguments are either bare identifiers (`ROLE_ADMIN`) or quoted strings
+    //! (`'read:users'`).
+    //!
+    //! | Function                   | Description                                             |
+    //! | -------------------------- | ------------------------------------------------------- |
+    //! | `hasRole(role)`            | The user has the role.                                  |
+    //! | `hasAnyRole(role, ...)`    | The user has at least one of the roles.                 |
+    //! | `hasAllRoles(role, ...)`   | The user has all the roles.                             |
+    //! | `hasScope(scope)`          | The token was granted the scope.                        |
+    //! | `hasAnyScope(scope, ...)`  | The token was granted at least one of the scopes.       |
+    //! | `hasClaim(name)`           | The token has the claim.                                |
+    //! | `hasClaim(name, value)`    | The claim is equal to (or, for arrays, contains) value. |
+    //! | `isAuthenticated()`        | The token is valid.                                     |
+    //! | `permitAll()`              | Always granted.                                         |
+    //! | `denyAll()`                | Always denied.                                          |
+    //!
+    //! A single role (`ROLE_ADMIN`) is accepted as a shorthand for `hasRole(ROLE_ADMIN)`
+    //! and a list of roles (`ROLE_ADMIN, ROLE_USER`) for `hasAnyRole(ROLE_ADMIN, ROLE_USER)`.
+    //!
+    //! The same grammar is checked at compile time by the `secured` macro, so both
+    //! parsers must be kept in sync. Both are tested with the expressions of
+    //! `rust-microservice-macros/tests/authorize_expressions.txt`. At runtime, each rule is parsed once and the
+    //! parsed expression is shared by the following requests.
+
+    use std::collections::HashMap;
+    use std::sync::{Arc, LazyLock, RwLock};
+
+    use serde_json::Value;
+
+    use crate::security::oauth2::{AuthenticatedUser, OAuth2Error, Result};
+
+    /// Maximum number of cached expressions.
+    const MAX_CACHE_ENTRIES: usize = 1024;
+
+    /// Parsed expressions, keyed by their source rule.
+    static EXPRESSIONS: LazyLock<RwLock<HashMap<String, Arc<Expression>>>> =
+        LazyLock::new(|| RwLock::new(HashMap::new()));
+
+    /// A parsed authorization expression.
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum Expression {
+        PermitAll,
+        DenyAll,
+        IsAuthenticated,
+        HasRole(String),
+        HasAnyRole(Vec<String>),
+        HasAllRoles(Vec<String>),
+        HasScope(String),
+        HasAnyScope(Vec<String>),
+        HasClaim(String, Option<String>),
+        Not(Box<Expression>),
+        And(Box<Expression>, Box<Expression>),
+        Or(Box<Expression>, Box<Expression>),
+    }
+
+    impl Expression {
+        /// Parses an authorization expression.
+        ///
+        /// # Errors
+        /// Returns `OAuth2Error::RoleAuthorizationParse` if the expression is invalid.
+        pub(crate) fn parse(authorize: &str) -> Result<Self> {
+            let tokens = tokenize(authorize)?;
+            if let Some(roles) = role_list(&tokens) {
+                return Ok(Expression::HasAnyRole(roles));
+            }
+
+            let mut parser = Parser {
+                tokens,
+                position: 0,
+            };
+
+            let expression = parser.parse_or()?;
+            if let Some(token) = parser.peek() {
+                return Err(parse_error(format!("unexpected {token}")));
+            }
+
+            Ok(expression)
+        }
+
+        /// Returns the parsed expression of a rule, parsing it on its first use.
+        ///
+        /// # Errors
+        /// Returns `OAuth2Error::RoleAuthorizationParse` if the expression is invalid.
+        pub(crate) fn cached(authorize: &str) -> Result<Arc<Self>> {
+            if let Some(expression) = EXPRESSIONS
+                .read()
+                .ok()
+                .and_then(|cache| cache.get(authorize).cloned())
+            {
+                return Ok(expression);
+            }
+
+            let expression = Arc::new(Expression::parse(authorize)?);
+            if let Ok(mut cache) = EXPRESSIONS.write()
+                && cache.len() < MAX_CACHE_ENTRIES
+            {
+                cache.insert(authorize.to_string(), expression.clone());
+            }
+
+            Ok(expression)
+        }
+
+        /// Evaluates the expression for the given user.
+        ///
+        /// The roles of the expression are normalized with the role mapping `case`
+        /// before being compared with the roles of the user.
+        pub(crate) fn evaluate(&self, user: &AuthenticatedUser, case: Option<&str>) -> bool {
+            let has_role = |role: &String| user.has_role(&super::oauth2::apply_case(role, case));
+
+            match self {
+                Expression::PermitAll | Expression::IsAuthenticated => true,
+                Expression::DenyAll => false,
+                Expression::HasRole(role) => has_role(role),
+                Expression::HasAnyRole(roles) => roles.iter().any(has_role),
+                Expression::HasAllRoles(roles) => roles.iter().all(has_role),
+                Expression::HasScope(scope) => user.has_scope(scope),
+                Expression::HasAnyScope(scopes) => scopes.iter().any(|s| user.has_scope(s)),
+                Expression::HasClaim(name, value) => match (user.claim(name), value) {
+                    (None, _) => false,
+                    (Some(_), None) => true,
+                    (Some(claim), Some(value)) => claim_matches(claim, value),
+                },
+                Expression::Not(expression) => !expression.evaluate(user, case),
+                Expression::And(left, right) => {
+                    left.evaluate(user, case) && right.evaluate(user, case)
+                }
+                Expression::Or(left, right) => {
+                    left.evaluate(user, case) || right.evaluate(user, case)
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the claim is equal to the value or, for arrays, contains it.
+    fn claim_matches(claim: &Value, value: &str) -> bool {
+        match claim {
+            Value::String(claim) => claim == value,
+            Value::Array(items) => items.iter().any(|item| claim_matches(item, value)),
+            Value::Number(number) => number.to_string() == value,
+            Value::Bool(boolean) => boolean.to_string() == value,
+            Value::Null | Value::Object(_) => false,
+        }
+    }
+
+    /// Lexical tokens of an authorization expression.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Ident(String),
+        Str(String),
+        LParen,
+        RParen,
+        Comma,
+        And,
+        Or,
+        Not,
+    }
+
+    impl std::fmt::Display for Token {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Token::Ident(ident) => write!(f, "`{ident}`"),
+                Token::Str(value) => write!(f, "'{value}'"),
+                Token::LParen => write!(f, "`(`"),
+                Token::RParen => write!(f, "`)`"),
+                Token::Comma => write!(f, "`,`"),
+                Token::And => write!(f, "`and`"),
+                Token::Or => write!(f, "`or`"),
+                Token::Not => write!(f, "`not`"),
+            }
+        }
+    }
+
+    /// Creates an expression parse error.
+    fn parse_error(message: String) -> OAuth2Error {
+        OAuth2Error::RoleAuthorizationParse(message)
+    }
+
+    /// Splits the expression into tokens.
+    fn tokenize(input: &str) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '(' => tokens.push(Token::LParen),
+                ')' => tokens.push(Token::RParen),
+                ',' => tokens.push(Token::Comma),
+                '!' => tokens.push(Token::Not),
+                '&' | '|' => {
+                    if chars.next_if_eq(&c).is_none() {
+                        return Err(parse_error(format!("expected `{c}{c}`")));
+                    }
+                    tokens.push(if c == '&' { Token::And } else { Token::Or });
+                }
+                '\'' | '"' => {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(next) if next == c => break,
+                            Some(next) => value.push(next),
+                            None => return Err(parse_error("unterminated string".into())),
+                        }
+                    }
+                    tokens.push(Token::Str(value));
+                }
+                c if c.is_alphanumeric() || c == '_' => {
+                    let mut ident = c.to_string();
+                    while let Some(next) = chars.next_if(|n| n.is_alphanumeric() || *n == '_') {
+                        ident.push(next);
+                    }
+                    tokens.push(match ident.to_lowercase().as_str() {
+                        "and" => Token::And,
+                        "or" => Token::Or,
+                        "not" => Token::Not,
+                        _ => Token::Ident(ident),
+                    });
+                }
+                other => return Err(parse_error(format!("unexpected character `{other}`"))),
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    /// Returns the roles of a `ROLE_A, ROLE_B` list, a shorthand for
+    /// `hasAnyRole(ROLE_A, ROLE_B)`.
+    fn role_list(tokens: &[Token]) -> Option<Vec<String>> {
+        if tokens.len() < 2 || tokens.len().is_multiple_of(2) {
+            return None;
+        }
+
+        tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(index, token)| match token {
+                Token::Ident(role)
+                    if index.is_multiple_of(2) && role.to_uppercase().starts_with("ROLE_") =>
+                {
+                    Some(Some(role.clone()))
+                }
+                Token::Comma if !index.is_multiple_of(2) => None,
+                _ => Some(None),
+            })
+            .collect()
+    }
+
+    /// Recursive descent parser of authorization expressions.
+    ///
+    /// ```text
+    /// rule    := ROLE_ident ("," ROLE_ident)+ | or
+    /// or      := and (("or" | "||") and)*
+    /// and     := unary (("and" | "&&") unary)*
+    /// unary   := ("not" | "!") unary | primary
+    /// primary := "(" or ")" | ident "(" args? ")" | ident
+    /// args    := (ident | string) ("," (ident | string))*
+    /// ```
+    struct Parser {
+        tokens: Vec<Token>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.position)
+        }
+
+        fn next(&mut self) -> Option<Token> {
+            let token = self.tokens.get(self.position).cloned();
+            self.position += 1;
+            token
+        }
+
+        fn expect(&mut self, expected: Token) -> Result<()> {
+            match self.next() {
+                Some(token) if token == expected => Ok(()),
+                Some(token) => Err(parse_error(format!("expected {expected}, found {token}"))),
+                None => Err(parse_error(format!("expected {expected}"))),
+            }
+        }
+
+        fn parse_or(&mut self) -> Result<Expression> {
+            let mut expression = self.parse_and()?;
+            while self.peek() == Some(&Token::Or) {
+                self.position += 1;
+                expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+            }
+            Ok(expression)
+        }
+
+        fn parse_and(&mut self) -> Result<Expression> {
+            let mut expression = self.parse_unary()?;
+            while self.peek() == Some(&Token::And) {
+                self.position += 1;
+                expression = Expression::And(Box::new(expression), Box::new(self.parse_unary()?));
+            }
+            Ok(expression)
+        }
+
+        fn parse_unary(&mut self) -> Result<Expression> {
+            if self.peek() == Some(&Token::Not) {
+                self.position += 1;
+                return Ok(Expression::Not(Box::new(self.parse_unary()?)));
+            }
+            self.parse_primary()
+        }
+
+        fn parse_primary(&mut self) -> Result<Expression> {
+            match self.next() {
+                Some(Token::LParen) => {
+                    let expression = self.parse_or()?;
+                    self.expect(Token::RParen)?;
+                    Ok(expression)
+                }
+                Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                    self.position += 1;
+                    let args = self.parse_args()?;
+                    function(&name, args)
+                }
+                Some(Token::Ident(role)) if role.to_uppercase().starts_with("ROLE_") => {
+                    Ok(Expression::HasRole(role))
+                }
+                Some(token) => Err(parse_error(format!("unexpected {token}"))),
+                None => Err(parse_error("unexpected end of expression".into())),
+            }
+        }
+
+        fn parse_args(&mut self) -> Result<Vec<String>> {
+            let mut args = Vec::new();
+            if self.peek() == Some(&Token::RParen) {
+                self.position += 1;
+                return Ok(args);
+            }
+
+            loop {
+                match self.next() {
+                    Some(Token::Ident(value)) | Some(Token::Str(value)) => args.push(value),
+                    Some(token) => return Err(parse_error(format!("unexpected {token}"))),
+                    None => return Err(parse_error("unterminated function call".into())),
+                }
+                match self.next() {
+                    Some(Token::Comma) => continue,
+                    Some(Token::RParen) => return Ok(args),
+                    Some(token) => return Err(parse_error(format!("unexpected {token}"))),
+                    None => return Err(parse_error("unterminated function call".into())),
+                }
+            }
+        }
+    }
+
+    /// Builds the expression of a function call, checking its arguments.
+    fn function(name: &str, mut args: Vec<String>) -> Result<Expression> {
+        let arity_error = |expected: &str| {
+            Err(parse_error(format!(
+                "`{name}` expects {expected}, found {} argument(s)",
+                args.len()
+            )))
+        };
+
+        match name.to_lowercase().as_str() {
+            "permitall" if args.is_empty() => Ok(Expression::PermitAll),
+            "denyall" if args.is_empty() => Ok(Expression::DenyAll),
+            "isauthenticated" if args.is_empty() => Ok(Expression::IsAuthenticated),
+            "permitall" | "denyall" | "isauthenticated" => arity_error("no arguments"),
+            "hasrole" if args.len() == 1 => Ok(Expression::HasRole(args.remove(0))),
+            "hasscope" if args.len() == 1 => Ok(Expression::HasScope(args.remove(0))),
+            "hasrole" | "hasscope" => arity_error("one argument"),
+            "hasanyrole" if !args.is_empty() => Ok(Expression::HasAnyRole(args)),
+            "hasallroles" if !args.is_empty() => Ok(Expression::HasAllRoles(args)),
+            "hasanyscope" if !args.is_empty() => Ok(Expression::HasAnyScope(args)),
+            "hasanyrole" | "hasallroles" | "hasanyscope" => arity_error("at least one argument"),
+            "hasclaim" if args.len() == 1 => Ok(Expression::HasClaim(args.remove(0), None)),
+            "hasclaim" if args.len() == 2 => {
+                let value = args.remove(1);
+                Ok(Expression::HasClaim(args.remove(0), Some(value)))
+            }
+            "hasclaim" => arity_error("one or two arguments"),
+            _ => Err(parse_error(format!("unknown function `{name}`"))),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::sync::Arc;
+
+        use super::Expression;
+
+        #[test]
+        fn should_parse_logical_expression_with_precedence() {
+            let expression = Expression::parse(
+                "hasRole('ROLE_ADMIN') or hasScope('read:users') and not hasClaim(tenant, 'acme')",
+            );
+
+            assert_eq!(
+                expression.ok(),
+                Some(Expression::Or(
+                    Box::new(Expression::HasRole("ROLE_ADMIN".into())),
+                    Box::new(Expression::And(
+                        Box::new(Expression::HasScope("read:users".into())),
+                        Box::new(Expression::Not(Box::new(Expression::HasClaim(
+                            "tenant".into(),
+                            Some("acme".into())
+                        ))))
+                    ))
+                ))
+            );
+        }
+
+        #[test]
+        fn should_parse_each_rule_once() {
+            let rule = "hasAnyRole(ROLE_ADMIN, ROLE_AUDITOR) and hasScope('audit:read')";
+
+            let first = Expression::cached(rule).unwrap_or_else(|e| panic!("{e}"));
+            let second = Expression::cached(rule).unwrap_or_else(|e| panic!("{e}"));
+
+            assert!(Arc::ptr_eq(&first, &second));
+            assert_eq!(Expression::parse(rule).ok().as_ref(), Some(first.as_ref()));
+            assert!(Expression::cached("hasRole(ROLE_ADMIN").is_err());
+        }
+
+        #[test]
+        fn should_accept_the_same_expressions_as_the_secured_macro() {
+            let expressions =
+                include_str!("../rust-microservice-macros/tests/authorize_expressions.txt");
+
+            for line in expressions
+                .lines()
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            {
+                let (expected, expression) = line.split_once(' ').unwrap_or((line, ""));
+                assert_eq!(
+                    Expression::parse(expression).is_ok(),
+                    expected == "valid",
+                    "unexpected result for `{expression}`"
+                );
+            }
+
+            assert_eq!(
+                Expression::parse("ROLE_ADMIN, ROLE_USER").ok(),
+                Some(Expression::HasAnyRole(vec![
+                    "ROLE_ADMIN".into(),
+                    "ROLE_USER".into()
+                ]))
+            );
+        }
+
+        #[test]
+        fn should_reject_invalid_expression() {
+            assert!(Expression::parse("hasRol(ROLE_ADMIN)").is_err());
+            assert!(Expression::parse("hasRole(ROLE_ADMIN").is_err());
+            assert!(Expression::parse("hasRole(ROLE_ADMIN) and").is_err());
+            assert!(Expression::parse("isAuthenticated(ROLE_ADMIN)").is_err());
         }
     }
 }
@@ -41,31 +41,29 @@
 //! The server module loads CLI parameters from the command line and overrides the configuration
 //! accordingly.
 
-use crate::settings::{OAuth2Configuration, Security, Settings};
-use crate::{cmd::root::Cli, data::bigquery};
-use crate::{data, security};
+use crate::health::{self, HealthIndicator};
+use crate::lifecycle::{LifecycleHooks, LifecycleState, Phase};
+use crate::security::oauth2::AuthenticatedUser;
+use crate::settings::{OAuth2Configuration, Settings};
+use crate::{cmd::root::Cli, data::bigquery, data::redis};
+use crate::{data, http::client, lifecycle, logging, security, telemetry};
 use actix_web::dev::ServiceRequest;
 use actix_web::http::header;
 use actix_web::web::ServiceConfig;
 use clap::Parser;
 use colored::Colorize;
-use env_logger::{Builder, Env};
-use jsonwebtoken::jwk::JwkSet;
 use log::{info, warn};
-use reqwest_middleware::ClientBuilder;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use sea_orm::DatabaseConnection;
 use std::any::Any;
-use std::io::Write;
-use std::sync::OnceLock;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
 use thiserror::Error;
 use tracing::{debug, error};
 
 #[cfg(feature = "memory-database")]
 use sea_orm::MockDatabase;
 
-#[cfg(feature = "memory-database")]
-use std::sync::Arc;
-
 /// Global static instance of the [`Server`].
 static SERVER: OnceLock<Box<dyn GlobalServer + Send + Sync>> = OnceLock::new();
 
@@ -77,11 +75,15 @@ static SERVER: OnceLock<Box<dyn GlobalServer + Send + Sync>> = OnceLock::new();
 /// and an optional configuration callback for the Actix-Web service.
 #[derive(Clone)]
 pub struct Server {
-    running: bool,
+    app_version: String,
     args: Option<Cli>,
     settings: Option<Settings>,
     fnconfig: Option<fn(&mut ServiceConfig)>,
     database: Option<data::ServerDatabase>,
+    http_client: Option<ClientWithMiddleware>,
+    http_clients: client::HttpClientRegistry,
+    lifecycle: LifecycleHooks,
+    health_indicators: Vec<Arc<dyn HealthIndicator>>,
 }
 
 // Implementation of the `GlobalServer` trait for the `Server` struct.
@@ -213,14 +215,18 @@ impl Server {
     pub fn new(app_version: String, banner: Option<String>) -> Self {
         Server::check_initialized();
 
-        Server::preflight(app_version, banner);
+        Server::preflight(app_version.clone(), banner);
 
         Server {
-            running: false,
+            app_version,
             args: None,
             settings: None,
             fnconfig: None,
             database: None,
+            http_client: None,
+            http_clients: client::HttpClientRegistry::default(),
+            lifecycle: LifecycleHooks::default(),
+            health_indicators: Vec::new(),
         }
     }
 
@@ -241,11 +247,15 @@ impl Server {
         let databases = data::ServerDatabase::new_with_mock_database(name, database);
 
         Server {
-            running: false,
+            app_version: String::new(),
             args: None,
             settings: None,
             fnconfig: None,
             database: Some(databases),
+            http_client: None,
+            http_clients: client::HttpClientRegistry::default(),
+            lifecycle: LifecycleHooks::default(),
+            health_indicators: Vec::new(),
         }
     }
 
@@ -275,33 +285,38 @@ impl Server {
             .map_err(|e| ServerError::Database(e.to_string()))?;
 
         Ok(Server {
-            running: false,
+            app_version: String::new(),
             args: None,
             settings: None,
             fnconfig: None,
             database: Some(databases),
+            http_client: None,
+            http_clients: client::HttpClientRegistry::default(),
+            lifecycle: LifecycleHooks::default(),
+            health_indicators: Vec::new(),
         })
     }
 
     pub async fn new_with_settings(settings: Settings) -> Result<Self> {
         Server::preflight("".into(), None);
 
-        let result = Server::discover_oauth_security_settings(&settings).await;
+        logging::configure(&settings).map_err(|e| ServerError::Configuration(e.to_string()))?;
 
-        let settings = match result {
-            Ok(s) => s,
-            Err(e) => {
-                info!("Failed to discover OAuth2 security settings: {}", e);
-                settings
-            }
-        };
+        let settings = Server::load_oauth_security_settings(settings).await;
+        let http_client = client::create_oauth2_client(&settings);
+        let http_clients = client::HttpClientRegistry::from_settings(&settings)
+            .map_err(|e| ServerError::Configuration(e.to_string()))?;
 
         let server = Server {
-            running: false,
+            app_version: String::new(),
             args: None,
             settings: Some(settings),
             fnconfig: None,
             database: None,
+            http_client,
+            http_clients,
+            lifecycle: LifecycleHooks::default(),
+            health_indicators: Vec::new(),
         };
 
         Ok(server)
@@ -321,7 +336,28 @@ impl Server {
         let args = Cli::parse();
         let settings =
             Cli::load_config(&args).map_err(|e| ServerError::Configuration(e.to_string()))?;
+        logging::configure(&settings).map_err(|e| ServerError::Configuration(e.to_string()))?;
+
+        let settings = Server::load_oauth_security_settings(settings).await;
 
+        self.http_client = client::create_oauth2_client(&settings);
+        self.http_clients = client::HttpClientRegistry::from_settings(&settings)
+            .map_err(|e| ServerError::Configuration(e.to_string()))?;
+        self.settings = Some(settings);
+        self.args = Some(args);
+
+        Ok(self)
+    }
+
+    /// Resolves the OAuth2 security settings and starts the JWKS store.
+    ///
+    /// The settings are first completed through the OpenID discovery document (when
+    /// enabled). The JWKS store is then initialized from the resolved `jwks-uri` and
+    /// refreshed in the background, so key rotations on the identity provider are
+    /// picked up without restarting the server.
+    ///
+    /// Failures are logged and the original settings are kept.
+    async fn load_oauth_security_settings(settings: Settings) -> Settings {
         let result = Server::discover_oauth_security_settings(&settings).await;
         let settings = match result {
             Ok(s) => s,
@@ -331,10 +367,9 @@ impl Server {
             }
         };
 
-        self.settings = Some(settings);
-        self.args = Some(args);
+        security::jwks::initialize(&settings).await;
 
-        Ok(self)
+        settings
     }
 
     /// Discovers OAuth2 security settings from the configuration or an
@@ -373,7 +408,7 @@ impl Server {
                 discovery_url.bright_blue()
             );
 
-            let mut discovery = client
+            let discovery = client
                 .get(discovery_url)
                 .send()
                 .await
@@ -388,39 +423,24 @@ impl Server {
                     ServerError::Configuration(e.to_string())
                 })?;
 
-            discovery.enabled = oauth2.enabled;
-            discovery.discovery_url = Some(discovery_url.clone());
-            discovery.discovery_enabled = Some(true);
-
             //info!("Discovered OAuth2 security settings: {:#?}", discovery);
 
-            if let Some(jwks_uri) = discovery.jwks_uri.clone() {
-                info!("Fetching JWKs Certs from {}", jwks_uri.bright_blue());
-
-                let jwks = client
-                    .get(jwks_uri)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        info!("Failed to fetch JWKs: {}", e);
-                        ServerError::Configuration(e.to_string())
-                    })?
-                    .json::<JwkSet>()
-                    .await
-                    .map_err(|e| {
-                        info!("Failed to parse JWKs: {}", e);
-                        ServerError::Configuration(e.to_string())
-                    })?;
-
-                //info!("Discovered JWKs: {:#?}", jwks);
-
-                discovery.jwks = Some(jwks);
-            }
+            // Discovered endpoints take precedence. Local-only settings (client
+            // credentials, JWKS cache parameters, ...) are preserved.
+            let mut oauth2 = oauth2.clone();
+            oauth2.discovery_enabled = Some(true);
+            oauth2.issuer_uri = discovery.issuer_uri.or(oauth2.issuer_uri);
+            oauth2.jwks_uri = discovery.jwks_uri.or(oauth2.jwks_uri);
+            oauth2.token_uri = discovery.token_uri.or(oauth2.token_uri);
+            oauth2.authorization_uri = discovery.authorization_uri.or(oauth2.authorization_uri);
+            oauth2.introspection_uri = discovery.introspection_uri.or(oauth2.introspection_uri);
+            oauth2.user_info_uri = discovery.user_info_uri.or(oauth2.user_info_uri);
+            oauth2.end_session_uri = discovery.end_session_uri.or(oauth2.end_session_uri);
 
             let mut settings = settings.clone();
-            settings.security = Some(Security {
-                oauth2: Some(discovery),
-            });
+            if let Some(security) = settings.security.as_mut() {
+                security.oauth2 = Some(oauth2);
+            }
 
             //info!("Updated OAuth2 security settings: {:#?}", settings);
 
@@ -444,44 +464,17 @@ impl Server {
     ///   and `actix_web_prom`.
     /// - Applies colorized output based on the log level.
     /// - Formats log entries with timestamp, level, module path, and message.
+    /// - Allows changing the log levels at runtime through `/actuator/loggers`.
+    /// - Switches to the `logging` settings (e.g. JSON output) once they are loaded.
     ///
     /// # Returns
     ///
     /// Returns `Self` to allow method chaining during application configuration.
     fn configure_log() -> Result<()> {
-        // Initialize Logger ENV
-        let level = Env::default().default_filter_or("info,actix_web=error,actix_web_prom=error");
-
-        let _ = Builder::from_env(level)
-            .format(|buf, record| {
-                let level = match record.level() {
-                    log::Level::Info => record.level().as_str().bright_green(),
-                    log::Level::Debug => record.level().as_str().bright_blue(),
-                    log::Level::Trace => record.level().as_str().bright_cyan(),
-                    log::Level::Warn => record.level().as_str().bright_yellow(),
-                    log::Level::Error => record.level().as_str().bright_red(),
-                };
-
-                let datetime = chrono::Local::now()
-                    .format("%d-%m-%YT%H:%M:%S%.3f%:z")
-                    .to_string()
-                    .white();
-
-                // Align timestamp, level, and module path
-                writeln!(
-                    buf,
-                    "{:<24}  {:<5} [{:<60}] - {}",
-                    datetime,                                         // Timestamp
-                    level,                                            // Log level
-                    record.module_path().unwrap_or("unknown").blue(), // Module path
-                    record.args()                                     // Log message
-                )
-            })
-            .try_init();
+        // An already installed logger is kept
+        let _ = logging::init(60);
 
         Ok(())
-
-        //env_logger::init_from_env(level);
     }
 
     /// Applies a custom Actix-Web configuration callback to the server.
@@ -503,7 +496,7 @@ impl Server {
     /// Initializes the database connections using the previously loaded settings.
     ///
     /// This method creates and initializes all required database connections,
-    /// including the BigQuery client, based on the application settings.
+    /// including the Redis and BigQuery clients, based on the application settings.
     /// It must be called **after** the settings have been loaded.
     ///
     /// # Behavior
@@ -555,25 +548,92 @@ impl Server {
         Ok(self)
     }
 
+    /// Registers an asynchronous hook run before the listeners are opened.
+    ///
+    /// Startup hooks run in registration order. A failing hook aborts the startup
+    /// and the remaining hooks are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use rust_microservice::Server;
+    ///
+    /// let server = Server::new("0.1.3".to_string(), None)
+    ///     .on_startup(|| async { Ok::<_, String>(()) });
+    /// ```
+    pub fn on_startup<F, Fut, E>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<(), E>> + 'static,
+        E: std::fmt::Display,
+    {
+        Server::check_initialized();
+        self.lifecycle.register(Phase::Startup, hook);
+        self
+    }
+
+    /// Registers an asynchronous hook run once the main and health-check servers
+    /// accept requests (e.g. to register the instance in a service registry).
+    ///
+    /// Failures are logged and don't stop the server.
+    pub fn on_ready<F, Fut, E>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<(), E>> + 'static,
+        E: std::fmt::Display,
+    {
+        Server::check_initialized();
+        self.lifecycle.register(Phase::Ready, hook);
+        self
+    }
+
+    /// Registers an asynchronous hook run at shutdown, once the in-flight requests
+    /// completed and before the data sources are closed (e.g. to flush queues,
+    /// deregister the instance or finish background jobs).
+    ///
+    /// Failures are logged and the next hooks still run.
+    pub fn on_shutdown<F, Fut, E>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<(), E>> + 'static,
+        E: std::fmt::Display,
+    {
+        Server::check_initialized();
+        self.lifecycle.register(Phase::Shutdown, hook);
+        self
+    }
+
+    /// Registers an application health indicator, reported by the `/actuator/health`
+    /// endpoint next to the built-in data source and disk space indicators.
+    pub fn with_health_indicator<I: HealthIndicator + 'static>(mut self, indicator: I) -> Self {
+        Server::check_initialized();
+        self.health_indicators.push(Arc::new(indicator));
+        self
+    }
+
     /// Executes the server using the previously loaded settings and CLI input.
     ///
     /// If both arguments and settings are available, this method delegates
-    /// execution to the CLI dispatcher, starting the server workflow.
+    /// execution to the CLI dispatcher, starting the server workflow. Once the
+    /// server is shut down, the data sources are closed.
     pub async fn run(&self) {
-        if self.running {
+        if lifecycle::state() != LifecycleState::Starting {
             warn!("The server is already running and cannot be started again.");
             return;
         }
 
-        self.clone().running = true;
-
         if let (Some(args), Some(settings)) = (&self.args, &self.settings) {
-            Cli::init(args, settings, self.fnconfig).await;
+            Cli::init(args, settings, self.fnconfig, &self.lifecycle).await;
         }
 
         if let Some(database) = &self.database {
-            database.close();
+            database.close().await;
         }
+
+        // Exports the pending spans
+        let _ = tokio::task::spawn_blocking(telemetry::shutdown).await;
+
+        lifecycle::set_state(LifecycleState::Stopped);
     }
 }
 
@@ -603,6 +663,9 @@ pub trait GlobalServer {
     // Returns a reference to the application settings.
     fn settings(&self) -> &Settings;
 
+    // Returns the application version passed to `Server::new`.
+    fn app_version(&self) -> &str;
+
     // Returns a reference to the server database configuration, if available.
     #[cfg(feature = "memory-database")]
     fn database_with_name(&self, name: &str) -> Result<Arc<DatabaseConnection>>;
@@ -614,18 +677,38 @@ pub trait GlobalServer {
     // Returns a reference to the BigQuery client, if available.
     fn bigquery(&self) -> Option<&bigquery::BigQueryClient>;
 
+    // Returns a reference to the pooled Redis client, if available.
+    fn redis(&self) -> Option<&redis::RedisClient>;
+
+    // Returns a reference to the outbound HTTP client authenticated with
+    // OAuth2 client-credentials tokens, if available.
+    fn http_client(&self) -> Option<&ClientWithMiddleware>;
+
+    // Returns a reference to the named outbound HTTP client declared in the
+    // `http-clients` settings, if available.
+    fn http_client_with_name(&self, name: &str) -> Option<&client::HttpClient>;
+
+    // Returns the built-in and the application health indicators.
+    fn health_indicators(&self) -> Vec<Arc<dyn HealthIndicator>>;
+
     // Returns a boolean indicating whether the server is currently running.
     fn is_running(&self) -> bool;
 
-    // Validates the JWT token in the given request and checks whether the
-    // associated roles match the provided list.
-    fn validate_jwt(
-        &self,
-        request: &ServiceRequest,
+    // Validates the JWT token (or the API key) in the given request, checks whether the
+    // associated roles match the provided list and returns the authenticated user.
+    fn validate_jwt<'a>(
+        &'a self,
+        request: &'a ServiceRequest,
         authorize: String,
-    ) -> security::oauth2::Result<()>;
+    ) -> LocalBoxFuture<'a, security::oauth2::Result<AuthenticatedUser>>;
 }
 
+/// A boxed, non-`Send` future returned by the asynchronous `GlobalServer` methods.
+///
+/// Actix-Web runs each worker on a single-threaded runtime, so the futures
+/// used by request middlewares do not need to be `Send`.
+pub type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
 /// Trait implementation for the `Server` structure.
 impl GlobalServer for Server {
     /// Returns a reference to the underlying `Any` value.
@@ -689,6 +772,12 @@ impl GlobalServer for Server {
             .expect("Settings must be initialized before calling settings()")
     }
 
+    /// Returns the application version passed to `Server::new`, empty when the
+    /// server was created by another constructor.
+    fn app_version(&self) -> &str {
+        &self.app_version
+    }
+
     /// Returns a reference to the BigQuery client, if available.
     ///
     /// # Returns
@@ -698,13 +787,65 @@ impl GlobalServer for Server {
         self.database.as_ref().and_then(|db| db.bigquery.as_ref())
     }
 
+    /// Returns a reference to the pooled Redis client, if available.
+    ///
+    /// # Returns
+    /// - `Some(&RedisClient)` if the Redis client is configured and enabled.
+    /// - `None` if no Redis client configuration is present.
+    fn redis(&self) -> Option<&redis::RedisClient> {
+        self.database.as_ref().and_then(|db| db.redis.as_ref())
+    }
+
+    /// Returns a reference to the outbound HTTP client, if available.
+    ///
+    /// Requests sent through this client carry an OAuth2 access token obtained
+    /// with the `client_credentials` grant.
+    ///
+    /// # Returns
+    /// - `Some(&ClientWithMiddleware)` if the OAuth2 client and token URI are configured.
+    /// - `None` otherwise.
+    fn http_client(&self) -> Option<&ClientWithMiddleware> {
+        self.http_client.as_ref()
+    }
+
+    /// Returns a reference to the named outbound HTTP client, if available.
+    ///
+    /// Named clients are declared in the `http-clients` settings and apply their
+    /// timeouts, retry, circuit breaker and bulkhead policies to every request.
+    ///
+    /// # Returns
+    /// - `Some(&HttpClient)` if a client with the given name is configured.
+    /// - `None` otherwise.
+    fn http_client_with_name(&self, name: &str) -> Option<&client::HttpClient> {
+        self.http_clients.get(name)
+    }
+
+    /// Returns the health indicators reported by the `/actuator/health` endpoint.
+    ///
+    /// The built-in indicators of the configured data sources and the disk space
+    /// come first, followed by the indicators registered by the application.
+    fn health_indicators(&self) -> Vec<Arc<dyn HealthIndicator>> {
+        let database = self.database.as_ref();
+        let mut indicators = health::builtin_indicators(
+            self.settings.as_ref(),
+            database.map(|d| d.databases.as_slice()).unwrap_or_default(),
+            database.and_then(|d| d.bigquery.as_ref()),
+            database.and_then(|d| d.redis.as_ref()),
+        );
+        indicators.extend(self.health_indicators.iter().cloned());
+        indicators
+    }
+
     /// Returns a boolean indicating whether the server is currently running.
     ///
     /// # Returns
-    /// - `true` if the server is running.
-    /// - `false` if the server is not running.
+    /// - `true` if the server accepts requests or drains them during shutdown.
+    /// - `false` if the server is not started yet or stopped.
     fn is_running(&self) -> bool {
-        self.running
+        matches!(
+            lifecycle::state(),
+            LifecycleState::Ready | LifecycleState::Draining
+        )
     }
 
     /// Validates the JWT token in the given request and checks whether the
@@ -718,30 +859,59 @@ impl GlobalServer for Server {
     /// - `Ok(())` if the JWT token is valid and the roles match.
     /// - `Err(securityity::oauth2::OAuth2Error)` if the JWT token is invalid or the roles do
     ///   not match.
-    fn validate_jwt(
-        &self,
-        request: &ServiceRequest,
+    fn validate_jwt<'a>(
+        &'a self,
+        request: &'a ServiceRequest,
         authorize: String,
-    ) -> security::oauth2::Result<()> {
-        let token: &str = request
-            .headers()
-            .get(header::AUTHORIZATION)
-            .and_then(|value| value.to_str().ok())
-            .ok_or_else(|| {
-                security::oauth2::OAuth2Error::InvalidJwt("Invalid JWT Header in request.".into())
-            })?
-            .trim_start_matches("Bearer ");
-
-        // Retrieves the server settings required to proceed with the security configuration
-        let settings = self.settings.as_ref().ok_or_else(|| {
-            warn!("Settings not configured.");
-            security::oauth2::OAuth2Error::Configuration("Settings not configured.".into())
-        })?;
-
-        // Validate JWT
-        security::oauth2::validate_jwt(token, settings, authorize)?;
+    ) -> LocalBoxFuture<'a, security::oauth2::Result<AuthenticatedUser>> {
+        Box::pin(async move {
+            // Retrieves the server settings required to proceed with the security configuration
+            let settings = self.settings.as_ref().ok_or_else(|| {
+                warn!("Settings not configured.");
+                security::oauth2::OAuth2Error::Configuration("Settings not configured.".into())
+            })?;
+
+            // Validate API key, when sent instead of a bearer token
+            if let Some(header_name) = security::api_key::header_name(settings)
+                && let Some(key) = request
+                    .headers()
+                    .get(header_name)
+                    .and_then(|value| value.to_str().ok())
+            {
+                return security::api_key::validate_api_key(key, settings, authorize);
+            }
 
-        Ok(())
+            let token = request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| {
+                    value
+                        .strip_prefix("Bearer ")
+                        .or_else(|| value.strip_prefix("bearer "))
+                })
+                .map(str::trim)
+                .filter(|token| !token.is_empty());
+
+            // Validate the client certificate of the mTLS connection, when no bearer
+            // token is sent
+            let Some(token) = token else {
+                if security::client_certificate::is_enabled(settings)
+                    && let Some(certificate) =
+                        request.conn_data::<security::client_certificate::PeerCertificate>()
+                {
+                    return security::client_certificate::validate_client_certificate(
+                        certificate,
+                        settings,
+                        authorize,
+                    );
+                }
+                return Err(security::oauth2::OAuth2Error::MissingToken);
+            };
+
+            // Validate JWT
+            security::oauth2::validate_jwt(token, settings, authorize).await
+        })
     }
 }
 
@@ -14,45 +14,162 @@
 //! | `health-check-port`   | Dedicated port exposing the health endpoint.                              |
 //! | `use-docker-compose`  | Enables orchestration of dependencies via Docker Compose.                 |
 //! | `docker-compose-file` | Path to the Docker Compose definition used when orchestration is enabled. |
+//! | `tls`                 | HTTPS configuration of the main and health-check servers (see below).     |
+//! | `shutdown`            | Graceful shutdown: `drain-period` and `timeout`, in seconds (see below).  |
+//!
+//! ## Graceful Shutdown
+//!
+//! On `SIGTERM` or `Ctrl+C` the readiness probe (`/actuator/health/readiness`) starts
+//! answering `OUT_OF_SERVICE` (HTTP 503), then the server waits `drain-period` seconds
+//! before closing its listeners, so load balancers and Kubernetes endpoints stop
+//! routing new requests to the instance. The in-flight requests are then given up to
+//! `timeout` seconds to complete, the `on_shutdown` hooks run and the data sources
+//! are closed.
+//!
+//! | Field          | Description                                                                |
+//! | -------------- | -------------------------------------------------------------------------- |
+//! | `drain-period` | Seconds reporting not ready before closing the listeners. Defaults to `0`. |
+//! | `timeout`      | Seconds given to the in-flight requests to complete. Defaults to `60`.     |
+//!
+//! ```yaml
+//! server:
+//!   shutdown:
+//!     drain-period: 10
+//!     timeout: 30
+//! ```
+//!
+//! ## TLS
+//!
+//! Serves the main server and the health-check server over HTTPS with `rustls`, without
+//! a sidecar or an ingress terminating TLS.
+//!
+//! | Field             | Description                                                                  |
+//! | ----------------- | ---------------------------------------------------------------------------- |
+//! | `enabled`         | Enables HTTPS. Defaults to `true` when the section is present.               |
+//! | `certificate`     | Path to the PEM certificate chain.                                           |
+//! | `private-key`     | Path to the PEM private key (PKCS#8, PKCS#1 or SEC1).                        |
+//! | `certificate-pem` | Inline PEM certificate chain, instead of `certificate`.                      |
+//! | `private-key-pem` | Inline PEM private key, instead of `private-key`.                            |
+//! | `client-ca`       | Path (or inline PEM) of the CA certificates trusted for client certificates. |
+//! | `client-auth`     | `required` (default when `client-ca` is set) or `optional`.                  |
+//! | `min-version`     | Minimum TLS version: `1.2` (default) or `1.3`.                               |
+//! | `reload`          | Reloads the certificate when its files change. Defaults to `true`.           |
+//! | `health-check`    | Serves the health-check server over HTTPS too. Defaults to `true`.           |
+//!
+//! Certificate renewals (e.g. by cert-manager) are picked up without restarting: the
+//! directories of the certificate and key files are watched and new connections use
+//! the reloaded certificate. An invalid certificate is logged and the previous one is
+//! kept. Client certificates are only verified on the main server, so probes and
+//! metric scrapers can reach the health-check server without one. The verified client
+//! certificates can authenticate the callers of the secured endpoints (see
+//! *Security — Client Certificates*).
+//!
+//! ```yaml
+//! server:
+//!   tls:
+//!     certificate: "/etc/tls/tls.crt"
+//!     private-key: "/etc/tls/tls.key"
+//!     client-ca: "/etc/tls/ca.crt"
+//!     min-version: "1.3"
+//! ```
 //!
 //! ## CORS
 //!
 //! Controls cross-origin access policies.
 //!
-//! | Field                     | Description                                                        |
-//! | ------------------------- | ------------------------------------------------------------------ |
-//! | `max-age`                 | Duration (seconds) browsers cache preflight responses.             |
-//! | `allow-credentials`       | Allows cookies and authorization headers in cross-origin requests. |
-//! | `allowed-methods`         | HTTP methods allowed for cross-origin calls.                       |
-//! | `allowed-headers`         | Headers accepted from clients.                                     |
-//! | `allowed-origins_pattern` | Comma-separated list of allowed origin patterns.                   |
+//! | Field                     | Description                                                                |
+//! | ------------------------- | -------------------------------------------------------------------------- |
+//! | `max-age`                 | Duration (seconds) browsers cache preflight responses.                     |
+//! | `allow-credentials`       | Allows cookies and authorization headers in cross-origin requests.         |
+//! | `allowed-methods`         | Comma-separated HTTP methods allowed for cross-origin calls (`*` for any). |
+//! | `allowed-headers`         | Comma-separated headers accepted from clients (`*` for any).               |
+//! | `exposed-headers`         | Comma-separated response headers readable by the browser (`*` for any).    |
+//! | `allowed-origins-pattern` | Comma-separated list of allowed origin patterns.                           |
+//!
+//! When `allowed-methods` is not set, the standard methods (`GET`, `POST`, `PUT`,
+//! `PATCH`, `DELETE`, `HEAD` and `OPTIONS`) are allowed.
+//!
+//! Origin patterns are exact origins (`https://app.example.com`), `*` for any origin,
+//! wildcards (`https://*.example.com`, `http://localhost:*`) or regular expressions
+//! starting with `^` (`^https://(app|admin)\.example\.com$`).
+//!
+//! Named policies declared in `server.cors-policies` replace the default policy for
+//! the controllers whose `paths` they list (a path matches its sub-paths too):
+//!
+//! ```yaml
+//! server:
+//!   cors:
+//!     allowed-origins-pattern: "https://*.example.com"
+//!     allow-credentials: true
+//!   cors-policies:
+//!     partners:
+//!       paths: ["/v1/partners"]
+//!       allowed-origins-pattern: "https://portal.partner.com"
+//!       allowed-methods: "GET"
+//! ```
 //!
 //! ## Security — OAuth2 / OpenID Connect
 //!
 //! Enables authentication and token validation using an OAuth2 provider.
 //!
-//! | Field                     | Description                                                        |
-//! | ------------------------- | ------------------------------------------------------------------ |
-//! | `enabled`                 | Activates OAuth2 protection for secured endpoints.                 |
-//! | `load-from-discovery-url` | Automatically loads provider metadata from the discovery endpoint. |
-//! | `discovery-url`           | OpenID Provider discovery document.                                |
-//! | `issuer-uri`              | Expected token issuer identifier.                                  |
-//! | `jwks-uri`                | JSON Web Key Set endpoint used to validate tokens.                 |
-//! | `token-uri`               | Endpoint for obtaining access tokens.                              |
-//! | `authorization-uri`       | Authorization endpoint for login flows.                            |
-//! | `introspection-uri`       | Endpoint for validating opaque tokens.                             |
-//! | `user_info-uri`           | Endpoint returning authenticated user claims.                      |
-//! | `end_session-uri`         | Logout endpoint for session termination.                           |
+//! | Field                       | Description                                                             |
+//! | --------------------------- | ----------------------------------------------------------------------- |
+//! | `enabled`                   | Activates OAuth2 protection for secured endpoints.                      |
+//! | `load-from-discovery-url`   | Automatically loads provider metadata from the discovery endpoint.      |
+//! | `discovery-url`             | OpenID Provider discovery document.                                     |
+//! | `issuer-uri`                | Expected token issuer identifier.                                       |
+//! | `jwks-uri`                  | JSON Web Key Set endpoint used to validate tokens.                      |
+//! | `token-uri`                 | Endpoint for obtaining access tokens.                                   |
+//! | `authorization-uri`         | Authorization endpoint for login flows.                                 |
+//! | `introspection-uri`         | Endpoint for validating opaque tokens.                                  |
+//! | `user_info-uri`             | Endpoint returning authenticated user claims.                           |
+//! | `end_session-uri`           | Logout endpoint for session termination.                                |
+//! | `jwks-cache-ttl`            | JWKS cache lifetime (seconds) when no `Cache-Control` is returned.      |
+//! | `jwks-min-refresh-interval` | Minimum interval (seconds) between two fetches for unknown key ids.     |
+//! | `audiences`                 | Expected `aud` values. Tokens minted for other clients are rejected.    |
+//! | `validate-nbf`              | Enforces the `nbf` (not before) claim. Defaults to `true`.              |
+//! | `algorithms`                | Accepted signature algorithms (e.g. `RS256`, `ES256`).                  |
+//! | `leeway`                    | Clock skew tolerance (seconds) for `exp` and `nbf`. Defaults to `60`.   |
+//! | `role-mapping`              | Claims used to build the user roles (see below).                        |
+//! | `token-validation`          | `jwt` (local signature check, default) or `introspection` (RFC 7662).   |
+//! | `introspection-cache-ttl`   | Lifetime (seconds) of cached introspection responses. Defaults to `60`. |
+//!
+//! ## Role Mapping
+//!
+//! Defines how the roles checked by `#[secured]` are extracted from the token, so
+//! identity providers other than Keycloak can be used.
+//!
+//! | Field            | Description                                                                                     |
+//! | ---------------- | ----------------------------------------------------------------------------------------------- |
+//! | `claims`         | Claim paths holding roles (`.` separated, `*` wildcard). Defaults to `resource_access.*.roles`. |
+//! | `clients`        | Keys matched by the `*` wildcard (e.g. only the roles of these clients).                        |
+//! | `prefix`         | Prefix added to each role. Defaults to `ROLE_`.                                                 |
+//! | `case`           | Case normalization: `upper` (default), `lower` or `preserve`.                                   |
+//! | `include-scopes` | Also maps the `scope` claim entries to roles.                                                   |
+//! | `scope-prefix`   | Prefix added to the roles mapped from scopes. Defaults to `SCOPE_`.                             |
+//!
+//! Characters other than letters, digits and `_` are replaced by `_` (`read:users`
+//! becomes `ROLE_READ_USERS`). A claim name containing dots or colons, such as
+//! `cognito:groups` or `https://example.com/roles`, can be used as is.
+//!
+//! ```yaml
+//! role-mapping:
+//!   claims: ["realm_access.roles", "resource_access.*.roles", "permissions"]
+//!   clients: ["rust-client"]
+//!   include-scopes: true
+//! ```
 //!
 //! ## OAuth2 Client
 //!
 //! Credentials used by the server when interacting with the identity provider.
 //!
-//! | Field    | Description                             |
-//! | -------- | --------------------------------------- |
-//! | `id`     | OAuth2 client identifier.               |
-//! | `secret` | OAuth2 client secret.                   |
-//! | `scope`  | Requested scopes during authentication. |
+//! | Field             | Description                                                            |
+//! | ----------------- | ---------------------------------------------------------------------- |
+//! | `id`              | OAuth2 client identifier.                                              |
+//! | `secret`          | OAuth2 client secret.                                                  |
+//! | `scope`           | Requested scopes during authentication.                                |
+//! | `connect-timeout` | Connection timeout (seconds) of the token requests. Defaults to `5`.   |
+//! | `timeout`         | Total timeout (seconds) of the token requests. Defaults to `10`.       |
 //!
 //!
 //! ## JWKS
@@ -70,6 +187,129 @@
 //! - x5c — X.509 certificate chain
 //!
 //! This section is typically used when keys are managed internally or cached locally.
+//! When a `jwks-uri` is available, the keys fetched from the identity provider replace
+//! these keys and are refreshed in the background, so key rotations (and revocations)
+//! are picked up without restarting the server.
+//!
+//! ## Security — API Keys
+//!
+//! Accepts static API keys as an alternative to OAuth2 tokens on `#[secured]`
+//! endpoints, for callers that cannot obtain tokens (batch jobs, partner systems).
+//!
+//! | Field         | Description                                                   |
+//! | ------------- | ------------------------------------------------------------- |
+//! | `enabled`     | Enables the API key authentication.                           |
+//! | `header-name` | Request header carrying the key. Defaults to `X-API-Key`.     |
+//! | `keys`        | Accepted keys (`name`, `hash`, `roles`, `enabled`).           |
+//!
+//! Keys are never stored in clear text: `hash` is the hex encoded SHA-256 digest of
+//! the key (`echo -n "$KEY" | sha256sum`). The key `name` becomes the subject of the
+//! authenticated user and its `roles` are checked by the `authorize` rules.
+//!
+//! ```yaml
+//! api-keys:
+//!   enabled: true
+//!   keys:
+//!     - name: "billing-job"
+//!       hash: "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8"
+//!       roles: ["ROLE_BATCH"]
+//! ```
+//!
+//! ## Security — Client Certificates
+//!
+//! Authenticates the callers of `#[secured]` endpoints by the client certificate
+//! verified on the mTLS connection (`server.tls.client-ca`), for service-to-service
+//! traffic inside the cluster.
+//!
+//! | Field        | Description                                                           |
+//! | ------------ | --------------------------------------------------------------------- |
+//! | `enabled`    | Enables the client certificate authentication.                        |
+//! | `principals` | Accepted certificates (`name`, `subject`, `san`, `roles`, `enabled`). |
+//!
+//! A principal matches when the certificate `subject` distinguished name (attribute
+//! order and spacing ignored) and/or one of its subject alternative names (DNS name,
+//! URI or email) equal the configured values. The principal `name` becomes the
+//! subject of the authenticated user and its `roles` are checked by the `authorize`
+//! rules. A bearer token or an API key sent on the request takes precedence over the
+//! client certificate.
+//!
+//! ```yaml
+//! client-certificates:
+//!   enabled: true
+//!   principals:
+//!     - name: "billing-service"
+//!       san: "spiffe://cluster.local/ns/billing/sa/billing"
+//!       roles: ["ROLE_BATCH"]
+//!     - name: "reporting"
+//!       subject: "CN=reporting,O=Example"
+//!       roles: ["ROLE_REPORT"]
+//! ```
+//!
+//! ## HTTP Clients
+//!
+//! Named outbound HTTP clients used to call other services. Each client is built on
+//! `reqwest-middleware`, traced with `reqwest-tracing` and exposes Prometheus metrics
+//! labelled with its name.
+//!
+//! | Field             | Description                                                              |
+//! | ----------------- | ------------------------------------------------------------------------ |
+//! | `base-url`        | URL prepended to the relative paths of the requests.                     |
+//! | `connect-timeout` | Connection timeout (in seconds).                                         |
+//! | `timeout`         | Total request timeout (in seconds), including the response body.         |
+//! | `authentication`  | `none` (default), `client-credentials`, `propagate` or `token-exchange`. |
+//! | `token-exchange`  | Requested `audience`, `resource` and `scope` of the exchanged tokens.    |
+//! | `retry`           | Retry of idempotent requests on transient failures (see below).          |
+//! | `circuit-breaker` | Stops calling the service after consecutive failures (see below).        |
+//! | `bulkhead`        | Limits the number of concurrent calls to the service (see below).        |
+//!
+//! | Field                               | Description                                                       |
+//! | ----------------------------------- | ----------------------------------------------------------------- |
+//! | `retry.max-attempts`                | Total attempts, including the first one. Defaults to `3`.         |
+//! | `retry.initial-backoff`             | Delay (in milliseconds) before the first retry. Defaults `100`.   |
+//! | `retry.max-backoff`                 | Maximum delay (in milliseconds) between retries. Defaults `2000`. |
+//! | `retry.multiplier`                  | Backoff growth factor between retries. Defaults to `2.0`.         |
+//! | `circuit-breaker.failure-threshold` | Consecutive failures opening the circuit. Defaults to `5`.        |
+//! | `circuit-breaker.open-duration`     | Time (in seconds) before a trial call is allowed. Defaults `30`.  |
+//! | `bulkhead.max-concurrent-calls`     | Maximum concurrent calls. Defaults to `100`.                      |
+//! | `bulkhead.max-wait`                 | Time (in milliseconds) to wait for a free slot. Defaults `0`.     |
+//!
+//! The `authentication` modes set the `Authorization` header of the requests that
+//! don't already carry one:
+//!
+//! - `client-credentials` — token of the server itself (OAuth2 client credentials).
+//! - `propagate` — bearer token of the `#[secured]` request being handled.
+//! - `token-exchange` — token obtained from the `token-uri` by exchanging the bearer
+//!   token of the `#[secured]` request (RFC 8693). Exchanged tokens are cached until
+//!   they expire.
+//!
+//! Requests sent outside a secured handler (or by a user authenticated with an API
+//! key) are sent without token in the `propagate` and `token-exchange` modes.
+//!
+//! Connection errors, timeouts and `429`, `502`, `503`, `504` responses are retried
+//! with an exponential backoff. Connection errors, timeouts and `5xx` responses count
+//! as failures for the circuit breaker. Calls rejected by an open circuit or a full
+//! bulkhead fail immediately without reaching the remote service.
+//!
+//! ```yaml
+//! http-clients:
+//!   orders:
+//!     base-url: "http://orders:8080/api"
+//!     connect-timeout: 2
+//!     timeout: 10
+//!     authentication: "client-credentials"
+//!     retry:
+//!       max-attempts: 3
+//!     circuit-breaker:
+//!       failure-threshold: 5
+//!       open-duration: 30
+//!     bulkhead:
+//!       max-concurrent-calls: 20
+//!   inventory:
+//!     base-url: "http://inventory:8080"
+//!     authentication: "token-exchange"
+//!     token-exchange:
+//!       audience: "inventory"
+//! ```
 //!
 //! ## Data Sources
 //!
@@ -81,6 +321,9 @@
 //! | ---------------------- | ----------------------------------------------- |
 //! | `enabled`              | Enables Redis integration.                      |
 //! | `host` / `port`        | Connection settings.                            |
+//! | `username`/`password`  | Optional ACL credentials.                       |
+//! | `database`             | Logical database index (defaults to `0`).       |
+//! | `connect-timeout`      | Pool acquire timeout (in seconds).              |
 //! | `client-type`          | Redis client implementation.                    |
 //! | `lettuce.pool`         | Connection pool configuration.                  |
 //! | `repositories.enabled` | Enables repository abstraction backed by Redis. |
@@ -134,11 +377,187 @@
 //!
 //! Controls application observability and monitoring integration.
 //!
-//! | Field      | Description                             |
-//! | ---------- | --------------------------------------- |
-//! | `enabled`  | Enables metrics collection.             |
-//! | `app-name` | Identifier used when exporting metrics. |
+//! | Field               | Description                                                                      |
+//! | ------------------- | -------------------------------------------------------------------------------- |
+//! | `enabled`           | Enables metrics collection.                                                      |
+//! | `app-name`          | Identifier used when exporting metrics.                                          |
+//! | `sampling-interval` | Seconds between two samples of the system and process metrics. Defaults to `15`. |
+//!
+//! The system, process and container metrics are sampled in a background thread and
+//! the scrapes return the last sample.
+//!
+//! ## Logging
+//!
+//! Selects the output of the process logger. The levels are set by the `RUST_LOG`
+//! environment variable and can be changed at runtime with `/actuator/loggers`.
+//!
+//! | Field    | Description                                                                       |
+//! | -------- | --------------------------------------------------------------------------------- |
+//! | `format` | `pretty` (default, colored columns) or `json` (one object per line).              |
+//! | `colors` | `auto` (default, only on a terminal), `always` or `never`. JSON is never colored. |
+//!
+//! JSON records hold the `timestamp`, `level`, `target`, `message` and `service`
+//! fields, plus `trace_id`, `span_id` and `request_id` when available. The service
+//! name is `tracing.service-name`, then `metrics.app-name`, then `api`.
+//!
+//! ```yaml
+//! logging:
+//!   format: json
+//! ```
+//!
+//! ## Access Log
+//!
+//! Logs one entry per request served by the main and health-check servers, with the
+//! `access_log` target (`RUST_LOG=access_log=off` silences it).
+//!
+//! | Field                 | Description                                                                      |
+//! | --------------------- | -------------------------------------------------------------------------------- |
+//! | `enabled`             | Enables the access log. Defaults to `true`.                                      |
+//! | `format`              | `text` (default, rendered from `template`) or `json` (one object per entry).     |
+//! | `template`            | Template of the `text` entries, with `{field}` placeholders (see below).         |
+//! | `exclude-paths`       | Regular expressions of the paths not logged. Defaults to the probes and metrics. |
+//! | `headers`             | Request headers added to the entries. Credentials and cookies are masked.        |
+//! | `masked-query-params` | Query parameters whose values are masked. `*` masks all of them.                 |
+//! | `slow-threshold`      | Duration (in milliseconds) from which a request is logged as slow, with `WARN`.  |
+//!
+//! The fields of an entry are `remote_addr`, `method`, `uri` (path and masked query),
+//! `path`, `protocol`, `status`, `bytes`, `duration_ms`, `request_id`, `subject` (the
+//! authenticated user), `user_agent`, `referer`, `headers` and `slow`. Missing values
+//! are rendered as `-`. The default template is:
+//!
+//! ```text
+//! {remote_addr} "{method} {uri} {protocol}" {status} {bytes} {duration_ms}ms "{user_agent}" {request_id} {subject} {headers}
+//! ```
+//!
+//! By default, `/actuator/health`, `/actuator/metrics` and `/metrics` are not logged,
+//! and the values of the `access_token`, `token`, `code`, `password`, `secret`,
+//! `client_secret`, `api_key` and `apikey` query parameters are masked. Slow
+//! requests are highlighted in yellow in the `text` entries.
+//!
+//! ```yaml
+//! access-log:
+//!   format: json
+//!   exclude-paths: ["^/actuator/.*", "^/metrics$"]
+//!   headers: ["X-Tenant-Id", "X-Forwarded-For"]
+//!   slow-threshold: 500
+//! ```
+//!
+//! ## Tracing
+//!
+//! Exports the spans of the application to an OpenTelemetry collector over OTLP.
+//! Each request of the main server runs in a span continuing the trace of the W3C
+//! `traceparent` header, and the outbound HTTP clients forward the trace context.
+//!
+//! | Field            | Description                                                              |
+//! | ---------------- | ------------------------------------------------------------------------ |
+//! | `enabled`        | Enables the trace export. Defaults to `true` when the section is set.    |
+//! | `protocol`       | OTLP protocol: `grpc` (default) or `http` (protobuf over HTTP).          |
+//! | `endpoint`       | Collector endpoint. Defaults to the local collector of the protocol.     |
+//! | `service-name`   | Service name of the spans. Defaults to `metrics.app-name`, then `api`.   |
+//! | `sampling-ratio` | Ratio of the sampled new traces, from `0.0` to `1.0`. Defaults to `1.0`. |
+//! | `timeout`        | Export timeout (in milliseconds). Defaults to `10000`.                   |
+//!
+//! The default endpoints are `http://localhost:4317` for `grpc` and
+//! `http://localhost:4318/v1/traces` for `http`. A request continuing a trace follows
+//! the sampling decision of its caller.
+//!
+//! ```yaml
+//! tracing:
+//!   protocol: grpc
+//!   endpoint: "http://otel-collector:4317"
+//!   service-name: "orders"
+//!   sampling-ratio: 0.25
+//! ```
+//!
+//! ## Health
+//!
+//! Configures the health indicators aggregated by `/actuator/health` on the
+//! health-check port. The response holds the aggregated `status` and, depending on
+//! `show-details`, the status and details of each component.
+//!
+//! | Field          | Description                                                                     |
+//! | -------------- | ------------------------------------------------------------------------------- |
+//! | `show-details` | `never` (status only), `components` (component statuses, default) or `always`. |
+//! | `timeout`      | Timeout (in milliseconds) of each indicator. Defaults to `2000`.                |
+//! | `indicators`   | Per-component `enabled` and `timeout`, keyed by component name.                 |
+//! | `disk-space`   | Disk space indicator: `enabled`, `path` and `threshold` (bytes, 10 MB).         |
+//! | `groups`       | Health groups (`include`, `show-details`) served on `/actuator/health/{group}`. |
+//!
+//! Built-in components are `db.<name>` for each relational database, `bigquery`,
+//! `redis`, `disk-space` and the lifecycle states `liveness-state`, `readiness-state`
+//! and `startup-state`. Applications register their own indicators with
+//! `Server::with_health_indicator`. An indicator exceeding its timeout is reported
+//! `DOWN`. The aggregated status is the most severe one (`DOWN`, `OUT_OF_SERVICE`,
+//! `UP`, `UNKNOWN`) and the endpoint answers HTTP 503 when it is `DOWN` or
+//! `OUT_OF_SERVICE`.
+//!
+//! ### Probes and Groups
+//!
+//! Kubernetes probes use the health groups, each one aggregating a subset of the
+//! components:
+//!
+//! | Group       | Default components                                   | Probe            |
+//! | ----------- | ---------------------------------------------------- | ---------------- |
+//! | `liveness`  | `liveness-state` (the process is running)            | `livenessProbe`  |
+//! | `readiness` | `readiness-state`, `db.*`, `bigquery`, `redis`       | `readinessProbe` |
+//! | `startup`   | `startup-state` (startup hooks done, listeners open) | `startupProbe`   |
+//!
+//! `readiness-state` is `OUT_OF_SERVICE` until the server is started and while it
+//! drains its requests during a graceful shutdown. The groups declared in `groups`
+//! replace the default ones with the same name or add new groups.
+//!
+//! ```yaml
+//! health:
+//!   show-details: components
+//!   timeout: 1000
+//!   indicators:
+//!     bigquery:
+//!       timeout: 5000
+//!     redis:
+//!       enabled: false
+//!   disk-space:
+//!     path: "/data"
+//!     threshold: 104857600
+//!   groups:
+//!     readiness:
+//!       include: ["readiness-state", "db.*", "disk-space"]
+//!     dependencies:
+//!       include: ["db.*", "bigquery", "redis"]
+//!       show-details: always
+//! ```
+//!
+//! ## Actuator
+//!
+//! The health-check server exposes management endpoints next to the health and
+//! metrics endpoints. They require an authenticated caller (bearer token, API key)
+//! holding the configured role.
+//!
+//! | Field          | Description                                                          |
+//! | -------------- | -------------------------------------------------------------------- |
+//! | `role`         | Role expression required by the endpoints. Defaults to `ROLE_ADMIN`. |
+//! | `loggers-role` | Role expression required by `/actuator/loggers`. Defaults to `role`. |
 //!
+//! | Endpoint                | Description                                                        |
+//! | ----------------------- | ------------------------------------------------------------------ |
+//! | `/actuator/info`        | Application and framework versions, git metadata and build time.   |
+//! | `/actuator/env`         | Merged settings as flat properties (`server.port`).                |
+//! | `/actuator/configprops` | Merged settings as a tree.                                         |
+//! | `/actuator/loggers`     | Lists the log levels (`GET`) and changes them at runtime (`POST`). |
+//!
+//! The git metadata and the build time are read from the `GIT_BRANCH`, `GIT_COMMIT`
+//! and `BUILD_TIME` environment variables, usually set when building the image.
+//! Passwords, client secrets, private keys, API key hashes, BigQuery credentials
+//! and the credentials of connection URLs are masked in `env` and `configprops`.
+//!
+//! A logger level is changed by posting its `configured-level` (`OFF`, `ERROR`, `WARN`,
+//! `INFO`, `DEBUG` or `TRACE`) to `/actuator/loggers/{name}`, where the name is a module
+//! path or `ROOT`. Posting a `null` level restores the `RUST_LOG` level.
+//!
+//! ```yaml
+//! actuator:
+//!   role: "hasAnyRole(ROLE_ADMIN, ROLE_OPERATOR)"
+//!   loggers-role: "ROLE_ADMIN"
+//! ```
 //!
 //! ## Runtime Notes
 //!
@@ -151,13 +570,14 @@ use config::{Case, Config, ConfigError, Environment, File, FileFormat};
 use jsonwebtoken::jwk::{Jwk, JwkSet};
 #[allow(unused)]
 use log::LevelFilter;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Configuration for enabling or disabling data repositories.
 ///
 /// This structure is usually used to control whether repository
 /// layers backed by Redis or other data sources should be enabled.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Repositories {
     /// Enables or disables repositories.
@@ -168,7 +588,7 @@ pub struct Repositories {
 ///
 /// Defines limits and behavior for resource pooling,
 /// such as database or Redis connections.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Pool {
     /// Enables or disables the connection pool.
@@ -188,7 +608,7 @@ pub struct Pool {
 ///
 /// Represents advanced Redis client settings,
 /// including connection pool configuration.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Lettuce {
     /// Connection pool configuration for the Lettuce client.
@@ -199,15 +619,30 @@ pub struct Lettuce {
 ///
 /// Defines connection parameters and client behavior
 /// for Redis-based integrations.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Redis {
+    /// Redis connection enabled. Defaults true.
+    pub enabled: Option<bool>,
+
     /// Redis server host.
     pub host: Option<String>,
 
     /// Redis server port.
     pub port: Option<u16>,
 
+    /// Redis ACL username.
+    pub username: Option<String>,
+
+    /// Redis password.
+    pub password: Option<String>,
+
+    /// Redis logical database index. Defaults 0.
+    pub database: Option<i64>,
+
+    /// Timeout for acquiring a connection from the pool (in seconds).
+    pub connect_timeout: Option<u64>,
+
     /// Type of Redis client implementation.
     pub client_type: Option<String>,
 
@@ -222,7 +657,7 @@ pub struct Redis {
 ///
 /// Controls connection details, pooling behavior,
 /// timeouts, and logging options.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Database {
     /// Database connection name.
@@ -263,7 +698,7 @@ pub struct Database {
 ///
 /// Defines access parameters and datasets used
 /// for analytics and data processing.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct BigQuery {
     /// BigQuery connection enabled. Defaults true.
@@ -289,7 +724,7 @@ pub struct BigQuery {
 ///
 /// Groups all data-related configurations,
 /// such as Redis, BigQuery, and relational databases.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Data {
     /// Enables or disables redis connection.
@@ -308,7 +743,7 @@ pub struct Data {
 /// CORS (Cross-Origin Resource Sharing) configuration.
 ///
 /// Controls how the server handles cross-origin HTTP requests.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Cors {
     /// Maximum cache age for CORS preflight requests (in seconds).
@@ -325,12 +760,31 @@ pub struct Cors {
 
     /// Allowed origin patterns.
     pub allowed_origins_pattern: Option<String>,
+
+    /// Response headers exposed to the browser.
+    pub exposed_headers: Option<String>,
+}
+
+/// Named CORS policy.
+///
+/// CORS configuration applied to the requests of specific controllers instead of
+/// the default `server.cors` policy.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct CorsPolicy {
+    /// Paths of the controllers the policy applies to, including their sub-paths.
+    #[serde(default)]
+    pub paths: Vec<String>,
+
+    /// CORS configuration of the policy.
+    #[serde(flatten)]
+    pub cors: Cors,
 }
 
 /// Server configuration.
 ///
 /// Defines network, runtime, and deployment-related settings.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Server {
     /// Server host address.
@@ -356,12 +810,73 @@ pub struct Server {
 
     /// CORS configuration.
     pub cors: Option<Cors>,
+
+    /// Named CORS policies attached to specific controllers.
+    pub cors_policies: Option<HashMap<String, CorsPolicy>>,
+
+    /// TLS configuration.
+    pub tls: Option<Tls>,
+
+    /// Graceful shutdown configuration.
+    pub shutdown: Option<Shutdown>,
+}
+
+/// Graceful shutdown configuration.
+///
+/// Controls how long the server keeps serving after a shutdown signal.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Shutdown {
+    /// Delay (in seconds) between reporting the server as not ready and closing the
+    /// listeners, so load balancers stop routing new requests. Defaults 0.
+    pub drain_period: Option<u64>,
+
+    /// Maximum time (in seconds) given to the in-flight requests to complete once the
+    /// listeners are closed. Defaults 60.
+    pub timeout: Option<u64>,
+}
+
+/// TLS configuration.
+///
+/// Certificates and client authentication of the HTTPS listeners.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Tls {
+    /// Enables or disables HTTPS. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Path to the PEM certificate chain.
+    pub certificate: Option<String>,
+
+    /// Path to the PEM private key.
+    pub private_key: Option<String>,
+
+    /// Inline PEM certificate chain.
+    pub certificate_pem: Option<String>,
+
+    /// Inline PEM private key.
+    pub private_key_pem: Option<String>,
+
+    /// Path or inline PEM of the CA certificates trusted for client certificates.
+    pub client_ca: Option<String>,
+
+    /// Client authentication: `required` (default) or `optional`.
+    pub client_auth: Option<String>,
+
+    /// Minimum TLS version: `1.2` (default) or `1.3`.
+    pub min_version: Option<String>,
+
+    /// Reloads the certificate when its files change. Defaults true.
+    pub reload: Option<bool>,
+
+    /// Serves the health-check server over HTTPS. Defaults true.
+    pub health_check: Option<bool>,
 }
 
 /// Metrics configuration.
 ///
 /// Controls application metrics exposure and identification.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Metrics {
     /// Enables or disables metrics collection.
@@ -369,12 +884,152 @@ pub struct Metrics {
 
     /// Application name used in metrics labels.
     pub app_name: Option<String>,
+
+    /// Interval (in seconds) between the samples of the system and process metrics.
+    /// Defaults to 15.
+    pub sampling_interval: Option<u64>,
+}
+
+/// Logging configuration.
+///
+/// Controls the output format of the process logger.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Logging {
+    /// Output format: `pretty` or `json`. Defaults to `pretty`.
+    pub format: Option<String>,
+
+    /// Colors of the `pretty` output: `auto`, `always` or `never`. Defaults to `auto`.
+    pub colors: Option<String>,
+}
+
+/// Access log configuration.
+///
+/// Controls the entries logged for the requests of the main and health-check servers.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct AccessLog {
+    /// Enables or disables the access log. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Entry format: `text` or `json`. Defaults to `text`.
+    pub format: Option<String>,
+
+    /// Template of the `text` entries, with `{field}` placeholders.
+    pub template: Option<String>,
+
+    /// Regular expressions of the paths not logged. Defaults to the health and metrics
+    /// endpoints.
+    pub exclude_paths: Option<Vec<String>>,
+
+    /// Request headers added to the entries.
+    pub headers: Option<Vec<String>>,
+
+    /// Query parameters whose values are masked, or `*` for all of them.
+    pub masked_query_params: Option<Vec<String>>,
+
+    /// Duration (in milliseconds) from which a request is logged as slow.
+    pub slow_threshold: Option<u64>,
+}
+
+/// Distributed tracing configuration.
+///
+/// Controls the export of the spans to an OpenTelemetry collector.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Tracing {
+    /// Enables or disables the trace export. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// OTLP protocol: `grpc` or `http`. Defaults to `grpc`.
+    pub protocol: Option<String>,
+
+    /// OTLP collector endpoint.
+    pub endpoint: Option<String>,
+
+    /// Service name reported on the spans.
+    pub service_name: Option<String>,
+
+    /// Ratio of the sampled new traces, from 0.0 to 1.0. Defaults to 1.0.
+    pub sampling_ratio: Option<f64>,
+
+    /// Export timeout (in milliseconds).
+    pub timeout: Option<u64>,
+}
+
+/// Health endpoint configuration.
+///
+/// Controls the health indicators aggregated by `/actuator/health`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Health {
+    /// Visibility of the components: `never`, `components` (default) or `always`.
+    pub show_details: Option<String>,
+
+    /// Default timeout (in milliseconds) of each health indicator. Defaults 2000.
+    pub timeout: Option<u64>,
+
+    /// Per-indicator settings, keyed by component name (e.g. `redis`, `db.primary`).
+    pub indicators: Option<HashMap<String, HealthIndicator>>,
+
+    /// Disk space indicator configuration.
+    pub disk_space: Option<DiskSpace>,
+
+    /// Health groups served on `/actuator/health/{group}`, keyed by group name.
+    pub groups: Option<HashMap<String, HealthGroup>>,
+}
+
+/// Health group configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthGroup {
+    /// Components of the group. A trailing `*` matches a prefix (e.g. `db.*`).
+    pub include: Option<Vec<String>>,
+
+    /// Visibility of the components, overriding `health.show-details`.
+    pub show_details: Option<String>,
+}
+
+/// Management endpoints configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Actuator {
+    /// Role expression required by the management endpoints. Defaults to `ROLE_ADMIN`.
+    pub role: Option<String>,
+
+    /// Role expression required by `/actuator/loggers`. Defaults to `role`.
+    pub loggers_role: Option<String>,
+}
+
+/// Health indicator configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthIndicator {
+    /// Enables or disables the indicator. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Timeout (in milliseconds) of the indicator, overriding the default timeout.
+    pub timeout: Option<u64>,
+}
+
+/// Disk space health indicator configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct DiskSpace {
+    /// Enables or disables the disk space indicator. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Path whose file system is checked. Defaults to the working directory.
+    pub path: Option<String>,
+
+    /// Minimum free space (in bytes) for the indicator to be `UP`. Defaults 10 MB.
+    pub threshold: Option<u64>,
 }
 
 /// OAuth2 configuration.
 ///
 /// Controls authentication and authorization settings.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct OAuth2Configuration {
     /// Enables or disables OAuth2 integration.
@@ -420,11 +1075,72 @@ pub struct OAuth2Configuration {
 
     /// OAuth2 JSON Web Key Set. This list of keys is used to validate tokens.
     pub jwks: Option<JwkSet>,
+
+    /// JWKS cache lifetime (in seconds) used when the JWKS endpoint does not send
+    /// a `Cache-Control: max-age` directive. Defaults 300.
+    pub jwks_cache_ttl: Option<u64>,
+
+    /// Minimum interval (in seconds) between two on-demand JWKS fetches triggered by
+    /// unknown key ids, and between two retries of a failed refresh. Defaults 30.
+    pub jwks_min_refresh_interval: Option<u64>,
+
+    /// Expected token audiences. When set, the `aud` claim is required and must
+    /// contain at least one of these values.
+    #[serde(alias = "audience")]
+    pub audiences: Option<Vec<String>>,
+
+    /// Enables or disables the `nbf` (not before) claim validation. Defaults true.
+    pub validate_nbf: Option<bool>,
+
+    /// Signature algorithms accepted in the token header (e.g. `RS256`). When not
+    /// set, the algorithm declared by the token is accepted.
+    pub algorithms: Option<Vec<String>>,
+
+    /// Clock skew tolerance (in seconds) applied to the `exp` and `nbf` claims. Defaults 60.
+    pub leeway: Option<u64>,
+
+    /// Mapping of the token claims to the roles checked by `#[secured]`.
+    pub role_mapping: Option<RoleMapping>,
+
+    /// Token validation mode: `jwt` validates the token signature locally with the
+    /// JWKS, `introspection` asks the identity provider (RFC 7662). Defaults `jwt`.
+    pub token_validation: Option<String>,
+
+    /// Lifetime (in seconds) of the cached introspection responses. The token
+    /// expiration is never exceeded. Defaults 60.
+    pub introspection_cache_ttl: Option<u64>,
+}
+
+/// Role mapping configuration.
+///
+/// Defines which token claims hold the user roles and how they are normalized
+/// before being compared with the `authorize` rules.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct RoleMapping {
+    /// Claim paths holding the roles, using `.` as separator and `*` to match every
+    /// key of an object. Defaults `["resource_access.*.roles"]`.
+    pub claims: Option<Vec<String>>,
+
+    /// Restricts the keys matched by `*` (e.g. the `resource_access` clients).
+    pub clients: Option<Vec<String>>,
+
+    /// Prefix added to every role. Defaults `ROLE_`.
+    pub prefix: Option<String>,
+
+    /// Case normalization of the roles: `upper`, `lower` or `preserve`. Defaults `upper`.
+    pub case: Option<String>,
+
+    /// Maps the `scope` claim entries to roles. Defaults false.
+    pub include_scopes: Option<bool>,
+
+    /// Prefix added to the roles mapped from scopes. Defaults `SCOPE_`.
+    pub scope_prefix: Option<String>,
 }
 
 /// OAuth2 client configuration.
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct OAuth2Client {
     /// OAuth2 client ID.
@@ -435,23 +1151,199 @@ pub struct OAuth2Client {
 
     /// OAuth2 client scopes.
     pub scope: Option<String>,
+
+    /// Connection timeout (in seconds) of the requests sent to the token endpoint.
+    /// Defaults 5.
+    pub connect_timeout: Option<u64>,
+
+    /// Total timeout (in seconds) of the requests sent to the token endpoint.
+    /// Defaults 10.
+    pub timeout: Option<u64>,
 }
 
 /// Server security configuration.
 ///
 /// This structure aggregates all security-related settings.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Security {
     /// OAuth2 configuration.
     pub oauth2: Option<OAuth2Configuration>,
+
+    /// API keys configuration.
+    pub api_keys: Option<ApiKeys>,
+
+    /// Client certificate (mTLS) authentication configuration.
+    pub client_certificates: Option<ClientCertificates>,
+}
+
+/// API keys configuration.
+///
+/// Static keys accepted as an alternative credential on secured endpoints.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ApiKeys {
+    /// Enables or disables the API key authentication.
+    pub enabled: Option<bool>,
+
+    /// Request header carrying the API key. Defaults `X-API-Key`.
+    pub header_name: Option<String>,
+
+    /// Accepted API keys.
+    pub keys: Option<Vec<ApiKey>>,
+}
+
+/// API key configuration.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ApiKey {
+    /// Name of the key owner, used as the subject of the authenticated user.
+    pub name: String,
+
+    /// Hex encoded SHA-256 digest of the key.
+    pub hash: String,
+
+    /// Roles granted to the key (e.g. `ROLE_BATCH`).
+    pub roles: Option<Vec<String>>,
+
+    /// Enables or disables this key. Defaults true.
+    pub enabled: Option<bool>,
+}
+
+/// Client certificate authentication configuration.
+///
+/// Maps the verified client certificates of the mTLS connections to principals.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClientCertificates {
+    /// Enables or disables the client certificate authentication.
+    pub enabled: Option<bool>,
+
+    /// Accepted client certificates.
+    pub principals: Option<Vec<CertificatePrincipal>>,
+}
+
+/// Principal granted to the callers presenting a matching client certificate.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct CertificatePrincipal {
+    /// Name of the caller, used as the subject of the authenticated user.
+    pub name: String,
+
+    /// Distinguished name of the certificate subject (e.g. `CN=billing,O=Example`).
+    pub subject: Option<String>,
+
+    /// Subject alternative name (DNS name, URI or email) of the certificate.
+    pub san: Option<String>,
+
+    /// Roles granted to the caller (e.g. `ROLE_BATCH`).
+    pub roles: Option<Vec<String>>,
+
+    /// Enables or disables this principal. Defaults true.
+    pub enabled: Option<bool>,
+}
+
+/// Outbound HTTP client configuration.
+///
+/// Defines the target service and the resilience policies of a named client.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpClient {
+    /// URL prepended to the relative request paths.
+    pub base_url: Option<String>,
+
+    /// Connection timeout (in seconds).
+    pub connect_timeout: Option<u64>,
+
+    /// Total request timeout (in seconds).
+    pub timeout: Option<u64>,
+
+    /// Authentication of the outbound requests: `none` (default),
+    /// `client-credentials`, `propagate` or `token-exchange`.
+    pub authentication: Option<String>,
+
+    /// Token exchange parameters, used by the `token-exchange` authentication.
+    pub token_exchange: Option<TokenExchange>,
+
+    /// Retry policy.
+    pub retry: Option<Retry>,
+
+    /// Circuit breaker configuration.
+    pub circuit_breaker: Option<CircuitBreaker>,
+
+    /// Bulkhead configuration.
+    pub bulkhead: Option<Bulkhead>,
+}
+
+/// Token exchange (RFC 8693) parameters of an outbound HTTP client.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct TokenExchange {
+    /// Logical name of the target service (`audience` parameter).
+    pub audience: Option<String>,
+
+    /// URI of the target service (`resource` parameter).
+    pub resource: Option<String>,
+
+    /// Scopes requested for the exchanged token.
+    pub scope: Option<String>,
+}
+
+/// Retry policy of an outbound HTTP client.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Retry {
+    /// Enables or disables the retries. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Total number of attempts, including the first one. Defaults 3.
+    pub max_attempts: Option<u32>,
+
+    /// Delay (in milliseconds) before the first retry. Defaults 100.
+    pub initial_backoff: Option<u64>,
+
+    /// Maximum delay (in milliseconds) between two attempts. Defaults 2000.
+    pub max_backoff: Option<u64>,
+
+    /// Growth factor of the delay between two attempts. Defaults 2.0.
+    pub multiplier: Option<f64>,
+}
+
+/// Circuit breaker configuration of an outbound HTTP client.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct CircuitBreaker {
+    /// Enables or disables the circuit breaker. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Number of consecutive failures that opens the circuit. Defaults 5.
+    pub failure_threshold: Option<u32>,
+
+    /// Time (in seconds) the circuit stays open before a trial call. Defaults 30, at most
+    /// one day.
+    pub open_duration: Option<u64>,
+}
+
+/// Bulkhead configuration of an outbound HTTP client.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Bulkhead {
+    /// Enables or disables the bulkhead. Defaults true.
+    pub enabled: Option<bool>,
+
+    /// Maximum number of concurrent calls, greater than zero. Defaults 100.
+    pub max_concurrent_calls: Option<usize>,
+
+    /// Time (in milliseconds) to wait for a free slot before rejecting the call.
+    /// Defaults 0.
+    pub max_wait: Option<u64>,
 }
 
 /// Global application settings.
 ///
 /// Root configuration structure that aggregates
 /// server, data, and metrics configurations.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct Settings {
     /// Server configuration.
@@ -463,8 +1355,26 @@ pub struct Settings {
     /// Metrics configuration.
     pub metrics: Option<Metrics>,
 
+    /// Logging configuration.
+    pub logging: Option<Logging>,
+
+    /// Access log configuration.
+    pub access_log: Option<AccessLog>,
+
+    /// Distributed tracing configuration.
+    pub tracing: Option<Tracing>,
+
     /// Security configuration.
     pub security: Option<Security>,
+
+    /// Named outbound HTTP clients.
+    pub http_clients: Option<HashMap<String, HttpClient>>,
+
+    /// Health endpoint configuration.
+    pub health: Option<Health>,
+
+    /// Management endpoints configuration.
+    pub actuator: Option<Actuator>,
 }
 
 impl Settings {
@@ -490,6 +1400,17 @@ impl Settings {
         config.try_deserialize::<Settings>()
     }
 
+    /// Returns the service name reported by the logs and the traces.
+    ///
+    /// The name is `tracing.service-name`, then `metrics.app-name`, then `api`.
+    pub fn service_name(&self) -> String {
+        self.tracing
+            .as_ref()
+            .and_then(|tracing| tracing.service_name.clone())
+            .or_else(|| self.metrics.as_ref().and_then(|m| m.app_name.clone()))
+            .unwrap_or_else(|| "api".into())
+    }
+
     /// Returns the OAuth2 configuration object if available.
     ///
     /// # Returns
@@ -501,6 +1422,9 @@ impl Settings {
 
     /// Returns a JWK (JSON Web Key) object if available for the given kid.
     ///
+    /// The key is looked up in the background-refreshed JWKS store when it is
+    /// initialized, and otherwise in the `jwks` keys declared in the configuration.
+    ///
     /// # Arguments
     ///
     /// * `kid` - A string slice that holds the key ID of the JWK object.
@@ -509,14 +1433,13 @@ impl Settings {
     ///
     /// * `Option<Jwk>` - The JWK object if present, `None` otherwise.
     pub fn get_auth2_public_key(&self, kid: &str) -> Option<Jwk> {
-        self.security
-            .as_ref()?
-            .oauth2
-            .as_ref()?
-            .jwks
-            .as_ref()?
-            .find(kid)
-            .cloned()
+        let configured = self
+            .security
+            .as_ref()
+            .and_then(|s| s.oauth2.as_ref())
+            .and_then(|oauth2| oauth2.jwks.as_ref());
+
+        crate::security::jwks::find_key(kid, configured)
     }
 
     /// Returns the OAuth2 token endpoint URL if available.
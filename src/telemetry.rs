@@ -0,0 +1,173 @@
+//! # Telemetry Module
+//!
+//! This module installs the OpenTelemetry trace pipeline configured by the `tracing`
+//! settings: the `tracing` spans of the application are exported over OTLP (gRPC or
+//! HTTP) to a collector, and the W3C Trace Context propagator is registered so the
+//! incoming `traceparent` headers are continued and forwarded by the outbound HTTP
+//! clients.
+//!
+//! The log output is not affected: the `tracing` events are still written by the
+//! process logger.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use opentelemetry::global;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use thiserror::Error;
+use tracing::level_filters::LevelFilter;
+use tracing::{Level, info, warn};
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::settings::Settings;
+
+/// Default OTLP endpoint of the `grpc` protocol.
+const DEFAULT_GRPC_ENDPOINT: &str = "http://localhost:4317";
+
+/// Default OTLP endpoint of the `http` protocol.
+const DEFAULT_HTTP_ENDPOINT: &str = "http://localhost:4318/v1/traces";
+
+/// Default export timeout (in milliseconds).
+const DEFAULT_TIMEOUT: u64 = 10000;
+
+/// Targets whose spans are never exported, to avoid tracing the exporter itself.
+const EXCLUDED_TARGETS: [&str; 6] = ["opentelemetry", "tonic", "h2", "hyper", "tower", "reqwest"];
+
+/// The installed tracer provider, flushed on shutdown.
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Returns `true` if the trace export is configured and enabled.
+pub(crate) fn is_enabled(settings: &Settings) -> bool {
+    settings
+        .tracing
+        .as_ref()
+        .is_some_and(|tracing| tracing.enabled.unwrap_or(true))
+}
+
+/// Installs the trace pipeline when enabled in the settings.
+///
+/// # Errors
+///
+/// Returns an error if the settings are invalid, the exporter cannot be created or
+/// a `tracing` subscriber is already installed.
+pub(crate) fn init(settings: &Settings) -> Result<()> {
+    let Some(config) = settings.tracing.as_ref().filter(|_| is_enabled(settings)) else {
+        return Ok(());
+    };
+
+    if PROVIDER.get().is_some() {
+        return Err(TelemetryError::AlreadyInstalled);
+    }
+
+    let ratio = config.sampling_ratio.unwrap_or(1.0);
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(TelemetryError::SamplingRatio(ratio));
+    }
+
+    let timeout = Duration::from_millis(config.timeout.unwrap_or(DEFAULT_TIMEOUT));
+    let exporter = match config.protocol.as_deref().unwrap_or("grpc") {
+        "grpc" => SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(config.endpoint.as_deref().unwrap_or(DEFAULT_GRPC_ENDPOINT))
+            .with_timeout(timeout)
+            .build(),
+        "http" => SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(config.endpoint.as_deref().unwrap_or(DEFAULT_HTTP_ENDPOINT))
+            .with_timeout(timeout)
+            .build(),
+        protocol => return Err(TelemetryError::Protocol(protocol.into())),
+    }
+    .map_err(|e| TelemetryError::Exporter(e.to_string()))?;
+
+    let service_name = settings.service_name();
+
+    // Continues the sampling decision of the caller, samples the new traces by ratio
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let targets = Targets::new()
+        .with_default(Level::INFO)
+        .with_targets(EXCLUDED_TARGETS.map(|target| (target, LevelFilter::OFF)));
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        .with_filter(targets);
+
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .map_err(|_| TelemetryError::AlreadyInstalled)?;
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+
+    info!("Exporting traces of {} over OTLP.", service_name);
+
+    Ok(())
+}
+
+/// Exports the pending spans and stops the trace pipeline, if installed.
+pub(crate) fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        warn!("Failed to shut down the trace exporter: {e}");
+    }
+}
+
+/// A type alias for a `Result` with the `TelemetryError` error type.
+pub type Result<T, E = TelemetryError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum TelemetryError {
+    #[error("Unsupported OTLP protocol: {0}. Expected grpc or http.")]
+    Protocol(String),
+
+    #[error("Invalid sampling ratio: {0}. Expected a value from 0.0 to 1.0.")]
+    SamplingRatio(f64),
+
+    #[error("Failed to create the OTLP exporter: {0}")]
+    Exporter(String),
+
+    #[error("A tracing subscriber is already installed.")]
+    AlreadyInstalled,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{TelemetryError, init};
+    use crate::settings::Settings;
+
+    #[test]
+    fn should_reject_invalid_tracing_settings() {
+        let settings = |tracing| -> Settings {
+            serde_json::from_value(json!({ "tracing": tracing })).unwrap_or_else(|e| panic!("{e}"))
+        };
+
+        assert!(matches!(
+            init(&settings(json!({ "protocol": "udp" }))),
+            Err(TelemetryError::Protocol(protocol)) if protocol == "udp"
+        ));
+        assert!(matches!(
+            init(&settings(json!({ "sampling-ratio": 1.5 }))),
+            Err(TelemetryError::SamplingRatio(_))
+        ));
+        assert!(init(&settings(json!({ "enabled": false }))).is_ok());
+    }
+}
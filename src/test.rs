@@ -248,11 +248,10 @@
 //! test environment for subsequent runs.
 //!
 use colored::Colorize;
-use env_logger::{Builder, Env};
 use std::any::Any;
 use std::collections::HashMap;
 use std::sync::mpsc::{self, Receiver, Sender};
-use std::{io::Write, sync::OnceLock, thread};
+use std::{sync::OnceLock, thread};
 use testcontainers::bollard::Docker;
 use testcontainers::bollard::query_parameters::{
     InspectContainerOptionsBuilder, RemoveContainerOptionsBuilder,
@@ -264,6 +263,7 @@ use tokio::task;
 use tracing::info;
 
 use crate::Server;
+use crate::logging;
 use crate::settings::Settings;
 
 pub type Result<T, E = TestError> = std::result::Result<T, E>;
@@ -564,38 +564,12 @@ where
 /// - Uses `RUST_LOG` environment variable when available.
 /// - Defaults to `info` level and suppresses noisy logs from `actix_web`
 ///   and `actix_web_prom`.
-/// - Applies colorized output based on the log level.
+/// - Applies colorized output based on the log level, when the standard error is
+///   a terminal.
 /// - Formats log entries with timestamp, level, module path, and message.
 fn configure_log() {
-    // Initialize Logger ENV
-    let level = Env::default().default_filter_or("info,actix_web=error,actix_web_prom=error");
-
-    let _ = Builder::from_env(level)
-        .format(|buf, record| {
-            let level = match record.level() {
-                log::Level::Info => record.level().as_str().bright_green(),
-                log::Level::Debug => record.level().as_str().bright_blue(),
-                log::Level::Trace => record.level().as_str().bright_cyan(),
-                log::Level::Warn => record.level().as_str().bright_yellow(),
-                log::Level::Error => record.level().as_str().bright_red(),
-            };
-
-            let datetime = chrono::Local::now()
-                .format("%d-%m-%YT%H:%M:%S%.3f%:z")
-                .to_string()
-                .white();
-
-            // Align timestamp, level, and module path
-            writeln!(
-                buf,
-                "{:<24}  {:<5} [{:<40}] - {}",
-                datetime,                                         // Timestamp
-                level,                                            // Log level
-                record.module_path().unwrap_or("unknown").blue(), // Module path
-                record.args()                                     // Log message
-            )
-        })
-        .try_init();
+    // An already installed logger is kept
+    let _ = logging::init(40);
 }
 
 pub mod containers {
@@ -783,3 +757,60 @@ pub mod containers {
         Ok((container, uri))
     }
 }
+
+/// Local HTTP servers standing in for the identity provider and the remote services
+/// in the unit tests.
+#[cfg(test)]
+pub(crate) mod mock {
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{Server, ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{App, HttpServer};
+
+    /// Binds a single-worker server built by `factory` to a random local port.
+    ///
+    /// # Returns
+    ///
+    /// The base URI of the server (e.g. `http://127.0.0.1:41234`) and the server
+    /// itself, which must be awaited or spawned to answer requests.
+    pub(crate) fn bind<F, T, B>(factory: F) -> (String, Server)
+    where
+        F: Fn() -> App<T> + Send + Clone + 'static,
+        T: ServiceFactory<
+                ServiceRequest,
+                Config = (),
+                Response = ServiceResponse<B>,
+                Error = actix_web::Error,
+                InitError = (),
+            > + 'static,
+        B: MessageBody + 'static,
+    {
+        let server = HttpServer::new(factory)
+            .workers(1)
+            .disable_signals()
+            .bind(("127.0.0.1", 0))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let uri = format!("http://{}", server.addrs()[0]);
+
+        (uri, server.run())
+    }
+
+    /// Starts a server built by `factory` on a random local port and returns its
+    /// base URI.
+    pub(crate) fn serve<F, T, B>(factory: F) -> String
+    where
+        F: Fn() -> App<T> + Send + Clone + 'static,
+        T: ServiceFactory<
+                ServiceRequest,
+                Config = (),
+                Response = ServiceResponse<B>,
+                Error = actix_web::Error,
+                InitError = (),
+            > + 'static,
+        B: MessageBody + 'static,
+    {
+        let (uri, server) = bind(factory);
+        actix_web::rt::spawn(server);
+
+        uri
+    }
+}